@@ -0,0 +1,216 @@
+use crate::eval::*;
+use crate::parse::*;
+
+fn not_a_char(name: &str, v: &Sexpr) -> Unwind {
+    Unwind::from(format!(
+        "{} - {} is not a character",
+        name,
+        sexpr_to_string(v)
+    ))
+}
+
+fn one_char(name: &str, args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<char, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [Sexpr::Char(c)] => Ok(*c),
+        [x] => Err(not_a_char(name, x)),
+        _ => Err(Unwind::from(format!("{} - takes exactly one arg", name))),
+    }
+}
+
+pub fn char_code(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    Ok(Sexpr::Integer(one_char("char-code", args, alist)? as i64))
+}
+
+// NIL for codes that aren't chars, surrogates and anything past U+10FFFF
+pub fn code_char(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [Sexpr::Integer(code)] => Ok(u32::try_from(*code)
+            .ok()
+            .and_then(char::from_u32)
+            .map(Sexpr::Char)
+            .unwrap_or(Sexpr::Nil)),
+        [Sexpr::Bignum(_)] => Ok(Sexpr::Nil),
+        [x] => Err(Unwind::from(format!(
+            "code-char - {} is not an integer",
+            sexpr_to_string(x)
+        ))),
+        _ => Err(Unwind::from("code-char - takes exactly one arg")),
+    }
+}
+
+// chars whose case takes more than one char, like ß, are left as they are
+fn change_case<I: Iterator<Item = char>>(c: char, mut changed: I) -> char {
+    match (changed.next(), changed.next()) {
+        (Some(single), None) => single,
+        _ => c,
+    }
+}
+
+pub fn char_upcase(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let c: char = one_char("char-upcase", args, alist)?;
+    Ok(Sexpr::Char(change_case(c, c.to_uppercase())))
+}
+
+pub fn char_downcase(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let c: char = one_char("char-downcase", args, alist)?;
+    Ok(Sexpr::Char(change_case(c, c.to_lowercase())))
+}
+
+pub fn alpha_char_p(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    Ok(bool_sexpr(
+        one_char("alpha-char-p", args, alist)?.is_alphabetic(),
+    ))
+}
+
+// (digit-char-p c [radix]) is the weight of c as a digit, or NIL
+pub fn digit_char_p(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (c, radix): (char, i64) = match eval_args(args, alist)?.as_slice() {
+        [Sexpr::Char(c)] => (*c, 10),
+        [Sexpr::Char(c), Sexpr::Integer(radix)] => (*c, *radix),
+        [Sexpr::Char(_), radix] => {
+            return Err(Unwind::from(format!(
+                "digit-char-p - {} is not a radix",
+                sexpr_to_string(radix)
+            )))
+        }
+        [x, ..] => return Err(not_a_char("digit-char-p", x)),
+        _ => {
+            return Err(Unwind::from(
+                "digit-char-p - takes a char and an optional radix",
+            ))
+        }
+    };
+    if !(2..=36).contains(&radix) {
+        return Err(Unwind::from(format!(
+            "digit-char-p - {} is not a radix from 2 to 36",
+            radix
+        )));
+    }
+    Ok(match c.to_digit(radix as u32) {
+        Some(d) => Sexpr::Integer(d as i64),
+        None => Sexpr::Nil,
+    })
+}
+
+// (char< a b c) holds when every neighbouring pair does, by char code
+fn char_chain(
+    name: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+    holds: fn(&char, &char) -> bool,
+) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let chars: Vec<char> = args
+        .iter()
+        .map(|c| match c {
+            Sexpr::Char(c) => Ok(*c),
+            x => Err(not_a_char(name, x)),
+        })
+        .collect::<Result<_, _>>()?;
+    if chars.is_empty() {
+        return Err(Unwind::from(format!("{} - needs at least one arg", name)));
+    }
+    Ok(bool_sexpr(chars.windows(2).all(|w| holds(&w[0], &w[1]))))
+}
+
+pub fn char_equal(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    char_chain("char=", args, alist, char::eq)
+}
+
+pub fn char_less(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    char_chain("char<", args, alist, char::lt)
+}
+
+pub fn char_greater(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    char_chain("char>", args, alist, char::gt)
+}
+
+pub fn char_less_equal(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    char_chain("char<=", args, alist, char::le)
+}
+
+pub fn char_greater_equal(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    char_chain("char>=", args, alist, char::ge)
+}
+
+// (char/= a b c) holds when no two args are the same char
+pub fn char_not_equal(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    if let Some(x) = args.iter().find(|c| !matches!(c, Sexpr::Char(_))) {
+        return Err(not_a_char("char/=", x));
+    }
+    if args.is_empty() {
+        return Err(Unwind::from("char/= - needs at least one arg"));
+    }
+    Ok(bool_sexpr(
+        args.iter()
+            .enumerate()
+            .all(|(i, a)| !args[i + 1..].contains(a)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+
+    #[test]
+    fn test_char_syntax() {
+        assert_eq!(run("#\\a"), Ok(Sexpr::Char('a')));
+        assert_eq!(run("#\\A"), Ok(Sexpr::Char('A')));
+        assert_eq!(run("#\\Space"), Ok(Sexpr::Char(' ')));
+        assert_eq!(run("#\\newline"), Ok(Sexpr::Char('\n')));
+        assert_eq!(run("#\\u+03BB"), Ok(Sexpr::Char('λ')));
+        assert_eq!(run("#\\("), Ok(Sexpr::Char('(')));
+        assert_eq!(
+            run("'(#\\) #\\x)"),
+            Ok(Sexpr::List(vec![
+                Sexpr::Char(')'),
+                Sexpr::Char('x'),
+                Sexpr::Nil
+            ]))
+        );
+        assert_eq!(
+            run("#\\Bogus"),
+            Err(Unwind::from("tokenize - unknown character name #\\Bogus"))
+        );
+
+        assert_eq!(sexpr_to_string(&Sexpr::Char('a')), "#\\a");
+        assert_eq!(sexpr_to_string(&Sexpr::Char(' ')), "#\\Space");
+        assert_eq!(sexpr_to_string(&Sexpr::Char('λ')), "#\\λ");
+        assert_eq!(sexpr_to_string(&Sexpr::Char('\u{7}')), "#\\U+0007");
+    }
+
+    #[test]
+    fn test_char_functions() {
+        assert_eq!(run("(char-code #\\A)"), Ok(Sexpr::Integer(65)));
+        assert_eq!(run("(code-char 955)"), Ok(Sexpr::Char('λ')));
+        assert_eq!(run("(code-char 55296)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(char-upcase #\\a)"), Ok(Sexpr::Char('A')));
+        assert_eq!(run("(char-upcase #\\ß)"), Ok(Sexpr::Char('ß')));
+        assert_eq!(run("(char-downcase #\\Λ)"), Ok(Sexpr::Char('λ')));
+        assert_eq!(run("(alpha-char-p #\\λ)"), Ok(Sexpr::T));
+        assert_eq!(run("(alpha-char-p #\\1)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(digit-char-p #\\7)"), Ok(Sexpr::Integer(7)));
+        assert_eq!(run("(digit-char-p #\\f 16)"), Ok(Sexpr::Integer(15)));
+        assert_eq!(run("(digit-char-p #\\a)"), Ok(Sexpr::Nil));
+        assert_eq!(
+            run("(char-code \"a\")"),
+            Err(Unwind::from("char-code - \"a\" is not a character"))
+        );
+    }
+
+    #[test]
+    fn test_char_comparisons() {
+        assert_eq!(run("(char= #\\a #\\a #\\a)"), Ok(Sexpr::T));
+        assert_eq!(run("(char= #\\a #\\A)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(char< #\\a #\\b #\\c)"), Ok(Sexpr::T));
+        assert_eq!(run("(char< #\\a #\\c #\\b)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(char>= #\\b #\\b #\\a)"), Ok(Sexpr::T));
+        assert_eq!(run("(char/= #\\a #\\b #\\a)"), Ok(Sexpr::Nil));
+        assert_eq!(
+            run("(char< #\\a 1)"),
+            Err(Unwind::from("char< - 1 is not a character"))
+        );
+    }
+}
@@ -0,0 +1,628 @@
+use crate::eval::*;
+use crate::lisp::*;
+use crate::parse::*;
+use crate::streams::*;
+
+use once_cell::sync::Lazy;
+use std::cell::{Cell, RefCell};
+use std::sync::Mutex;
+
+// (type, parent) for every condition type, DEFINE-CONDITION adds to it
+pub static CONDITION_TYPES: Lazy<Mutex<Vec<(String, String)>>> = Lazy::new(|| {
+    let types = [
+        ("SERIOUS-CONDITION", "CONDITION"),
+        ("SIMPLE-CONDITION", "CONDITION"),
+        ("WARNING", "CONDITION"),
+        ("SIMPLE-WARNING", "WARNING"),
+        ("ERROR", "SERIOUS-CONDITION"),
+        ("SIMPLE-ERROR", "ERROR"),
+        ("TYPE-ERROR", "ERROR"),
+        ("PROGRAM-ERROR", "ERROR"),
+        ("CONTROL-ERROR", "ERROR"),
+        ("ARITHMETIC-ERROR", "ERROR"),
+        ("DIVISION-BY-ZERO", "ARITHMETIC-ERROR"),
+        ("FLOATING-POINT-OVERFLOW", "ARITHMETIC-ERROR"),
+        ("STREAM-ERROR", "ERROR"),
+        ("END-OF-FILE", "STREAM-ERROR"),
+        ("FILE-ERROR", "ERROR"),
+        ("PARSE-ERROR", "ERROR"),
+    ];
+    Mutex::new(
+        types
+            .iter()
+            .map(|(t, p)| (String::from(*t), String::from(*p)))
+            .collect(),
+    )
+});
+
+// called with an unhandled error while its restarts are still active, the
+// REPL installs one to let the user pick a restart
+pub static DEBUGGER_HOOK: Lazy<Mutex<Option<DebuggerHook>>> = Lazy::new(|| Mutex::new(None));
+pub type DebuggerHook = fn(Sexpr, &mut [(String, Sexpr)]) -> Unwind;
+
+#[derive(Clone)]
+enum Handler {
+    // the id of the handler-case and the index of its clause
+    Case(usize, usize, String),
+    // a handler-bind function, it declines by returning normally
+    Bind(String, Sexpr),
+}
+
+#[derive(Clone, Debug)]
+pub struct Restart {
+    pub id: usize,
+    pub name: String,
+    pub params: Sexpr,
+}
+
+// handlers and restarts are dynamic, so they are only visible to the
+// evaluation running on the same thread
+thread_local! {
+    static HANDLERS: RefCell<Vec<Handler>> = const { RefCell::new(Vec::new()) };
+    static RESTARTS: RefCell<Vec<Restart>> = const { RefCell::new(Vec::new()) };
+    static NEXT_ID: Cell<usize> = const { Cell::new(0) };
+}
+
+fn next_id() -> usize {
+    NEXT_ID.with(|id| {
+        id.set(id.get() + 1);
+        id.get()
+    })
+}
+
+pub fn active_restarts() -> Vec<Restart> {
+    RESTARTS.with(|r| r.borrow().clone())
+}
+
+// true if signaling has to happen where the error is raised instead of at the top
+pub fn signal_needed() -> bool {
+    HANDLERS.with(|h| !h.borrow().is_empty())
+        || RESTARTS.with(|r| !r.borrow().is_empty())
+        || DEBUGGER_HOOK.lock().unwrap().is_some()
+}
+
+pub fn make_condition(ctype: &str, message: String) -> Sexpr {
+    Sexpr::Condition(String::from(ctype), Box::new(Sexpr::String(message)))
+}
+
+// the message of a condition, a string message as it is
+pub fn condition_message(c: &Sexpr) -> String {
+    match c {
+        Sexpr::Condition(_, data) => match data.as_ref() {
+            Sexpr::String(s) => s.clone(),
+            d => sexpr_to_string(d),
+        },
+        c => sexpr_to_string(c),
+    }
+}
+
+pub fn subtypep(ctype: &str, target: &str) -> bool {
+    let types = CONDITION_TYPES.lock().unwrap();
+    let mut t: String = String::from(ctype);
+    loop {
+        if t == target || target == "CONDITION" || target == "T" {
+            return true;
+        }
+        match types.iter().find(|(child, _)| *child == t) {
+            Some((_, parent)) => t = parent.clone(),
+            // an undefined type is only itself, a CONDITION and a T
+            None => return false,
+        }
+    }
+}
+
+// runs the handlers from the innermost out, the first one that exits decides
+// what happens, Ok means every handler declined
+fn signal_condition(c: &Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<(), Unwind> {
+    let ctype: String = match c {
+        Sexpr::Condition(t, _) => t.clone(),
+        _ => return Err(Unwind::from("signal - arg must be a condition")),
+    };
+
+    let handlers: Vec<Handler> = HANDLERS.with(|h| h.borrow().clone());
+    for (i, handler) in handlers.iter().enumerate().rev() {
+        match handler {
+            Handler::Case(id, clause, t) if subtypep(&ctype, t) => {
+                return Err(Unwind::HandlerCase(*id, *clause, c.clone()));
+            }
+            Handler::Bind(t, f) if subtypep(&ctype, t) => {
+                // a handler runs with only the handlers outside of it in effect
+                let saved: Vec<Handler> = HANDLERS.with(|h| h.replace(handlers[..i].to_vec()));
+                let result = call_function(f.clone(), vec![c.clone()], alist);
+                HANDLERS.with(|h| h.replace(saved));
+                result?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// signals c as an error, when nobody handles it the debugger gets a chance
+// to pick a restart before the error unwinds to the top
+pub fn signal_error(c: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Unwind {
+    if let Err(e) = signal_condition(&c, alist) {
+        return e;
+    }
+
+    let hook: Option<DebuggerHook> = *DEBUGGER_HOOK.lock().unwrap();
+    match hook {
+        Some(hook) => hook(c, alist),
+        None => Unwind::Condition(c),
+    }
+}
+
+// builtins report errors as strings, they become SIMPLE-ERRORs the first time
+// they pass through evaluate so that handlers still see them before unwinding
+pub fn signal_unwind(e: Unwind, alist: &mut Vec<(String, Sexpr)>) -> Unwind {
+    match e {
+        Unwind::Error(s) if signal_needed() => {
+            signal_error(make_condition("SIMPLE-ERROR", s), alist)
+        }
+        e => e,
+    }
+}
+
+// replaces ~a and ~s with the args in order, ~% with a newline
+fn format_message(control: &str, args: &[Sexpr]) -> String {
+    let mut message = String::new();
+    let mut args = args.iter();
+    let mut chars = control.chars();
+    while let Some(c) = chars.next() {
+        if c != '~' {
+            message.push(c);
+            continue;
+        }
+        match chars.next().map(|d| d.to_ascii_uppercase()) {
+            // ~a shows strings without their quotes, like princ
+            Some('A') => {
+                if let Some(a) = args.next() {
+                    message += &princ_to_string(a, 10);
+                }
+            }
+            Some('S') => {
+                if let Some(a) = args.next() {
+                    message += &sexpr_to_string(a);
+                }
+            }
+            Some('%') => message.push('\n'),
+            Some(d) => message.push(d),
+            None => message.push('~'),
+        }
+    }
+    message
+}
+
+// (error "control" args...), (error 'type :key value...) or (error condition)
+fn build_condition(name: &str, default: &str, args: Vec<Sexpr>) -> Result<Sexpr, Unwind> {
+    match args.first() {
+        Some(Sexpr::String(s)) => {
+            let message = format_message(s, &args[1..]);
+            Ok(make_condition(default, message))
+        }
+        Some(Sexpr::Symbol(s)) => {
+            let mut data: Vec<Sexpr> = args[1..].to_vec();
+            data.push(Sexpr::Nil);
+            let data = if data.len() == 1 {
+                Sexpr::Nil
+            } else {
+                Sexpr::List(data)
+            };
+            Ok(Sexpr::Condition(s.clone(), Box::new(data)))
+        }
+        Some(c @ Sexpr::Condition(_, _)) => Ok(c.clone()),
+        _ => Err(Unwind::from(format!(
+            "{} - arg must be a string, symbol or condition",
+            name
+        ))),
+    }
+}
+
+pub fn make_condition_fn(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    match args.first() {
+        Some(Sexpr::Symbol(_)) => build_condition("make-condition", "SIMPLE-CONDITION", args),
+        _ => Err(Unwind::from("make-condition - type must be a symbol")),
+    }
+}
+
+pub fn error(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let c: Sexpr = build_condition("error", "SIMPLE-ERROR", args)?;
+    Err(signal_error(c, alist))
+}
+
+// like error but returns NIL when no handler takes the condition
+pub fn signal(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let c: Sexpr = build_condition("signal", "SIMPLE-CONDITION", args)?;
+    signal_condition(&c, alist)?;
+    Ok(Sexpr::Nil)
+}
+
+pub fn condition_type(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.first() {
+        Some(Sexpr::Condition(t, _)) => Ok(Sexpr::Symbol(t.clone())),
+        _ => Err(Unwind::from("condition-type - arg must be a condition")),
+    }
+}
+
+pub fn condition_data(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.first() {
+        Some(Sexpr::Condition(_, data)) => Ok(data.as_ref().clone()),
+        _ => Err(Unwind::from("condition-data - arg must be a condition")),
+    }
+}
+
+pub fn condition_message_fn(
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.first() {
+        Some(c @ Sexpr::Condition(_, _)) => Ok(Sexpr::String(condition_message(c))),
+        _ => Err(Unwind::from("condition-message - arg must be a condition")),
+    }
+}
+
+// (define-condition name (parent) slots...), only the parent is kept
+pub fn define_condition(args: Sexpr) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("define-condition - args must be a list")),
+    };
+
+    let name: String = match args.first() {
+        Some(Sexpr::Symbol(s)) => s.clone(),
+        _ => return Err(Unwind::from("define-condition - name must be a symbol")),
+    };
+    let parent: String = match args.get(1) {
+        Some(Sexpr::List(l)) => match list_items(l).first() {
+            Some(Sexpr::Symbol(s)) => s.clone(),
+            _ => String::from("CONDITION"),
+        },
+        Some(Sexpr::Nil) | None => String::from("CONDITION"),
+        Some(_) => return Err(Unwind::from("define-condition - parents must be a list")),
+    };
+
+    let mut types = CONDITION_TYPES.lock().unwrap();
+    types.retain(|(t, _)| *t != name);
+    types.push((name.clone(), parent));
+    Ok(Sexpr::Symbol(name))
+}
+
+// the type symbol and the rest of a (type ...) clause
+fn clause_parts(name: &str, clause: &Sexpr) -> Result<(String, Vec<Sexpr>), Unwind> {
+    let clause: &[Sexpr] = match clause {
+        Sexpr::List(l) => list_items(l),
+        _ => {
+            return Err(Unwind::from(format!(
+                "{} - each clause must be a list",
+                name
+            )))
+        }
+    };
+    match clause.first() {
+        Some(Sexpr::Symbol(s)) => Ok((s.clone(), clause[1..].to_vec())),
+        Some(Sexpr::T) => Ok((String::from("T"), clause[1..].to_vec())),
+        _ => Err(Unwind::from(format!(
+            "{} - clause must start with a type",
+            name
+        ))),
+    }
+}
+
+// (handler-case form (type (var) body...)...)
+pub fn handler_case(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("handler-case - args must be a list")),
+    };
+    let form: Sexpr = match args.first() {
+        Some(s) => s.clone(),
+        None => return Err(Unwind::from("handler-case - no form arg")),
+    };
+
+    let mut clauses: Vec<(String, Vec<Sexpr>)> = Vec::new();
+    for clause in &args[1..] {
+        clauses.push(clause_parts("handler-case", clause)?);
+    }
+
+    let id = next_id();
+    let depth = HANDLERS.with(|h| h.borrow().len());
+    HANDLERS.with(|h| {
+        let mut h = h.borrow_mut();
+        // the first clause has to be the innermost handler
+        for (i, (t, _)) in clauses.iter().enumerate().rev() {
+            h.push(Handler::Case(id, i, t.clone()));
+        }
+    });
+    let result = evaluate(form, alist);
+    HANDLERS.with(|h| h.borrow_mut().truncate(depth));
+
+    match result {
+        Err(Unwind::HandlerCase(case, clause, c)) if case == id => {
+            let (_, rest) = &clauses[clause];
+            // the clause is a lambda taking the condition, or nothing
+            let params: Sexpr = rest.first().cloned().unwrap_or(Sexpr::Nil);
+            let mut lambda: Vec<Sexpr> = vec![params.clone()];
+            lambda.extend(rest.iter().skip(1).cloned());
+            lambda.push(Sexpr::Nil);
+            let args: Vec<Sexpr> = match &params {
+                Sexpr::List(l) if !list_items(l).is_empty() => vec![c],
+                _ => Vec::new(),
+            };
+            call_function(Sexpr::Lambda(String::from("LAMBDA"), lambda), args, alist)
+        }
+        r => r,
+    }
+}
+
+// (ignore-errors body...) returns NIL instead of signaling past it
+pub fn ignore_errors(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let forms: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("ignore-errors - args must be a list")),
+    };
+
+    let id = next_id();
+    let depth = HANDLERS.with(|h| h.borrow().len());
+    HANDLERS.with(|h| {
+        h.borrow_mut()
+            .push(Handler::Case(id, 0, String::from("ERROR")))
+    });
+    let result = progn_forms(&forms, alist);
+    HANDLERS.with(|h| h.borrow_mut().truncate(depth));
+
+    match result {
+        Err(Unwind::HandlerCase(case, _, _)) if case == id => Ok(Sexpr::Nil),
+        r => r,
+    }
+}
+
+// (handler-bind ((type handler)...) body...)
+pub fn handler_bind(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("handler-bind - args must be a list")),
+    };
+    let bindings: Vec<Sexpr> = match args.first() {
+        Some(Sexpr::List(l)) => list_items(l).to_vec(),
+        Some(Sexpr::Nil) => Vec::new(),
+        _ => {
+            return Err(Unwind::from(
+                "handler-bind - first arg must be a list of bindings",
+            ))
+        }
+    };
+
+    let mut handlers: Vec<Handler> = Vec::new();
+    for binding in &bindings {
+        let (t, rest) = clause_parts("handler-bind", binding)?;
+        let f: Sexpr = match rest.first() {
+            Some(s) => evaluate(s.clone(), alist)?,
+            None => return Err(Unwind::from("handler-bind - binding has no handler")),
+        };
+        handlers.push(Handler::Bind(t, f));
+    }
+
+    let depth = HANDLERS.with(|h| h.borrow().len());
+    HANDLERS.with(|h| h.borrow_mut().extend(handlers.into_iter().rev()));
+    let result = progn_forms(&args[1..], alist);
+    HANDLERS.with(|h| h.borrow_mut().truncate(depth));
+    result
+}
+
+// (restart-case form (name (params) body...)...)
+pub fn restart_case(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("restart-case - args must be a list")),
+    };
+    let form: Sexpr = match args.first() {
+        Some(s) => s.clone(),
+        None => return Err(Unwind::from("restart-case - no form arg")),
+    };
+
+    let mut restarts: Vec<(Restart, Vec<Sexpr>)> = Vec::new();
+    for clause in &args[1..] {
+        let (name, rest) = clause_parts("restart-case", clause)?;
+        let params: Sexpr = rest.first().cloned().unwrap_or(Sexpr::Nil);
+        let restart = Restart {
+            id: next_id(),
+            name,
+            params,
+        };
+        restarts.push((restart, rest.iter().skip(1).cloned().collect()));
+    }
+
+    let depth = RESTARTS.with(|r| r.borrow().len());
+    RESTARTS.with(|r| {
+        r.borrow_mut()
+            .extend(restarts.iter().rev().map(|(restart, _)| restart.clone()))
+    });
+    let result = evaluate(form, alist);
+    RESTARTS.with(|r| r.borrow_mut().truncate(depth));
+
+    match result {
+        Err(Unwind::Restart(id, values)) => match restarts.into_iter().find(|(r, _)| r.id == id) {
+            Some((restart, body)) => {
+                let mut lambda: Vec<Sexpr> = vec![restart.params];
+                lambda.extend(body);
+                lambda.push(Sexpr::Nil);
+                call_function(Sexpr::Lambda(restart.name, lambda), values, alist)
+            }
+            None => Err(Unwind::Restart(id, values)),
+        },
+        r => r,
+    }
+}
+
+// (invoke-restart 'name args...) transfers to the innermost restart with that name
+pub fn invoke_restart(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let mut args: Vec<Sexpr> = eval_args(args, alist)?;
+    if args.is_empty() {
+        return Err(Unwind::from("invoke-restart - no restart name arg"));
+    }
+    let name: String = match args.remove(0) {
+        Sexpr::Symbol(s) => s,
+        _ => {
+            return Err(Unwind::from(
+                "invoke-restart - restart name must be a symbol",
+            ))
+        }
+    };
+
+    match active_restarts().iter().rev().find(|r| r.name == name) {
+        Some(r) => Err(Unwind::Restart(r.id, args)),
+        None => Err(Unwind::from(format!(
+            "invoke-restart - no restart named {} is active",
+            name
+        ))),
+    }
+}
+
+pub fn compute_restarts(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    eval_args(args, alist)?;
+    let mut names: Vec<Sexpr> = active_restarts()
+        .iter()
+        .rev()
+        .map(|r| Sexpr::Symbol(r.name.clone()))
+        .collect();
+    if names.is_empty() {
+        return Ok(Sexpr::Nil);
+    }
+    names.push(Sexpr::Nil);
+    Ok(Sexpr::List(names))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+
+    #[test]
+    fn test_subtypep() {
+        assert!(subtypep("DIVISION-BY-ZERO", "ERROR"));
+        assert!(subtypep("SIMPLE-ERROR", "CONDITION"));
+        assert!(!subtypep("SIMPLE-WARNING", "ERROR"));
+        assert!(!subtypep("ERROR", "SIMPLE-ERROR"));
+
+        // undefined types are only themselves
+        assert!(subtypep("NEVER-DEFINED", "NEVER-DEFINED"));
+        assert!(subtypep("NEVER-DEFINED", "CONDITION"));
+        assert!(subtypep("NEVER-DEFINED", "T"));
+        assert!(!subtypep("NEVER-DEFINED", "ERROR"));
+    }
+
+    #[test]
+    fn test_format_message() {
+        let args = vec![Sexpr::Integer(1), Sexpr::Symbol(String::from("X"))];
+        assert_eq!(format_message("got-~a-and-~S~%", &args), "got-1-and-X\n");
+        assert_eq!(format_message("100~~", &[]), "100~");
+    }
+
+    #[test]
+    fn test_error() {
+        assert_eq!(
+            run("(error \"oops\")"),
+            Err(Unwind::Condition(make_condition(
+                "SIMPLE-ERROR",
+                String::from("oops")
+            )))
+        );
+        assert_eq!(
+            run("(error 'error-test :code 42)"),
+            Err(Unwind::Condition(Sexpr::Condition(
+                String::from("ERROR-TEST"),
+                Box::new(Sexpr::List(vec![
+                    Sexpr::Symbol(String::from(":CODE")),
+                    Sexpr::Integer(42),
+                    Sexpr::Nil
+                ]))
+            )))
+        );
+        assert_eq!(run("(signal \"ignored\")"), Ok(Sexpr::Nil));
+    }
+
+    #[test]
+    fn test_handler_case() {
+        assert_eq!(
+            run("(handler-case (error \"oops\") (error (c) (condition-message c)))"),
+            Ok(Sexpr::String(String::from("oops")))
+        );
+        // builtin errors are SIMPLE-ERRORs
+        assert_eq!(
+            run("(handler-case (car 1) (simple-error () 'caught))"),
+            Ok(Sexpr::Symbol(String::from("CAUGHT")))
+        );
+        // the first clause that matches the type wins
+        run("(define-condition handler-case-error (error))").unwrap();
+        assert_eq!(
+            run("(handler-case (error 'handler-case-error) (warning () 'warning) (handler-case-error (c) (condition-type c)) (error () 'error))"),
+            Ok(Sexpr::Symbol(String::from("HANDLER-CASE-ERROR")))
+        );
+        assert_eq!(
+            run("(handler-case (signal 'handler-case-warning) (error () 'error))"),
+            Ok(Sexpr::Nil)
+        );
+        assert_eq!(
+            run("(handler-case (signal 'handler-case-warning) (error () 'error) (condition () 'condition))"),
+            Ok(Sexpr::Symbol(String::from("CONDITION")))
+        );
+        assert_eq!(
+            run("(handler-case (+ 1 2) (error () 'error))"),
+            Ok(Sexpr::Integer(3))
+        );
+    }
+
+    #[test]
+    fn test_ignore_errors() {
+        assert_eq!(run("(ignore-errors (car 1))"), Ok(Sexpr::Nil));
+        assert_eq!(run("(ignore-errors 1 2)"), Ok(Sexpr::Integer(2)));
+        // non-local exits are not errors
+        assert_eq!(
+            run("(catch 'tag (ignore-errors (throw 'tag 1)))"),
+            Ok(Sexpr::Integer(1))
+        );
+    }
+
+    #[test]
+    fn test_handler_bind() {
+        // a handler that returns declines and the error keeps going
+        assert_eq!(
+            run("(handler-case (handler-bind ((error (lambda (c) (setq handler-bind-seen (condition-type c))))) (error \"oops\")) (error () handler-bind-seen))"),
+            Ok(Sexpr::Symbol(String::from("SIMPLE-ERROR")))
+        );
+        // a handler that exits takes over
+        assert_eq!(
+            run("(catch 'out (handler-bind ((error (lambda (c) (throw 'out 'handled)))) (car 1)))"),
+            Ok(Sexpr::Symbol(String::from("HANDLED")))
+        );
+    }
+
+    #[test]
+    fn test_restart_case() {
+        assert_eq!(
+            run("(handler-bind ((error (lambda (c) (invoke-restart 'use-value 7)))) (+ 1 (restart-case (error \"oops\") (use-value (v) v))))"),
+            Ok(Sexpr::Integer(8))
+        );
+        assert_eq!(
+            run("(restart-case (invoke-restart 'skip) (retry () 'retry) (skip () 'skipped))"),
+            Ok(Sexpr::Symbol(String::from("SKIPPED")))
+        );
+        assert_eq!(
+            run("(restart-case (compute-restarts) (outer () 1) (inner () 2))"),
+            Ok(Sexpr::List(vec![
+                Sexpr::Symbol(String::from("OUTER")),
+                Sexpr::Symbol(String::from("INNER")),
+                Sexpr::Nil
+            ]))
+        );
+        assert_eq!(
+            run("(invoke-restart 'nowhere)"),
+            Err(Unwind::from(
+                "invoke-restart - no restart named NOWHERE is active"
+            ))
+        );
+    }
+}
@@ -0,0 +1,542 @@
+use crate::condition::*;
+use crate::eval::*;
+use crate::lexer::*;
+use crate::lists::*;
+use crate::parse::*;
+use crate::shell::*;
+use crate::streams::*;
+
+use once_cell::sync::Lazy;
+use std::cell::{Cell, RefCell};
+use std::sync::Mutex;
+
+// a call through func, the bindings it made start at depth in the alist
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub name: String,
+    pub args: Vec<Sexpr>,
+    pub depth: usize,
+}
+
+// user functions that print their calls, like definitions these are global
+pub static TRACED: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+thread_local! {
+    static FRAMES: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+    // how many break loops are running, errors inside one open another
+    static LEVEL: Cell<usize> = const { Cell::new(0) };
+    // how many traced calls are running, for the indentation
+    static TRACE_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+// returns the index of the frame, which func hands back to pop_frame
+pub fn push_frame(name: &str, args: &[Sexpr], depth: usize) -> usize {
+    FRAMES.with(|f| {
+        let mut f = f.borrow_mut();
+        f.push(Frame {
+            name: String::from(name),
+            args: args.to_vec(),
+            depth,
+        });
+        f.len() - 1
+    })
+}
+
+pub fn pop_frame(index: usize) {
+    FRAMES.with(|f| f.borrow_mut().truncate(index));
+}
+
+pub fn frames() -> Vec<Frame> {
+    FRAMES.with(|f| f.borrow().clone())
+}
+
+fn trace_names(args: &Sexpr) -> Result<Vec<String>, Unwind> {
+    let args: &[Sexpr] = match args {
+        Sexpr::List(l) => list_items(l),
+        _ => return Ok(Vec::new()),
+    };
+    let mut names: Vec<String> = Vec::new();
+    for a in args {
+        match a {
+            Sexpr::Symbol(s) if BUILTINS.contains(&s.as_str()) || is_cxr(s) => {
+                return Err(Unwind::from(format!(
+                    "trace - {} is a builtin, only user functions can be traced",
+                    s
+                )))
+            }
+            Sexpr::Symbol(s) => names.push(s.clone()),
+            _ => return Err(Unwind::from("trace - function names must be symbols")),
+        }
+    }
+    Ok(names)
+}
+
+fn names_to_list(names: &[String]) -> Sexpr {
+    if names.is_empty() {
+        return Sexpr::Nil;
+    }
+    let mut l: Vec<Sexpr> = names.iter().map(|n| Sexpr::Symbol(n.clone())).collect();
+    l.push(Sexpr::Nil);
+    Sexpr::List(l)
+}
+
+// (trace f...) starts tracing the functions, (trace) lists the traced ones
+pub fn trace(args: Sexpr) -> Result<Sexpr, Unwind> {
+    let names: Vec<String> = trace_names(&args)?;
+    let mut traced = TRACED.lock().unwrap();
+    if names.is_empty() {
+        return Ok(names_to_list(&traced));
+    }
+    for name in &names {
+        if !traced.contains(name) {
+            traced.push(name.clone());
+        }
+    }
+    Ok(names_to_list(&names))
+}
+
+// (untrace f...) stops tracing the functions, (untrace) stops tracing all of them
+pub fn untrace(args: Sexpr) -> Result<Sexpr, Unwind> {
+    let names: Vec<String> = trace_names(&args)?;
+    let mut traced = TRACED.lock().unwrap();
+    let untraced: Vec<String> = if names.is_empty() {
+        traced.drain(..).collect()
+    } else {
+        traced.retain(|t| !names.contains(t));
+        names
+    };
+    Ok(names_to_list(&untraced))
+}
+
+fn trace_entry(depth: usize, name: &str, args: &[Sexpr]) -> String {
+    let mut call: Vec<Sexpr> = vec![Sexpr::Symbol(String::from(name))];
+    call.extend(args.iter().cloned());
+    call.push(Sexpr::Nil);
+    format!(
+        "{}{}: {}",
+        "  ".repeat(depth + 1),
+        depth,
+        sexpr_to_string(&Sexpr::List(call))
+    )
+}
+
+fn trace_exit(depth: usize, name: &str, result: &Result<Sexpr, Unwind>) -> String {
+    let outcome: String = match result {
+        Ok(v) => format!("returned {}", sexpr_to_string(v)),
+        Err(_) => String::from("unwound"),
+    };
+    format!("{}{}: {} {}", "  ".repeat(depth + 1), depth, name, outcome)
+}
+
+// prints the entry line of a call through func if name is traced, true if it was,
+// a stream that can't be written to doesn't change what the call does
+pub fn trace_call(name: &str, args: &[Sexpr], alist: &[(String, Sexpr)]) -> bool {
+    if !TRACED.lock().unwrap().iter().any(|t| t == name) {
+        return false;
+    }
+    let depth: usize = TRACE_DEPTH.with(|d| d.get());
+    let _ = write_standard_output("trace", &(trace_entry(depth, name, args) + "\n"), alist);
+    TRACE_DEPTH.with(|d| d.set(depth + 1));
+    true
+}
+
+pub fn trace_return(name: &str, result: &Result<Sexpr, Unwind>, alist: &[(String, Sexpr)]) {
+    let depth: usize = TRACE_DEPTH.with(|d| d.get().saturating_sub(1));
+    TRACE_DEPTH.with(|d| d.set(depth));
+    let _ = write_standard_output("trace", &(trace_exit(depth, name, result) + "\n"), alist);
+}
+
+// the part of the alist that was visible inside frame i
+fn frame_end(frames: &[Frame], i: usize, alist_len: usize) -> usize {
+    match frames.get(i + 1) {
+        Some(f) => f.depth.min(alist_len),
+        None => alist_len,
+    }
+}
+
+fn frame_locals<'a>(
+    frames: &[Frame],
+    i: usize,
+    alist: &'a [(String, Sexpr)],
+) -> &'a [(String, Sexpr)] {
+    let end: usize = frame_end(frames, i, alist.len());
+    &alist[frames[i].depth.min(end)..end]
+}
+
+fn frame_to_string(frame: &Frame) -> String {
+    let mut call: Vec<Sexpr> = vec![Sexpr::Symbol(frame.name.clone())];
+    call.extend(frame.args.iter().cloned());
+    call.push(Sexpr::Nil);
+    sexpr_to_string(&Sexpr::List(call))
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Help,
+    Backtrace,
+    Frame(usize),
+    Locals,
+    Return(String),
+    Restart(usize),
+    Abort,
+    Eval(String),
+    Unknown(String),
+}
+
+fn parse_command(line: &str) -> Command {
+    let line: &str = line.trim();
+    if !line.starts_with(':') {
+        return Command::Eval(String::from(line));
+    }
+
+    let (command, rest) = match line.split_once(char::is_whitespace) {
+        Some((c, r)) => (c, r.trim()),
+        None => (line, ""),
+    };
+    match command.to_ascii_uppercase().as_str() {
+        ":HELP" | ":H" => Command::Help,
+        ":BACKTRACE" | ":BT" => Command::Backtrace,
+        ":LOCALS" | ":L" => Command::Locals,
+        ":RETURN" | ":R" => Command::Return(String::from(rest)),
+        ":ABORT" | ":A" | ":Q" => Command::Abort,
+        ":FRAME" | ":F" => match rest.parse() {
+            Ok(n) => Command::Frame(n),
+            Err(_) => Command::Unknown(String::from(line)),
+        },
+        ":RESTART" => match rest.parse() {
+            Ok(n) => Command::Restart(n),
+            Err(_) => Command::Unknown(String::from(line)),
+        },
+        // a keyword like :test is still something to evaluate
+        _ => Command::Eval(String::from(line)),
+    }
+}
+
+fn print_help() {
+    println!(":backtrace, :bt     show the frames, innermost first");
+    println!(":frame n, :f n      select frame n and show its locals");
+    println!(":locals, :l         show the locals of the selected frame");
+    println!(":return expr, :r    return the value of expr from the selected frame");
+    println!(":restart n          invoke restart n");
+    println!(":abort, :a          leave this debugger level");
+    println!("anything else is evaluated in the selected frame");
+}
+
+fn print_backtrace(frames: &[Frame], selected: usize) {
+    if frames.is_empty() {
+        println!("  no frames, the error happened at the top level");
+    }
+    for (i, frame) in frames.iter().enumerate().rev() {
+        let marker = if i == selected { "*" } else { " " };
+        println!(
+            "{} {}: {}",
+            marker,
+            frames.len() - 1 - i,
+            frame_to_string(frame)
+        );
+    }
+}
+
+fn print_locals(frames: &[Frame], i: usize, alist: &[(String, Sexpr)]) {
+    let locals = frame_locals(frames, i, alist);
+    if locals.is_empty() {
+        println!("  no locals");
+    }
+    for (name, value) in locals {
+        println!("  {} = {}", name, sexpr_to_string(value));
+    }
+}
+
+fn print_restarts(restarts: &[Restart]) {
+    if restarts.is_empty() {
+        return;
+    }
+    println!("Restarts:");
+    for (i, r) in restarts.iter().rev().enumerate() {
+        println!("  {}: [{}]", i, r.name);
+    }
+}
+
+// the required params of a restart, the user is asked for a value for each
+fn restart_params(params: &Sexpr) -> Vec<String> {
+    match params {
+        Sexpr::List(l) => list_items(l)
+            .iter()
+            .map_while(|p| match p {
+                Sexpr::Symbol(s) if !s.starts_with('&') => Some(s.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// evaluates source with the bindings that were visible in frame i, setqs on
+// them are written back so that they are seen once the frame continues
+fn eval_in_frame(
+    source: &str,
+    frames: &[Frame],
+    i: Option<usize>,
+    alist: &mut [(String, Sexpr)],
+) -> Result<Sexpr, Unwind> {
+    let mut tokens: Vec<Token> = tokenize(source)?;
+    let form: Sexpr = parse(&mut tokens);
+
+    let end: usize = match i {
+        Some(i) => frame_end(frames, i, alist.len()),
+        None => alist.len(),
+    };
+    let mut env: Vec<(String, Sexpr)> = alist[..end].to_vec();
+    let result = evaluate(form, &mut env);
+    if env.len() >= end {
+        alist[..end].clone_from_slice(&env[..end]);
+    }
+    result
+}
+
+fn invoke_restart_interactively(
+    restart: &Restart,
+    frames: &[Frame],
+    selected: Option<usize>,
+    alist: &mut [(String, Sexpr)],
+) -> Result<Unwind, Unwind> {
+    let mut values: Vec<Sexpr> = Vec::new();
+    for p in restart_params(&restart.params) {
+        println!("value for {}:", p);
+        let source: String = get_command()?;
+        values.push(eval_in_frame(&source, frames, selected, alist)?);
+    }
+    Ok(Unwind::Restart(restart.id, values))
+}
+
+// the nested prompt the REPL enters on an unhandled error, before anything
+// has unwound so that every frame can still be inspected, installed as the
+// DEBUGGER_HOOK
+pub fn break_loop(c: Sexpr, alist: &mut [(String, Sexpr)]) -> Unwind {
+    let level: usize = LEVEL.with(|l| l.get() + 1);
+    LEVEL.with(|l| l.set(level));
+    let result: Unwind = run_break_loop(c, level, alist);
+    LEVEL.with(|l| l.set(level - 1));
+    result
+}
+
+fn run_break_loop(c: Sexpr, level: usize, alist: &mut [(String, Sexpr)]) -> Unwind {
+    let frames: Vec<Frame> = frames();
+    let restarts: Vec<Restart> = active_restarts();
+    // the innermost frame starts out selected
+    let mut selected: Option<usize> = frames.len().checked_sub(1);
+
+    println!("Error: {}", Unwind::Condition(c.clone()));
+    println!("Entering the debugger, :help for commands");
+    print_restarts(&restarts);
+
+    loop {
+        let source: String = match get_command_prompt(&format!("debug[{}]> ", level)) {
+            Ok(s) => s,
+            Err(e) if e == END_OF_INPUT => return Unwind::Condition(c),
+            Err(e) => {
+                println!("Error: {}", e);
+                continue;
+            }
+        };
+
+        match parse_command(&source) {
+            Command::Help => print_help(),
+            Command::Backtrace => print_backtrace(&frames, selected.unwrap_or(0)),
+            Command::Frame(n) if n < frames.len() => {
+                let i: usize = frames.len() - 1 - n;
+                selected = Some(i);
+                println!("{}: {}", n, frame_to_string(&frames[i]));
+                print_locals(&frames, i, alist);
+            }
+            Command::Frame(n) => println!("there is no frame {}", n),
+            Command::Locals => match selected {
+                Some(i) => print_locals(&frames, i, alist),
+                None => println!("  no frames, the error happened at the top level"),
+            },
+            Command::Return(expr) => match selected {
+                Some(i) => match eval_in_frame(&expr, &frames, selected, alist) {
+                    Ok(v) => return Unwind::Frame(i, v),
+                    Err(Unwind::Abort) => return Unwind::Abort,
+                    Err(e) => println!("Error: {}", e),
+                },
+                None => println!("there is no frame to return from"),
+            },
+            Command::Restart(n) if n < restarts.len() => {
+                let restart: &Restart = &restarts[restarts.len() - 1 - n];
+                match invoke_restart_interactively(restart, &frames, selected, alist) {
+                    Ok(u)
+                    | Err(u @ (Unwind::Frame(_, _) | Unwind::Restart(_, _) | Unwind::Abort)) => {
+                        return u
+                    }
+                    Err(e) => println!("Error: {}", e),
+                }
+            }
+            Command::Restart(n) => println!("there is no restart {}", n),
+            Command::Abort => return Unwind::Condition(c),
+            Command::Eval(source) if source.is_empty() => {}
+            Command::Eval(source) => match eval_in_frame(&source, &frames, selected, alist) {
+                Ok(v) => println!("{}", sexpr_to_string(&v)),
+                // a restart, return or abort picked in a nested level keeps unwinding
+                Err(u @ (Unwind::Frame(_, _) | Unwind::Restart(_, _) | Unwind::Abort)) => return u,
+                Err(e) => println!("Error: {}", e),
+            },
+            Command::Unknown(s) => println!("unknown command {}, :help for commands", s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+
+    fn frame(name: &str, depth: usize) -> Frame {
+        Frame {
+            name: String::from(name),
+            args: vec![Sexpr::Integer(1)],
+            depth,
+        }
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command(":bt"), Command::Backtrace);
+        assert_eq!(parse_command(" :FRAME 2 "), Command::Frame(2));
+        assert_eq!(
+            parse_command(":r (+ x 1)"),
+            Command::Return(String::from("(+ x 1)"))
+        );
+        assert_eq!(parse_command(":restart 0"), Command::Restart(0));
+        assert_eq!(
+            parse_command(":frame x"),
+            Command::Unknown(String::from(":frame x"))
+        );
+        assert_eq!(
+            parse_command("(car x)"),
+            Command::Eval(String::from("(car x)"))
+        );
+        assert_eq!(parse_command(":key"), Command::Eval(String::from(":key")));
+    }
+
+    #[test]
+    fn test_frame_locals() {
+        let frames = vec![frame("OUTER", 0), frame("INNER", 2)];
+        let alist: Vec<(String, Sexpr)> = vec![
+            (String::from("A"), Sexpr::Integer(1)),
+            (String::from("B"), Sexpr::Integer(2)),
+            (String::from("C"), Sexpr::Integer(3)),
+        ];
+        assert_eq!(frame_locals(&frames, 0, &alist), &alist[..2]);
+        assert_eq!(frame_locals(&frames, 1, &alist), &alist[2..]);
+        assert_eq!(frame_to_string(&frames[1]), "(INNER 1)");
+    }
+
+    #[test]
+    fn test_eval_in_frame() {
+        let frames = vec![frame("OUTER", 0), frame("INNER", 1)];
+        let mut alist: Vec<(String, Sexpr)> = vec![
+            (String::from("X"), Sexpr::Integer(1)),
+            (String::from("X"), Sexpr::Integer(2)),
+        ];
+        assert_eq!(
+            eval_in_frame("x", &frames, Some(0), &mut alist),
+            Ok(Sexpr::Integer(1))
+        );
+        assert_eq!(
+            eval_in_frame("x", &frames, Some(1), &mut alist),
+            Ok(Sexpr::Integer(2))
+        );
+
+        // assignments stay in the frame they were made in
+        eval_in_frame("(setq x 10)", &frames, Some(0), &mut alist).unwrap();
+        assert_eq!(alist[0].1, Sexpr::Integer(10));
+        assert_eq!(alist[1].1, Sexpr::Integer(2));
+    }
+
+    #[test]
+    fn test_frames() {
+        run("(defun frames-test (x) (car x))").unwrap();
+        assert!(run("(frames-test 1)").is_err());
+        // frames are popped while unwinding
+        assert!(frames().is_empty());
+
+        let i = push_frame("FRAMES-TEST", &[Sexpr::Integer(1)], 0);
+        assert_eq!(frames()[i].name, "FRAMES-TEST");
+        pop_frame(i);
+        assert!(frames().is_empty());
+    }
+
+    #[test]
+    fn test_trace() {
+        assert_eq!(
+            run("(trace trace-test-a trace-test-b)"),
+            Ok(Sexpr::List(vec![
+                Sexpr::Symbol(String::from("TRACE-TEST-A")),
+                Sexpr::Symbol(String::from("TRACE-TEST-B")),
+                Sexpr::Nil
+            ]))
+        );
+        assert!(TRACED
+            .lock()
+            .unwrap()
+            .contains(&String::from("TRACE-TEST-A")));
+        run("(untrace trace-test-a)").unwrap();
+        assert!(!TRACED
+            .lock()
+            .unwrap()
+            .contains(&String::from("TRACE-TEST-A")));
+        assert!(TRACED
+            .lock()
+            .unwrap()
+            .contains(&String::from("TRACE-TEST-B")));
+        run("(untrace trace-test-b)").unwrap();
+
+        // trace lines go wherever *standard-output* does
+        run("(defun trace-test-c (n) n)").unwrap();
+        run("(trace trace-test-c)").unwrap();
+        assert_eq!(
+            run("(with-output-to-string (*standard-output*) (trace-test-c 1))"),
+            Ok(Sexpr::String(String::from(
+                "  0: (TRACE-TEST-C 1)\n  0: TRACE-TEST-C returned 1\n"
+            )))
+        );
+        run("(untrace trace-test-c)").unwrap();
+
+        assert_eq!(
+            run("(trace car)"),
+            Err(Unwind::from(
+                "trace - CAR is a builtin, only user functions can be traced"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_trace_lines() {
+        assert_eq!(
+            trace_entry(0, "FACT", &[Sexpr::Integer(3)]),
+            "  0: (FACT 3)"
+        );
+        assert_eq!(
+            trace_entry(1, "FACT", &[Sexpr::Integer(2)]),
+            "    1: (FACT 2)"
+        );
+        assert_eq!(
+            trace_exit(1, "FACT", &Ok(Sexpr::Integer(2))),
+            "    1: FACT returned 2"
+        );
+        assert_eq!(
+            trace_exit(0, "FACT", &Err(Unwind::from("oops"))),
+            "  0: FACT unwound"
+        );
+        assert_eq!(trace_entry(0, "NONE", &[]), "  0: (NONE)");
+    }
+
+    #[test]
+    fn test_restart_params() {
+        let s = |n: &str| Sexpr::Symbol(String::from(n));
+        let params = Sexpr::List(vec![s("A"), s("B"), s("&OPTIONAL"), s("C"), Sexpr::Nil]);
+        assert_eq!(restart_params(&params), vec!["A", "B"]);
+        assert!(restart_params(&Sexpr::Nil).is_empty());
+    }
+}
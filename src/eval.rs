@@ -1,7 +1,84 @@
+use crate::chars::*;
+use crate::condition::*;
+use crate::debugger::*;
+use crate::files::*;
+use crate::limits::*;
 use crate::lisp::*;
+use crate::lists::*;
+use crate::numbers::*;
 use crate::parse::*;
+use crate::profiler::*;
+use crate::sandbox::*;
+use crate::sequences::*;
+use crate::stepper::*;
+use crate::streams::*;
+use crate::strings::*;
+use crate::types::*;
 
-fn assoc(v: String, alist: Vec<(String, Sexpr)>) -> Sexpr {
+use std::fmt;
+
+// anything that unwinds the evaluator, errors and non-local exits alike
+#[derive(Debug, Clone, PartialEq)]
+pub enum Unwind {
+    Error(String),
+    // (return-from name value), caught by the enclosing block with that name
+    Return(String, Sexpr),
+    // (throw tag value), caught by the innermost catch with an equal tag
+    Throw(Sexpr, Sexpr),
+    // a condition object that no handler took
+    Condition(Sexpr),
+    // the id and clause of the handler-case that takes a condition
+    HandlerCase(usize, usize, Sexpr),
+    // (invoke-restart name args...), caught by the restart-case with that id
+    Restart(usize, Vec<Sexpr>),
+    // a value the debugger returns from the frame at that index of the stack
+    Frame(usize, Sexpr),
+    // an evaluation ran out of its budget, nothing in lisp can catch it
+    ResourceExhausted(Limit),
+    // the user aborted a step, nothing in lisp can catch it either and the
+    // REPL goes back to the top level
+    Abort,
+}
+
+impl From<String> for Unwind {
+    fn from(s: String) -> Self {
+        Unwind::Error(s)
+    }
+}
+
+impl From<&str> for Unwind {
+    fn from(s: &str) -> Self {
+        Unwind::Error(String::from(s))
+    }
+}
+
+impl fmt::Display for Unwind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unwind::Error(s) => write!(f, "{}", s),
+            Unwind::Return(name, _) => {
+                write!(f, "return-from - no block named {} is visible", name)
+            }
+            Unwind::Throw(tag, _) => write!(f, "throw - no catch for tag {}", sexpr_to_string(tag)),
+            Unwind::Condition(c @ Sexpr::Condition(t, _)) if t == "SIMPLE-ERROR" => {
+                write!(f, "{}", condition_message(c))
+            }
+            Unwind::Condition(c @ Sexpr::Condition(t, _)) => {
+                write!(f, "{} - {}", t, condition_message(c))
+            }
+            Unwind::Condition(c) => write!(f, "{}", sexpr_to_string(c)),
+            Unwind::HandlerCase(_, _, c) => {
+                write!(f, "handler-case - {} was not caught", sexpr_to_string(c))
+            }
+            Unwind::Restart(_, _) => write!(f, "invoke-restart - restart is no longer active"),
+            Unwind::Frame(i, _) => write!(f, "debugger - frame {} is no longer active", i),
+            Unwind::ResourceExhausted(limit) => write!(f, "resource exhausted - {}", limit),
+            Unwind::Abort => write!(f, "aborted"),
+        }
+    }
+}
+
+fn assoc(v: String, alist: &[(String, Sexpr)]) -> Sexpr {
     for (s, o) in alist.iter().rev() {
         if *s == v {
             return o.clone();
@@ -11,6 +88,14 @@ fn assoc(v: String, alist: Vec<(String, Sexpr)>) -> Sexpr {
     Sexpr::Nil
 }
 
+// local bindings shadow the OBLIST, which only holds globals
+pub fn lookup(v: String, alist: &[(String, Sexpr)]) -> Sexpr {
+    match alist.iter().rev().find(|(s, _)| *s == v) {
+        Some((_, o)) => o.clone(),
+        None => assoc(v, &OBLIST.lock().unwrap()),
+    }
+}
+
 /*
 fn eval_list(l: Sexpr, alist: Vec<String, Sexpr>) -> Sexpr {
     Sexpr::Nil
@@ -18,57 +103,472 @@ fn eval_list(l: Sexpr, alist: Vec<String, Sexpr>) -> Sexpr {
 
 // fn bind_formals(formals: Sexpr, actuals: Sexpr, alist: Vec<String, Sexpr>) {}
 // pass a mutable reference of the formal and replace it with the actual
-fn apply_lambda(f: Sexpr, args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
+fn apply_lambda(f: Sexpr, args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
     // not sure how to dynamically create and store a lambda in rust
     // maybe i just store the list for the function and evaluate it when the function is called
     // yeah i'll do that, later tho...
     Ok(Sexpr::Nil)
 }
 */
-fn apply_atom(f: Sexpr, args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
+// functions that evaluate their own args, so they can also be passed around
+// as values with #' and called through funcall
+pub const BUILTINS: &[&str] = &[
+    "CAR",
+    "CDR",
+    "EQUAL",
+    "ATOM",
+    "LISTP",
+    "NULL",
+    "FLOOR",
+    "+",
+    "-",
+    "*",
+    "/",
+    "MOD",
+    "CEILING",
+    "TRUNCATE",
+    "ROUND",
+    "NUMERATOR",
+    "DENOMINATOR",
+    "RATIONAL",
+    "RATIONALIZE",
+    "EXPT",
+    "SQRT",
+    "ISQRT",
+    "EXP",
+    "LOG",
+    "SIN",
+    "COS",
+    "TAN",
+    "ASIN",
+    "ACOS",
+    "ATAN",
+    "GCD",
+    "LCM",
+    "FLOAT",
+    "VALUES",
+    "=",
+    "/=",
+    "<",
+    ">",
+    "<=",
+    ">=",
+    "MIN",
+    "MAX",
+    "ABS",
+    "1+",
+    "1-",
+    "CHAR-CODE",
+    "CODE-CHAR",
+    "CHAR-UPCASE",
+    "CHAR-DOWNCASE",
+    "ALPHA-CHAR-P",
+    "DIGIT-CHAR-P",
+    "CHAR=",
+    "CHAR/=",
+    "CHAR<",
+    "CHAR>",
+    "CHAR<=",
+    "CHAR>=",
+    "LENGTH",
+    "CONCATENATE",
+    "SUBSEQ",
+    "STRING-UPCASE",
+    "STRING-DOWNCASE",
+    "STRING=",
+    "STRING<",
+    "SEARCH",
+    "STRING-TRIM",
+    "SPLIT-STRING",
+    "PARSE-INTEGER",
+    "STRING-TO-SYMBOL",
+    "SYMBOL-NAME",
+    "CHAR",
+    "PRINT",
+    "EVAL",
+    "CONS",
+    "LIST",
+    "LIST*",
+    "APPEND",
+    "REVERSE",
+    "NTH",
+    "NTHCDR",
+    "LAST",
+    "BUTLAST",
+    "MEMBER",
+    "ASSOC",
+    "RASSOC",
+    "COPY-LIST",
+    "REMOVE",
+    "SUBST",
+    "MAPCAR",
+    "MAPC",
+    "MAPLIST",
+    "MAPCAN",
+    "REDUCE",
+    "REMOVE-IF",
+    "REMOVE-IF-NOT",
+    "FIND-IF",
+    "POSITION",
+    "COUNT-IF",
+    "EVERY",
+    "SOME",
+    "SORT",
+    "STABLE-SORT",
+    "NUMBERP",
+    "INTEGERP",
+    "FLOATP",
+    "STRINGP",
+    "SYMBOLP",
+    "CONSP",
+    "FUNCTIONP",
+    "CHARACTERP",
+    "ZEROP",
+    "EVENP",
+    "ODDP",
+    "PLUSP",
+    "MINUSP",
+    "TYPE-OF",
+    "TYPEP",
+    "FUNCALL",
+    "APPLY",
+    "ERROR",
+    "SIGNAL",
+    "MAKE-CONDITION",
+    "INVOKE-RESTART",
+    "COMPUTE-RESTARTS",
+    "CONDITION-TYPE",
+    "CONDITION-DATA",
+    "CONDITION-MESSAGE",
+    "PRIN1",
+    "PRINC",
+    "TERPRI",
+    "MAKE-STRING-OUTPUT-STREAM",
+    "GET-OUTPUT-STREAM-STRING",
+    "READ",
+    "READ-LINE",
+    "READ-CHAR",
+    "PEEK-CHAR",
+    "READ-FROM-STRING",
+    "MAKE-STRING-INPUT-STREAM",
+    "OPEN",
+    "CLOSE",
+    "PROBE-FILE",
+    "DELETE-FILE",
+    "RENAME-FILE",
+    "DIRECTORY",
+    "FILE-LENGTH",
+];
+
+// builtins the sandbox leaves out are unbound like any other undefined function,
+// the CADR family is matched by name rather than listed
+fn is_builtin(s: &str) -> bool {
+    (BUILTINS.contains(&s) || is_cxr(s)) && allowed(s)
+}
+
+fn apply_builtin(s: &str, args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let profiled: bool = profile_enter(s);
+    let result = call_builtin(s, args, alist);
+    if profiled {
+        profile_exit();
+    }
+    if !returns_values(s) {
+        // left over from evaluating the args, not values of this call
+        clear_extra_values();
+    }
+    result
+}
+
+// builtins whose extra values are their own or those of what they call
+fn returns_values(s: &str) -> bool {
+    matches!(
+        s,
+        "VALUES"
+            | "FLOOR"
+            | "CEILING"
+            | "TRUNCATE"
+            | "ROUND"
+            | "PARSE-INTEGER"
+            | "FUNCALL"
+            | "APPLY"
+            | "EVAL"
+    )
+}
+
+fn call_builtin(s: &str, args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match s {
+        "CAR" => car(args, alist),
+        "CDR" => cdr(args, alist),
+        "EQUAL" => equal(args, alist),
+        "ATOM" => atom(args, alist),
+        "LISTP" => listp(args, alist),
+        "NULL" => null(args, alist),
+        "FLOOR" => floor(args, alist),
+        "+" => add(args, alist),
+        "-" => subtract(args, alist),
+        "*" => multiply(args, alist),
+        "/" => divide(args, alist),
+        "MOD" => modulo(args, alist),
+        "CEILING" => ceiling(args, alist),
+        "TRUNCATE" => truncate(args, alist),
+        "ROUND" => round(args, alist),
+        "NUMERATOR" => numerator(args, alist),
+        "DENOMINATOR" => denominator(args, alist),
+        "RATIONAL" => rational(args, alist),
+        "RATIONALIZE" => rationalize(args, alist),
+        "EXPT" => expt(args, alist),
+        "SQRT" => sqrt(args, alist),
+        "ISQRT" => isqrt(args, alist),
+        "EXP" => exp(args, alist),
+        "LOG" => log(args, alist),
+        "SIN" => sin(args, alist),
+        "COS" => cos(args, alist),
+        "TAN" => tan(args, alist),
+        "ASIN" => asin(args, alist),
+        "ACOS" => acos(args, alist),
+        "ATAN" => atan(args, alist),
+        "GCD" => gcd(args, alist),
+        "LCM" => lcm(args, alist),
+        "FLOAT" => float(args, alist),
+        "VALUES" => values(args, alist),
+        "=" => num_equal(args, alist),
+        "/=" => num_not_equal(args, alist),
+        "<" => less(args, alist),
+        ">" => greater(args, alist),
+        "<=" => less_equal(args, alist),
+        ">=" => greater_equal(args, alist),
+        "MIN" => min(args, alist),
+        "MAX" => max(args, alist),
+        "ABS" => abs(args, alist),
+        "1+" => one_plus(args, alist),
+        "1-" => one_minus(args, alist),
+        "CHAR-CODE" => char_code(args, alist),
+        "CODE-CHAR" => code_char(args, alist),
+        "CHAR-UPCASE" => char_upcase(args, alist),
+        "CHAR-DOWNCASE" => char_downcase(args, alist),
+        "ALPHA-CHAR-P" => alpha_char_p(args, alist),
+        "DIGIT-CHAR-P" => digit_char_p(args, alist),
+        "CHAR=" => char_equal(args, alist),
+        "CHAR/=" => char_not_equal(args, alist),
+        "CHAR<" => char_less(args, alist),
+        "CHAR>" => char_greater(args, alist),
+        "CHAR<=" => char_less_equal(args, alist),
+        "CHAR>=" => char_greater_equal(args, alist),
+        "LENGTH" => length(args, alist),
+        "CONCATENATE" => concatenate(args, alist),
+        "SUBSEQ" => subseq(args, alist),
+        "STRING-UPCASE" => string_upcase(args, alist),
+        "STRING-DOWNCASE" => string_downcase(args, alist),
+        "STRING=" => string_equals(args, alist),
+        "STRING<" => string_less(args, alist),
+        "SEARCH" => search(args, alist),
+        "STRING-TRIM" => string_trim(args, alist),
+        "SPLIT-STRING" => split_string(args, alist),
+        "PARSE-INTEGER" => parse_integer(args, alist),
+        "STRING-TO-SYMBOL" => string_to_symbol(args, alist),
+        "SYMBOL-NAME" => symbol_name(args, alist),
+        "CHAR" => char_index(args, alist),
+        "PRINT" => print(args, alist),
+        "EVAL" => eval(args, alist),
+        "CONS" => cons(args, alist),
+        "LIST" => list(args, alist),
+        "LIST*" => list_star(args, alist),
+        "APPEND" => append(args, alist),
+        "REVERSE" => reverse(args, alist),
+        "NTH" => nth(args, alist),
+        "NTHCDR" => nthcdr(args, alist),
+        "LAST" => last(args, alist),
+        "BUTLAST" => butlast(args, alist),
+        "MEMBER" => member(args, alist),
+        "ASSOC" => assoc_fn(args, alist),
+        "RASSOC" => rassoc(args, alist),
+        "COPY-LIST" => copy_list(args, alist),
+        "REMOVE" => remove(args, alist),
+        "SUBST" => subst(args, alist),
+        "MAPCAR" => mapcar(args, alist),
+        "MAPC" => mapc(args, alist),
+        "MAPLIST" => maplist(args, alist),
+        "MAPCAN" => mapcan(args, alist),
+        "REDUCE" => reduce(args, alist),
+        "REMOVE-IF" => remove_if(args, alist),
+        "REMOVE-IF-NOT" => remove_if_not(args, alist),
+        "FIND-IF" => find_if(args, alist),
+        "POSITION" => position(args, alist),
+        "COUNT-IF" => count_if(args, alist),
+        "EVERY" => every(args, alist),
+        "SOME" => some(args, alist),
+        "SORT" => sort(args, alist),
+        "STABLE-SORT" => sort(args, alist),
+        "NUMBERP" => numberp(args, alist),
+        "INTEGERP" => integerp(args, alist),
+        "FLOATP" => floatp(args, alist),
+        "STRINGP" => stringp(args, alist),
+        "SYMBOLP" => symbolp(args, alist),
+        "CONSP" => consp(args, alist),
+        "FUNCTIONP" => functionp(args, alist),
+        "CHARACTERP" => characterp(args, alist),
+        "ZEROP" => zerop(args, alist),
+        "EVENP" => evenp(args, alist),
+        "ODDP" => oddp(args, alist),
+        "PLUSP" => plusp(args, alist),
+        "MINUSP" => minusp(args, alist),
+        "TYPE-OF" => type_of(args, alist),
+        "TYPEP" => typep(args, alist),
+        "FUNCALL" => funcall(args, alist),
+        "APPLY" => apply_function(args, alist),
+        "ERROR" => error(args, alist),
+        "SIGNAL" => signal(args, alist),
+        "MAKE-CONDITION" => make_condition_fn(args, alist),
+        "INVOKE-RESTART" => invoke_restart(args, alist),
+        "COMPUTE-RESTARTS" => compute_restarts(args, alist),
+        "CONDITION-TYPE" => condition_type(args, alist),
+        "CONDITION-DATA" => condition_data(args, alist),
+        "CONDITION-MESSAGE" => condition_message_fn(args, alist),
+        "PRIN1" => prin1(args, alist),
+        "PRINC" => princ(args, alist),
+        "TERPRI" => terpri(args, alist),
+        "MAKE-STRING-OUTPUT-STREAM" => make_string_output_stream(args, alist),
+        "GET-OUTPUT-STREAM-STRING" => get_output_stream_string(args, alist),
+        "READ" => read(args, alist),
+        "READ-LINE" => read_line(args, alist),
+        "READ-CHAR" => read_char(args, alist),
+        "PEEK-CHAR" => peek_char(args, alist),
+        "READ-FROM-STRING" => read_from_string(args, alist),
+        "MAKE-STRING-INPUT-STREAM" => make_string_input_stream(args, alist),
+        "OPEN" => open(args, alist),
+        "CLOSE" => close(args, alist),
+        "PROBE-FILE" => probe_file(args, alist),
+        "DELETE-FILE" => delete_file(args, alist),
+        "RENAME-FILE" => rename_file(args, alist),
+        "DIRECTORY" => directory(args, alist),
+        "FILE-LENGTH" => file_length(args, alist),
+        _ if is_cxr(s) => cxr(s, args, alist),
+        _ => Err(Unwind::from(String::from(s) + " function not found")),
+    }
+}
+
+// the function a symbol names, user functions first since they are looked up by value
+pub fn function_value(s: String, alist: &[(String, Sexpr)]) -> Result<Sexpr, Unwind> {
+    match lookup(s.clone(), alist) {
+        Sexpr::Lambda(_, l) => Ok(Sexpr::Lambda(s, l)),
+        Sexpr::Builtin(b) => Ok(Sexpr::Builtin(b)),
+        _ if is_builtin(&s) => Ok(Sexpr::Builtin(s)),
+        _ => Err(Unwind::from(s + " function not found")),
+    }
+}
+
+// calls a function value with args that are already evaluated
+pub fn call_function(
+    f: Sexpr,
+    args: Vec<Sexpr>,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Sexpr, Unwind> {
+    match f {
+        Sexpr::Lambda(name, l) => {
+            let mut args = args;
+            args.push(Sexpr::Nil);
+            func(
+                name.clone(),
+                Sexpr::Lambda(name, l),
+                Sexpr::List(args),
+                alist,
+            )
+        }
+        Sexpr::Builtin(name) if !allowed(&name) => Err(Unwind::from(name + " function not found")),
+        Sexpr::Builtin(name) => {
+            // builtins evaluate their args, so quote the values to pass them through as is
+            let mut quoted: Vec<Sexpr> = args
+                .into_iter()
+                .map(|a| Sexpr::List(vec![Sexpr::Symbol(String::from("QUOTE")), a, Sexpr::Nil]))
+                .collect();
+            quoted.push(Sexpr::Nil);
+            apply_builtin(&name, Sexpr::List(quoted), alist)
+        }
+        Sexpr::Symbol(s) => {
+            let f: Sexpr = function_value(s, alist)?;
+            call_function(f, args, alist)
+        }
+        f => Err(Unwind::from(sexpr_to_string(&f) + " is not a function")),
+    }
+}
+
+fn apply_atom(f: Sexpr, args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
     // args is a list containing the args of the function
     // (arg1 arg2 ... argN)
     match f {
+        Sexpr::Symbol(s) if !allowed(&s) => {
+            let f: Sexpr = function_value(s, alist)?;
+            let args: Vec<Sexpr> = eval_args(args, alist)?;
+            call_function(f, args, alist)
+        }
         Sexpr::Symbol(s) => match s.as_str() {
             "QUOTE" => quote(args),
-            "CAR" => car(args, alist.clone()),
-            "CDR" => cdr(args, alist.clone()),
-            "SETQ" => setq(args, alist.clone()),
-            "EQUAL" => equal(args, alist.clone()),
-            "ATOM" => atom(args, alist.clone()),
-            "LISTP" => listp(args, alist.clone()),
-            "NULL" => null(args, alist.clone()),
-            "FLOOR" => floor(args, alist.clone()),
-            "+" => add(args, alist.clone()),
-            "-" => subtract(args, alist.clone()),
-            "*" => multiply(args, alist.clone()),
-            "/" => divide(args, alist.clone()),
-            "MOD" => modulo(args, alist.clone()),
-            "PRINT" => print(args, alist.clone()),
-            "EVAL" => eval(args, alist.clone()),
-            "CONS" => cons(args, alist.clone()),
-            "COND" => cond(args, alist.clone()),
+            "SETQ" => setq(args, alist),
+            "SETF" => setf(args, alist),
+            "COND" => cond(args, alist),
             "DEFUN" => defun(args),
+            "LAMBDA" => lambda(args),
+            "FUNCTION" => function(args, alist),
+            "PROGN" => progn(args, alist),
+            "RETURN" => return_nil(args, alist),
+            "RETURN-FROM" => return_from(args, alist),
+            "BLOCK" => block(args, alist),
+            "CATCH" => catch(args, alist),
+            "THROW" => throw(args, alist),
+            "UNWIND-PROTECT" => unwind_protect(args, alist),
+            "DOTIMES" => dotimes(args, alist),
+            "DOLIST" => dolist(args, alist),
+            "DO" => do_loop(args, alist, false),
+            "DO*" => do_loop(args, alist, true),
+            "LOOP" => loop_macro(args, alist),
+            "HANDLER-CASE" => handler_case(args, alist),
+            "HANDLER-BIND" => handler_bind(args, alist),
+            "IGNORE-ERRORS" => ignore_errors(args, alist),
+            "RESTART-CASE" => restart_case(args, alist),
+            "DEFINE-CONDITION" => define_condition(args),
+            "TRACE" => trace(args),
+            "UNTRACE" => untrace(args),
+            "STEP" => step(args, alist),
+            "PROFILE" => profile(args, alist),
+            "WITH-OUTPUT-TO-STRING" => with_output_to_string(args, alist),
+            "WITH-INPUT-FROM-STRING" => with_input_from_string(args, alist),
+            "WITH-OPEN-FILE" => with_open_file(args, alist),
+            "CHECK-TYPE" => check_type(args, alist),
+            "MULTIPLE-VALUE-BIND" => multiple_value_bind(args, alist),
+            "MULTIPLE-VALUE-LIST" => multiple_value_list(args, alist),
+            _ if is_builtin(&s) => apply_builtin(&s, args, alist),
             _ => {
-                let lambda: Sexpr = match assoc(s.clone(), alist.clone()) {
-                    Sexpr::Nil => return Err(String::from(s + " function not found")),
-                    s => s,
-                };
-                func(s, lambda, args, alist.clone())
+                let f: Sexpr = function_value(s, alist)?;
+                let args: Vec<Sexpr> = eval_args(args, alist)?;
+                call_function(f, args, alist)
             }
         },
-        _ => Err(String::from("apply_atom - requires symbol as first arg")),
+        _ => Err(Unwind::from("apply_atom - requires symbol as first arg")),
     }
 }
 
-fn apply(v: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
+// user functions get their args evaluated left to right before they are bound
+pub fn eval_args(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Vec<Sexpr>, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("eval_args - args must be a list")),
+    };
+
+    let mut values: Vec<Sexpr> = Vec::new();
+    for a in args {
+        values.push(evaluate(a, alist)?);
+    }
+    Ok(values)
+}
+
+fn apply(v: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
     // create a copy of first sexpr
     let f = match &v {
         Sexpr::List(l) => match l.first() {
             Some(s) => s.clone(),
-            None => return Err(String::from("apply - list is empty")),
+            None => return Err(Unwind::from("apply - list is empty")),
         },
-        _ => return Err(String::from("apply - val passed in was not a list")),
+        _ => return Err(Unwind::from("apply - val passed in was not a list")),
     };
 
     // create copy of args
@@ -77,36 +577,60 @@ fn apply(v: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
             if l.len() >= 2 {
                 Sexpr::List(l[1..].to_vec())
             } else {
-                return Err(String::from("apply - v len must be >= 2"));
+                return Err(Unwind::from("apply - v len must be >= 2"));
             }
         }
-        _ => return Err(String::from("apply - v must be a list")),
+        _ => return Err(Unwind::from("apply - v must be a list")),
     };
 
     match f {
-        Sexpr::Symbol(_) => apply_atom(f, args, alist.clone()), // do i need to clone the alist here? it's safe to do so...
+        Sexpr::Symbol(_) => apply_atom(f, args, alist),
+        // ((lambda (x) ...) args)
+        Sexpr::List(_) => match evaluate(f, alist)? {
+            f @ Sexpr::Lambda(_, _) => {
+                let args: Vec<Sexpr> = eval_args(args, alist)?;
+                call_function(f, args, alist)
+            }
+            _ => Err(Unwind::from("apply - head of a form must be a function")),
+        },
         _ => Ok(Sexpr::Nil),
     }
 }
 
-fn eval_atom(v: Sexpr, alist: Vec<(String, Sexpr)>) -> Sexpr {
+fn eval_atom(v: Sexpr, alist: &[(String, Sexpr)]) -> Sexpr {
     match v {
-        Sexpr::Symbol(s) => assoc(s, alist),
+        // keywords like :test evaluate to themselves
+        Sexpr::Symbol(s) if s.starts_with(':') => Sexpr::Symbol(s),
+        Sexpr::Symbol(s) => lookup(s, alist),
         _ => v, // integers, T and NIL return themselves
     }
 }
-pub fn evaluate(v: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
+pub fn evaluate(v: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    enter_evaluate()?;
+    clear_extra_values();
+    let result = grow_stack(|| {
+        if stepping() {
+            step_evaluate(v, alist)
+        } else {
+            eval_form(v, alist)
+        }
+    });
+    leave_evaluate();
+    result
+}
+
+// evaluate without the stepper
+pub fn eval_form(v: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
     match &v {
         Sexpr::List(l) => {
-            if l.is_empty() {
-                Ok(Sexpr::Nil)
-            } else if l.first() == Some(&Sexpr::Nil) {
+            if l.is_empty() || l.first() == Some(&Sexpr::Nil) {
                 Ok(Sexpr::Nil)
             } else {
-                apply(v, alist.clone())
+                // handlers have to see an error before anything unwinds
+                apply(v, alist).map_err(|e| signal_unwind(e, alist))
             }
         }
-        _ => Ok(eval_atom(v, alist.clone())),
+        _ => Ok(eval_atom(v, alist)),
     }
 }
 
@@ -120,18 +644,18 @@ mod tests {
         let mut v: String = String::from("A");
         let mut alist: Vec<(String, Sexpr)> = Vec::new();
         let mut result: Sexpr = Sexpr::Nil;
-        assert!(equal_sexprs(&assoc(v, alist.clone()), &result));
+        assert!(equal_sexprs(&assoc(v, &alist), &result));
 
         v = String::from("A");
         alist.push((String::from("A"), Sexpr::Symbol(String::from("hello"))));
         result = Sexpr::Symbol(String::from("hello"));
-        assert!(equal_sexprs(&assoc(v, alist.clone()), &result));
+        assert!(equal_sexprs(&assoc(v, &alist), &result));
 
         // Local scope variable is used before anything else
         v = String::from("A");
         alist.push((String::from("A"), Sexpr::Integer(1)));
         result = Sexpr::Integer(1);
-        assert!(equal_sexprs(&assoc(v, alist.clone()), &result));
+        assert!(equal_sexprs(&assoc(v, &alist), &result));
     }
 
     #[test]
@@ -139,18 +663,18 @@ mod tests {
         let mut v: Sexpr = Sexpr::Symbol(String::from("A"));
         let mut alist: Vec<(String, Sexpr)> = Vec::new();
         let mut result: Sexpr = Sexpr::Nil;
-        assert!(equal_sexprs(&eval_atom(v, alist.clone()), &result));
+        assert!(equal_sexprs(&eval_atom(v, &alist), &result));
 
         v = Sexpr::Symbol(String::from("A"));
         alist.push((String::from("A"), Sexpr::Symbol(String::from("hello"))));
         result = Sexpr::Symbol(String::from("hello"));
-        assert!(equal_sexprs(&eval_atom(v, alist.clone()), &result));
+        assert!(equal_sexprs(&eval_atom(v, &alist), &result));
 
         // Local scope variable is used before anything else
         v = Sexpr::Symbol(String::from("A"));
         alist.push((String::from("A"), Sexpr::Integer(1)));
         result = Sexpr::Integer(1);
-        assert!(equal_sexprs(&eval_atom(v, alist.clone()), &result));
+        assert!(equal_sexprs(&eval_atom(v, &alist), &result));
     }
 
     #[test]
@@ -161,21 +685,21 @@ mod tests {
             Sexpr::Integer(3),
         ]);
         let v: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("QUOTE")), args.clone()]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
         assert!(equal_sexprs(
-            &evaluate(v.clone(), alist.clone()).unwrap(),
+            &evaluate(v.clone(), &mut alist).unwrap(),
             &args
         ));
 
         // () == NIL tests
         let v: Sexpr = Sexpr::List(Vec::new());
         assert!(equal_sexprs(
-            &evaluate(v.clone(), alist.clone()).unwrap(),
+            &evaluate(v.clone(), &mut alist).unwrap(),
             &Sexpr::Nil
         ));
         let v: Sexpr = Sexpr::List(vec![Sexpr::Nil]);
         assert!(equal_sexprs(
-            &evaluate(v.clone(), alist.clone()).unwrap(),
+            &evaluate(v.clone(), &mut alist).unwrap(),
             &Sexpr::Nil
         ));
     }
@@ -188,11 +712,8 @@ mod tests {
             Sexpr::Integer(3),
         ]);
         let v: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("QUOTE")), args.clone()]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert!(equal_sexprs(
-            &apply(v.clone(), alist.clone()).unwrap(),
-            &args
-        ));
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert!(equal_sexprs(&apply(v.clone(), &mut alist).unwrap(), &args));
     }
 
     #[test]
@@ -204,9 +725,9 @@ mod tests {
             Sexpr::Integer(3),
         ]);
         let args = Sexpr::List(vec![quoted.clone()]);
-        let alist = Vec::new();
+        let mut alist = Vec::new();
         assert!(equal_sexprs(
-            &apply_atom(f.clone(), args.clone(), alist.clone()).unwrap(),
+            &apply_atom(f.clone(), args.clone(), &mut alist).unwrap(),
             &quoted
         ));
     }
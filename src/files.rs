@@ -0,0 +1,465 @@
+use crate::condition::*;
+use crate::eval::*;
+use crate::lisp::*;
+use crate::parse::*;
+use crate::streams::*;
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// the file behind every stream OPEN made, for FILE-LENGTH
+static OPEN_FILES: Lazy<Mutex<HashMap<usize, PathBuf>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn file_error(name: &str, path: &str, e: impl std::fmt::Display) -> Sexpr {
+    make_condition("FILE-ERROR", format!("{} - {}: {}", name, path, e))
+}
+
+fn path_string(path: &Path) -> Sexpr {
+    Sexpr::String(path.display().to_string())
+}
+
+#[derive(Debug, PartialEq)]
+enum Direction {
+    Input,
+    Output,
+}
+
+// the keyword args of OPEN and WITH-OPEN-FILE
+#[derive(Debug, PartialEq)]
+struct OpenOptionsArgs {
+    direction: Direction,
+    // :supersede, :append or :error
+    if_exists: String,
+    // :error, :create or NIL
+    if_does_not_exist: Option<String>,
+}
+
+fn keyword(name: &str, v: &Sexpr) -> Result<Option<String>, Unwind> {
+    match v {
+        Sexpr::Symbol(s) if s.starts_with(':') => Ok(Some(s.clone())),
+        Sexpr::Nil => Ok(None),
+        _ => Err(Unwind::from(format!(
+            "{} - option values must be keywords",
+            name
+        ))),
+    }
+}
+
+fn open_options(name: &str, args: &[Sexpr]) -> Result<OpenOptionsArgs, Unwind> {
+    if !args.len().is_multiple_of(2) {
+        return Err(Unwind::from(format!(
+            "{} - odd number of keyword args",
+            name
+        )));
+    }
+
+    let mut direction: Direction = Direction::Input;
+    let mut if_exists: String = String::from(":SUPERSEDE");
+    let mut if_does_not_exist: Option<Option<String>> = None;
+    for pair in args.chunks(2) {
+        let value: Option<String> = keyword(name, &pair[1])?;
+        match (&pair[0], value.as_deref()) {
+            (Sexpr::Symbol(k), Some(":INPUT")) if k == ":DIRECTION" => direction = Direction::Input,
+            (Sexpr::Symbol(k), Some(":OUTPUT")) if k == ":DIRECTION" => {
+                direction = Direction::Output
+            }
+            (Sexpr::Symbol(k), Some(v @ (":SUPERSEDE" | ":APPEND" | ":ERROR")))
+                if k == ":IF-EXISTS" =>
+            {
+                if_exists = String::from(v)
+            }
+            (Sexpr::Symbol(k), v @ (None | Some(":ERROR" | ":CREATE")))
+                if k == ":IF-DOES-NOT-EXIST" =>
+            {
+                if_does_not_exist = Some(v.map(String::from))
+            }
+            (k, _) => {
+                return Err(Unwind::from(format!(
+                    "{} - bad option {} {}",
+                    name,
+                    sexpr_to_string(k),
+                    sexpr_to_string(&pair[1])
+                )))
+            }
+        }
+    }
+
+    // output creates missing files unless told otherwise, input doesn't
+    let if_does_not_exist: Option<String> = match if_does_not_exist {
+        Some(v) => v,
+        None if direction == Direction::Output => Some(String::from(":CREATE")),
+        None => Some(String::from(":ERROR")),
+    };
+    Ok(OpenOptionsArgs {
+        direction,
+        if_exists,
+        if_does_not_exist,
+    })
+}
+
+// opens path as a stream, Ok(None) when the file is missing and
+// :if-does-not-exist is NIL
+fn open_file(
+    name: &str,
+    path: &str,
+    options: &OpenOptionsArgs,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Option<usize>, Unwind> {
+    let exists: bool = Path::new(path).exists();
+    if !exists {
+        match options.if_does_not_exist.as_deref() {
+            None => return Ok(None),
+            Some(":ERROR") => {
+                return Err(signal_error(file_error(name, path, "no such file"), alist))
+            }
+            _ => {}
+        }
+    }
+
+    let stream: Stream = match options.direction {
+        Direction::Input => match File::open(path) {
+            Ok(f) => Stream::Input(Input::new(Source::Reader(Box::new(BufReader::new(f))))),
+            Err(e) => return Err(signal_error(file_error(name, path, e), alist)),
+        },
+        Direction::Output => {
+            if exists && options.if_exists == ":ERROR" {
+                return Err(signal_error(
+                    file_error(name, path, "file already exists"),
+                    alist,
+                ));
+            }
+            let mut open: OpenOptions = OpenOptions::new();
+            open.create(true);
+            if options.if_exists == ":APPEND" {
+                open.append(true);
+            } else {
+                open.write(true).truncate(true);
+            }
+            match open.open(path) {
+                Ok(f) => Stream::Sink(Box::new(f)),
+                Err(e) => return Err(signal_error(file_error(name, path, e), alist)),
+            }
+        }
+    };
+
+    let id: usize = open_stream(stream);
+    OPEN_FILES.lock().unwrap().insert(id, PathBuf::from(path));
+    Ok(Some(id))
+}
+
+// (open path :direction :input/:output :if-exists ... :if-does-not-exist ...)
+pub fn open(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let path: String = string_arg("open", args.first())?;
+    let options: OpenOptionsArgs = open_options("open", &args[1..])?;
+    match open_file("open", &path, &options, alist)? {
+        Some(id) => Ok(Sexpr::Stream(id)),
+        None => Ok(Sexpr::Nil),
+    }
+}
+
+fn close_file(id: usize) -> bool {
+    OPEN_FILES.lock().unwrap().remove(&id);
+    close_stream(id).is_some()
+}
+
+// (close stream) is T if the stream was open
+pub fn close(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [Sexpr::Stream(id)] if *id > STANDARD_INPUT => Ok(bool_sexpr(close_file(*id))),
+        [Sexpr::Stream(_)] => Err(Unwind::from("close - standard streams stay open")),
+        _ => Err(Unwind::from("close - arg must be a stream")),
+    }
+}
+
+// (with-open-file (var path options...) body...) closes the file however body exits
+pub fn with_open_file(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("with-open-file - args must be a list")),
+    };
+    let spec: Vec<Sexpr> = match args.first() {
+        Some(Sexpr::List(l)) => list_items(l).to_vec(),
+        _ => {
+            return Err(Unwind::from(
+                "with-open-file - first arg must be (var path options...)",
+            ))
+        }
+    };
+    let var: String = match spec.first() {
+        Some(Sexpr::Symbol(s)) => s.clone(),
+        _ => return Err(Unwind::from("with-open-file - var must be a symbol")),
+    };
+
+    let mut values: Vec<Sexpr> = Vec::new();
+    for arg in &spec[1..] {
+        values.push(evaluate(arg.clone(), alist)?);
+    }
+    let path: String = string_arg("with-open-file", values.first())?;
+    let options: OpenOptionsArgs = open_options("with-open-file", &values[1..])?;
+    let id: Option<usize> = open_file("with-open-file", &path, &options, alist)?;
+
+    let depth = alist.len();
+    alist.push((var, id.map(Sexpr::Stream).unwrap_or(Sexpr::Nil)));
+    let result = progn_forms(&args[1..], alist);
+    alist.truncate(depth);
+    if let Some(id) = id {
+        close_file(id);
+    }
+    result
+}
+
+// (probe-file path) is the full path of the file, or NIL if there is none
+pub fn probe_file(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let path: String = string_arg("probe-file", args.first())?;
+    match fs::canonicalize(&path) {
+        Ok(full) => Ok(path_string(&full)),
+        Err(_) => Ok(Sexpr::Nil),
+    }
+}
+
+pub fn delete_file(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let path: String = string_arg("delete-file", args.first())?;
+    match fs::remove_file(&path) {
+        Ok(_) => Ok(Sexpr::T),
+        Err(e) => Err(signal_error(file_error("delete-file", &path, e), alist)),
+    }
+}
+
+pub fn rename_file(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let from: String = string_arg("rename-file", args.first())?;
+    let to: String = string_arg("rename-file", args.get(1))?;
+    match fs::rename(&from, &to) {
+        Ok(_) => Ok(path_string(Path::new(&to))),
+        Err(e) => Err(signal_error(file_error("rename-file", &from, e), alist)),
+    }
+}
+
+// * matches any run of chars, everything else only itself
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let name = match name.strip_prefix(prefix) {
+                Some(n) => n,
+                None => return false,
+            };
+            (0..=name.len())
+                .filter(|i| name.is_char_boundary(*i))
+                .any(|i| wildcard_match(rest, &name[i..]))
+        }
+    }
+}
+
+// (directory "dir") lists dir, (directory "dir/*.lisp") only what matches
+pub fn directory(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let path: String = string_arg("directory", args.first())?;
+
+    let (dir, pattern): (PathBuf, String) = match Path::new(&path).file_name() {
+        Some(name) if name.to_string_lossy().contains('*') => (
+            Path::new(&path)
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or(Path::new("."))
+                .to_path_buf(),
+            name.to_string_lossy().to_string(),
+        ),
+        _ => (PathBuf::from(&path), String::from("*")),
+    };
+
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            return Err(signal_error(
+                file_error("directory", &dir.display().to_string(), e),
+                alist,
+            ))
+        }
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| wildcard_match(&pattern, &e.file_name().to_string_lossy()))
+        .map(|e| e.path())
+        .collect();
+    paths.sort();
+
+    if paths.is_empty() {
+        return Ok(Sexpr::Nil);
+    }
+    let mut list: Vec<Sexpr> = paths.iter().map(|p| path_string(p)).collect();
+    list.push(Sexpr::Nil);
+    Ok(Sexpr::List(list))
+}
+
+// (file-length stream-or-path) in bytes
+pub fn file_length(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let path: String = match eval_args(args, alist)?.first() {
+        Some(Sexpr::Stream(id)) => match OPEN_FILES.lock().unwrap().get(id) {
+            Some(p) => p.display().to_string(),
+            None => return Err(Unwind::from("file-length - stream is not a file")),
+        },
+        arg => string_arg("file-length", arg)?,
+    };
+    match fs::metadata(&path) {
+        Ok(m) => Ok(Sexpr::Integer(m.len() as i64)),
+        Err(e) => Err(signal_error(file_error("file-length", &path, e), alist)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+
+    fn string(s: &str) -> Sexpr {
+        Sexpr::String(String::from(s))
+    }
+
+    // a fresh directory for one test, named after it
+    fn test_dir(name: &str) -> String {
+        let dir: PathBuf =
+            std::env::temp_dir().join(format!("files-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.display().to_string()
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("*.lisp", "a.lisp"));
+        assert!(!wildcard_match("*.lisp", "a.lisp.bak"));
+        assert!(wildcard_match("a*b*c", "aXXbYc"));
+        assert!(!wildcard_match("a*b", "ba"));
+    }
+
+    #[test]
+    fn test_open_options() {
+        let k = |s: &str| Sexpr::Symbol(String::from(s));
+        assert_eq!(
+            open_options(
+                "open",
+                &[k(":DIRECTION"), k(":OUTPUT"), k(":IF-EXISTS"), k(":APPEND")]
+            ),
+            Ok(OpenOptionsArgs {
+                direction: Direction::Output,
+                if_exists: String::from(":APPEND"),
+                if_does_not_exist: Some(String::from(":CREATE")),
+            })
+        );
+        assert_eq!(
+            open_options("open", &[k(":IF-DOES-NOT-EXIST"), Sexpr::Nil]),
+            Ok(OpenOptionsArgs {
+                direction: Direction::Input,
+                if_exists: String::from(":SUPERSEDE"),
+                if_does_not_exist: None,
+            })
+        );
+        assert_eq!(
+            open_options("open", &[k(":DIRECTION"), k(":SIDEWAYS")]),
+            Err(Unwind::from("open - bad option :DIRECTION :SIDEWAYS"))
+        );
+    }
+
+    #[test]
+    fn test_files() {
+        let dir: String = test_dir("files");
+        let file: String = format!("{}/out.txt", dir);
+        let renamed: String = format!("{}/renamed.txt", dir);
+
+        assert_eq!(
+            run(&format!("(with-open-file (s \"{}\" :direction :output) (princ \"hello\" s) (terpri s) (prin1 '(1 2) s))", file)),
+            Ok(Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(2), Sexpr::Nil]))
+        );
+        assert_eq!(
+            run(&format!("(file-length \"{}\")", file)),
+            Ok(Sexpr::Integer(11))
+        );
+        assert_eq!(
+            run(&format!(
+                "(with-open-file (s \"{}\") (read-line s) (read s))",
+                file
+            )),
+            Ok(Sexpr::List(vec![
+                Sexpr::Integer(1),
+                Sexpr::Integer(2),
+                Sexpr::Nil
+            ]))
+        );
+
+        run(&format!(
+            "(with-open-file (s \"{}\" :direction :output :if-exists :append) (princ 3 s))",
+            file
+        ))
+        .unwrap();
+        assert_eq!(
+            run(&format!(
+                "(progn (setq files-test-s (open \"{}\")) (file-length files-test-s))",
+                file
+            )),
+            Ok(Sexpr::Integer(12))
+        );
+        assert_eq!(run("(close files-test-s)"), Ok(Sexpr::T));
+        assert_eq!(run("(close files-test-s)"), Ok(Sexpr::Nil));
+
+        assert_eq!(
+            run(&format!("(rename-file \"{}\" \"{}\")", file, renamed)),
+            Ok(string(&renamed))
+        );
+        assert_eq!(run(&format!("(probe-file \"{}\")", file)), Ok(Sexpr::Nil));
+        assert!(matches!(
+            run(&format!("(probe-file \"{}\")", renamed)),
+            Ok(Sexpr::String(_))
+        ));
+
+        fs::write(format!("{}/a.lisp", dir), "").unwrap();
+        let listed = run(&format!("(directory \"{}/*.lisp\")", dir)).unwrap();
+        let a: PathBuf = PathBuf::from(format!("{}/a.lisp", dir));
+        assert_eq!(listed, Sexpr::List(vec![path_string(&a), Sexpr::Nil]));
+
+        assert_eq!(run(&format!("(delete-file \"{}\")", renamed)), Ok(Sexpr::T));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_errors() {
+        let dir: String = test_dir("errors");
+        let missing: String = format!("{}/missing.txt", dir);
+
+        assert_eq!(
+            run(&format!(
+                "(handler-case (open \"{}\") (file-error (c) 'file-error))",
+                missing
+            )),
+            Ok(Sexpr::Symbol(String::from("FILE-ERROR")))
+        );
+        assert_eq!(
+            run(&format!(
+                "(handler-case (delete-file \"{}\") (error () 'error))",
+                missing
+            )),
+            Ok(Sexpr::Symbol(String::from("ERROR")))
+        );
+        assert_eq!(
+            run(&format!(
+                "(with-open-file (s \"{}\" :if-does-not-exist nil) s)",
+                missing
+            )),
+            Ok(Sexpr::Nil)
+        );
+        assert!(run(&format!(
+            "(open \"{}\" :direction :output :if-exists :error)",
+            dir
+        ))
+        .is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,9 +1,17 @@
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{ToPrimitive, Zero};
 use std::{iter::Peekable, vec::IntoIter};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Token {
     Integer(i64),
+    // an integer literal too long for i64
+    Bignum(BigInt),
+    // a ratio literal like 1/3, in lowest terms
+    Ratio(BigRational),
     Float(f64),
+    Char(char),
     String(String),
     Symbol(String),
     LParen,
@@ -13,7 +21,10 @@ pub enum Token {
 pub fn token_to_string(v: &Token) -> String {
     match v {
         Token::Integer(i) => i.to_string(),
+        Token::Bignum(b) => b.to_string(),
+        Token::Ratio(r) => r.to_string(),
         Token::Float(f) => f.to_string(),
+        Token::Char(c) => c.to_string(),
         Token::String(s) => s.to_string(),
         Token::Symbol(s) => s.to_string(),
         Token::LParen => String::from("("),
@@ -21,65 +32,301 @@ pub fn token_to_string(v: &Token) -> String {
     }
 }
 
-fn space_inputs(input: &String) -> String {
-    input
-        .replace("(", " ( ")
-        .replace(")", " ) ")
-        .replace("'", " ' ")
+// splits input into the parts outside of string and character literals and
+// the literals themselves, quotes included, so that neither spacing nor case
+// changes what is inside a string or which char a #\\ names
+fn string_parts(input: &str) -> Vec<(bool, String)> {
+    let mut parts: Vec<(bool, String)> = Vec::new();
+    let mut part = String::new();
+    let mut in_string: bool = false;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_string => {
+                part.push(c);
+                parts.push((true, std::mem::take(&mut part)));
+                in_string = false;
+            }
+            '"' => {
+                parts.push((false, std::mem::take(&mut part)));
+                part.push(c);
+                in_string = true;
+            }
+            // an escaped char never ends the string
+            '\\' if in_string => {
+                part.push(c);
+                if let Some(escaped) = chars.next() {
+                    part.push(escaped);
+                }
+            }
+            // #\\ takes the char after it whatever it is, and a name runs on
+            // to the end of the token
+            '#' if !in_string && chars.peek() == Some(&'\\') => {
+                parts.push((false, std::mem::take(&mut part)));
+                part.push(c);
+                part.push(chars.next().unwrap());
+                if let Some(first) = chars.next() {
+                    part.push(first);
+                }
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || "()'\"".contains(next) {
+                        break;
+                    }
+                    part.push(next);
+                    chars.next();
+                }
+                parts.push((true, std::mem::take(&mut part)));
+            }
+            _ => part.push(c),
+        }
+    }
+    parts.push((in_string, part));
+    parts
+}
+
+fn space_inputs(input: &str) -> String {
+    string_parts(input)
+        .into_iter()
+        .map(|(string, part)| {
+            if string {
+                // keeps the literal apart from whatever comes next
+                format!(" {} ", part)
+            } else {
+                part.replace("(", " ( ")
+                    .replace(")", " ) ")
+                    .replace("'", " ' ")
+                    .replace("# ' ", " #' ")
+            }
+        })
+        .collect()
 }
 fn space_separate_inputs(input: &str) -> Vec<String> {
-    let inputs: Vec<&str> = input.split_whitespace().collect();
     let mut outputs: Vec<String> = Vec::new();
-    for i in inputs.iter() {
-        if i.starts_with("\"") && i.ends_with("\"") {
-            outputs.push(i.to_string());
+    for (string, part) in string_parts(input) {
+        if string {
+            outputs.push(part);
         } else {
-            outputs.push(i.to_uppercase());
+            outputs.extend(part.split_whitespace().map(|i| i.to_uppercase()));
         }
     }
     outputs
 }
 
-fn get_datatype(input: &String) -> Token {
-    match input.as_str() {
-        "(" => Token::LParen,
-        ")" => Token::RParen,
-        _ => match input.trim().parse() {
-            Ok(i) => Token::Integer(i),
-            Err(_) => match input.trim().parse::<f64>() {
-                Ok(f) => Token::Float(f),
-                Err(_) => {
-                    if input.starts_with("\"") && input.ends_with("\"") {
-                        Token::String(input.to_string())
-                    } else {
-                        Token::Symbol(input.to_string())
+// an optionally signed run of digits in radix
+fn integer_digits(input: &str, radix: u32) -> Option<BigInt> {
+    let (negative, digits): (bool, &str) = match input.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, input.strip_prefix('+').unwrap_or(input)),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    let i: BigInt = BigInt::parse_bytes(digits.as_bytes(), radix)?;
+    Some(if negative { -i } else { i })
+}
+
+// n or n/d in radix, ratios in lowest terms so 4/2 is the integer 2, a zero
+// denominator is an error rather than a symbol
+fn rational_token(input: &str, radix: u32) -> Result<Option<Token>, String> {
+    let r: BigRational = match input.split_once('/') {
+        Some((_, d)) if d.starts_with(['+', '-']) => return Ok(None),
+        Some((n, d)) => match (integer_digits(n, radix), integer_digits(d, radix)) {
+            (Some(_), Some(d)) if d.is_zero() => {
+                return Err(format!("number_token - {} has a zero denominator", input))
+            }
+            (Some(n), Some(d)) => BigRational::new(n, d),
+            _ => return Ok(None),
+        },
+        None => match integer_digits(input, radix) {
+            Some(i) => BigRational::from_integer(i),
+            None => return Ok(None),
+        },
+    };
+    Ok(Some(if !r.is_integer() {
+        Token::Ratio(r)
+    } else {
+        match r.to_integer().to_i64() {
+            Some(i) => Token::Integer(i),
+            None => Token::Bignum(r.to_integer()),
+        }
+    }))
+}
+
+// digits with a point and digits after it, an exponent or both, the
+// exponent marked by any of e, s, f, d or l since there is one float type
+fn float_token(input: &str) -> Result<Option<Token>, String> {
+    let (mantissa, exponent): (&str, Option<&str>) = match input.find(['E', 'S', 'F', 'D', 'L']) {
+        Some(i) => (&input[..i], Some(&input[i + 1..])),
+        None => (input, None),
+    };
+    let (negative, unsigned): (bool, &str) = match mantissa.strip_prefix('-') {
+        Some(m) => (true, m),
+        None => (false, mantissa.strip_prefix('+').unwrap_or(mantissa)),
+    };
+    let (whole, fraction): (&str, &str) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if (whole.is_empty() && fraction.is_empty())
+        || !whole.chars().all(|c| c.is_ascii_digit())
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+    {
+        return Ok(None);
+    }
+    // without an exponent there have to be digits after the point, 1. is an integer
+    if exponent.is_none() && fraction.is_empty() {
+        return Ok(None);
+    }
+    let exponent: &str = match exponent {
+        Some(e) if integer_digits(e, 10).is_some() => e,
+        Some(_) => return Ok(None),
+        None => "0",
+    };
+
+    let text: String = format!(
+        "{}{}.{}e{}",
+        if negative { "-" } else { "" },
+        if whole.is_empty() { "0" } else { whole },
+        if fraction.is_empty() { "0" } else { fraction },
+        exponent
+    );
+    // 1E400 is a number, just not one a float can hold
+    match text.parse::<f64>() {
+        Ok(f) if f.is_finite() => Ok(Some(Token::Float(f))),
+        Ok(_) => Err(format!("number_token - {} is too large for a float", input)),
+        Err(_) => Ok(None),
+    }
+}
+
+// an integer or ratio after #x, #b, #o or #nr for radix n, without the #,
+// anything else after a # such as #' is not a number
+fn radix_token(rest: &str) -> Result<Option<Token>, String> {
+    let (radix, digits): (u32, &str) = match rest.chars().next() {
+        Some('X') => (16, &rest[1..]),
+        Some('B') => (2, &rest[1..]),
+        Some('O') => (8, &rest[1..]),
+        _ => match rest.split_once('R') {
+            Some((radix, digits))
+                if !radix.is_empty() && radix.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                match radix.parse().ok().filter(|r| (2..=36).contains(r)) {
+                    Some(radix) => (radix, digits),
+                    None => {
+                        return Err(format!(
+                            "number_token - #{} has a radix outside 2 to 36",
+                            rest
+                        ))
                     }
                 }
-            },
+            }
+            _ => return Ok(None),
         },
+    };
+    match rational_token(digits, radix)? {
+        Some(number) => Ok(Some(number)),
+        None => Err(format!(
+            "number_token - #{} is not a number in radix {}",
+            rest, radix
+        )),
     }
 }
 
+// Common Lisp number syntax, integers and ratios in decimal or after #x, #b,
+// #o or #nr for radix n, and floats; anything else is a symbol
+fn number_token(input: &str) -> Result<Option<Token>, String> {
+    let input: String = input.to_ascii_uppercase();
+    if let Some(rest) = input.strip_prefix('#') {
+        return radix_token(rest);
+    }
+
+    // a trailing point only says the integer is decimal
+    let decimal: &str = match input.strip_suffix('.') {
+        Some(integer) if !integer.contains(['/', '.']) => integer,
+        _ => &input,
+    };
+    match rational_token(decimal, 10)? {
+        Some(number) => Ok(Some(number)),
+        None => float_token(&input),
+    }
+}
+
+// the char after #\\, or the one it names, None for an unknown name
+pub fn char_named(name: &str) -> Option<char> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(c);
+    }
+    match name.to_ascii_uppercase().as_str() {
+        "SPACE" => Some(' '),
+        "NEWLINE" | "LINEFEED" => Some('\n'),
+        "TAB" => Some('\t'),
+        "RETURN" => Some('\r'),
+        "PAGE" => Some('\x0c'),
+        "BACKSPACE" => Some('\x08'),
+        "RUBOUT" | "DELETE" => Some('\x7f'),
+        "ESCAPE" => Some('\x1b'),
+        "NULL" | "NUL" => Some('\0'),
+        upper => {
+            let code: &str = upper.strip_prefix("U+")?;
+            char::from_u32(u32::from_str_radix(code, 16).ok()?)
+        }
+    }
+}
+
+// the chars of a string literal between its quotes, a \ taking the char
+// after it as it is
+fn unescape(literal: &str) -> String {
+    let mut s = String::new();
+    let mut chars = literal.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => s.extend(chars.next()),
+            c => s.push(c),
+        }
+    }
+    s
+}
+
+fn get_datatype(input: &String) -> Result<Token, String> {
+    if let Some(name) = input.strip_prefix("#\\") {
+        return match char_named(name) {
+            Some(c) => Ok(Token::Char(c)),
+            None => Err(format!("unknown character name {}", name)),
+        };
+    }
+    Ok(match input.as_str() {
+        "(" => Token::LParen,
+        ")" => Token::RParen,
+        _ => match number_token(input.trim())? {
+            Some(number) => number,
+            None => {
+                if input.len() > 1 && input.starts_with('"') && input.ends_with('"') {
+                    Token::String(unescape(&input[1..input.len() - 1]))
+                } else {
+                    Token::Symbol(input.to_string())
+                }
+            }
+        },
+    })
+}
+
 // only one that actually moves the pointer for the iterator
 fn tokenize_input(
     iter: &mut Peekable<IntoIter<String>>,
     tokens: &mut Vec<Token>,
 ) -> Result<Vec<Token>, String> {
-    if iter.peek() == None {
+    if iter.peek().is_none() {
         return Err(String::from(
             "tokenize_input - input is required to tokenize",
         ));
     }
     let data = iter.next().unwrap();
-    match get_datatype(&data) {
+    match get_datatype(&data)? {
         Token::LParen => {
             tokens.push(Token::LParen);
-            let _ = tokenize_list(iter, tokens);
+            tokenize_list(iter, tokens)?;
         }
         Token::Symbol(s) => match s.as_str() {
             "." => {} // needs to do something
             "'" => {
-                if iter.peek() == None || iter.peek() == Some(&String::from(".")) {
+                if iter.peek().is_none() || iter.peek() == Some(&String::from(".")) {
                     return Err(String::from(
                         "tokenize_input - ' must be followed by an atom or list",
                     ));
@@ -87,7 +334,20 @@ fn tokenize_input(
 
                 tokens.push(Token::LParen);
                 tokens.push(Token::Symbol(String::from("QUOTE")));
-                let _ = tokenize_input(iter, tokens);
+                tokenize_input(iter, tokens)?;
+                tokens.push(Token::Symbol(String::from("NIL")));
+                tokens.push(Token::RParen);
+            }
+            "#'" => {
+                if iter.peek().is_none() || iter.peek() == Some(&String::from(".")) {
+                    return Err(String::from(
+                        "tokenize_input - #' must be followed by a symbol or lambda",
+                    ));
+                }
+
+                tokens.push(Token::LParen);
+                tokens.push(Token::Symbol(String::from("FUNCTION")));
+                tokenize_input(iter, tokens)?;
                 tokens.push(Token::Symbol(String::from("NIL")));
                 tokens.push(Token::RParen);
             }
@@ -102,23 +362,60 @@ fn tokenize_list(
     iter: &mut Peekable<IntoIter<String>>,
     tokens: &mut Vec<Token>,
 ) -> Result<Vec<Token>, String> {
-    while iter.peek() != None {
+    while iter.peek().is_some() {
         let part = iter.peek().unwrap();
-        match get_datatype(part) {
+        match get_datatype(part)? {
             Token::RParen => {
                 iter.next();
                 tokens.push(Token::Symbol(String::from("NIL")));
                 tokens.push(Token::RParen);
                 return Ok(tokens.to_vec());
             }
-            _ => match tokenize_input(iter, tokens) {
-                Err(e) => return Err(e),
-                Ok(_) => {} // returned Vec<Token> should be the same as tokens
-            },
+            Token::Symbol(s) if s == "." => {
+                iter.next();
+                tokenize_dotted_tail(iter, tokens)?;
+                return Ok(tokens.to_vec());
+            }
+            _ => {
+                // returned Vec<Token> should be the same as tokens
+                tokenize_input(iter, tokens)?;
+            }
         }
     }
     Ok(tokens.to_vec())
 }
+// the form after the . of a dotted list takes the place of the NIL that ends
+// a proper list, a list there is spliced in so (1 . (2 3)) is (1 2 3)
+fn tokenize_dotted_tail(
+    iter: &mut Peekable<IntoIter<String>>,
+    tokens: &mut Vec<Token>,
+) -> Result<(), String> {
+    if tokens.last() == Some(&Token::LParen) {
+        return Err(String::from(
+            "tokenize_list - . must follow an atom or list",
+        ));
+    }
+    if iter.peek().is_none() || iter.peek() == Some(&String::from(")")) {
+        return Err(String::from(
+            "tokenize_list - . must be followed by an atom or list",
+        ));
+    }
+    let mut tail: Vec<Token> = Vec::new();
+    tokenize_input(iter, &mut tail)?;
+    if tail.first() == Some(&Token::LParen) {
+        // the tail's own ) closes the dotted list
+        tokens.extend(tail.into_iter().skip(1));
+    } else {
+        tokens.extend(tail);
+        tokens.push(Token::RParen);
+    }
+    match iter.next() {
+        Some(p) if p == ")" => Ok(()),
+        _ => Err(String::from(
+            "tokenize_list - the atom or list after . must be followed by )",
+        )),
+    }
+}
 fn tokenize_inputs(input: Vec<String>) -> Result<Vec<Token>, String> {
     let mut tokens: Vec<Token> = Vec::new();
     let mut iter: Peekable<IntoIter<String>> = input.into_iter().peekable();
@@ -126,9 +423,15 @@ fn tokenize_inputs(input: Vec<String>) -> Result<Vec<Token>, String> {
     tokenize_input(&mut iter, &mut tokens)
 }
 
-pub fn tokenize(input: &String) -> Result<Vec<Token>, String> {
-    let spaced_input = space_inputs(&input);
+pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let spaced_input = space_inputs(input);
     let space_separated_input: Vec<String> = space_separate_inputs(&spaced_input);
+    if let Some(unknown) = space_separated_input
+        .iter()
+        .find(|i| i.starts_with("#\\") && char_named(&i[2..]).is_none())
+    {
+        return Err(format!("tokenize - unknown character name {}", unknown));
+    }
 
     tokenize_inputs(space_separated_input)
 }
@@ -158,6 +461,10 @@ mod tests {
         input = String::from("'(1)");
         expected = String::from(" '  ( 1 ) ");
         assert_eq!(space_inputs(&input), expected);
+
+        input = String::from("#'car");
+        expected = String::from(" #' car");
+        assert_eq!(space_inputs(&input), expected);
     }
 
     #[test]
@@ -178,6 +485,20 @@ mod tests {
         input = "";
         expected = Vec::new();
         assert_eq!(space_separate_inputs(input), expected);
+
+        // strings keep their spaces, parens and case
+        input = " ( print  \"a (b)  C\" ) ";
+        expected = vec![
+            String::from("("),
+            String::from("PRINT"),
+            String::from("\"a (b)  C\""),
+            String::from(")"),
+        ];
+        assert_eq!(space_separate_inputs(&space_inputs(input)), expected);
+
+        input = "\"say \\\"hi\\\"\"x";
+        expected = vec![String::from("\"say \\\"hi\\\"\""), String::from("X")];
+        assert_eq!(space_separate_inputs(&space_inputs(input)), expected);
     }
 
     fn compare_token_vectors(result: Vec<Token>, expected: Vec<Token>) -> bool {
@@ -197,22 +518,121 @@ mod tests {
     #[test]
     fn test_get_datatype() {
         let mut input = String::from("(");
-        assert_eq!(get_datatype(&input), Token::LParen);
+        assert_eq!(get_datatype(&input), Ok(Token::LParen));
 
         input = String::from(")");
-        assert_eq!(get_datatype(&input), Token::RParen);
+        assert_eq!(get_datatype(&input), Ok(Token::RParen));
 
         input = String::from("1");
-        assert_eq!(get_datatype(&input), Token::Integer(1));
+        assert_eq!(get_datatype(&input), Ok(Token::Integer(1)));
 
         input = String::from("234.1");
-        assert_eq!(get_datatype(&input), Token::Float(234.1));
+        assert_eq!(get_datatype(&input), Ok(Token::Float(234.1)));
+
+        input = String::from("-123456789012345678901234567890");
+        assert_eq!(
+            get_datatype(&input),
+            Ok(Token::Bignum(
+                "-123456789012345678901234567890".parse().unwrap()
+            ))
+        );
 
         input = String::from("\"hello\"");
         assert_eq!(
             get_datatype(&input),
-            Token::String(String::from("\"hello\""))
+            Ok(Token::String(String::from("hello")))
+        );
+
+        input = String::from("\"say \\\"hi\\\" \\\\\"");
+        assert_eq!(
+            get_datatype(&input),
+            Ok(Token::String(String::from("say \"hi\" \\")))
+        );
+
+        input = String::from("#\\Space");
+        assert_eq!(get_datatype(&input), Ok(Token::Char(' ')));
+
+        input = String::from("#\\Foo");
+        assert_eq!(
+            get_datatype(&input),
+            Err(String::from("unknown character name Foo"))
+        );
+    }
+
+    #[test]
+    fn test_number_token() {
+        let ratio = |n: i64, d: i64| {
+            Ok(Some(Token::Ratio(BigRational::new(
+                BigInt::from(n),
+                BigInt::from(d),
+            ))))
+        };
+        assert_eq!(number_token("#XFF"), Ok(Some(Token::Integer(255))));
+        assert_eq!(number_token("#xff"), Ok(Some(Token::Integer(255))));
+        assert_eq!(number_token("#B1010"), Ok(Some(Token::Integer(10))));
+        assert_eq!(number_token("#O17"), Ok(Some(Token::Integer(15))));
+        assert_eq!(number_token("#36RZZ"), Ok(Some(Token::Integer(1295))));
+        assert_eq!(number_token("#X-1/A"), ratio(-1, 10));
+        // radix syntax with digits or a radix that don't fit is an error
+        assert_eq!(
+            number_token("#B102"),
+            Err(String::from(
+                "number_token - #B102 is not a number in radix 2"
+            ))
+        );
+        assert_eq!(
+            number_token("#xZZ"),
+            Err(String::from(
+                "number_token - #XZZ is not a number in radix 16"
+            ))
+        );
+        assert_eq!(
+            number_token("#37R1"),
+            Err(String::from(
+                "number_token - #37R1 has a radix outside 2 to 36"
+            ))
+        );
+        assert_eq!(
+            number_token("#X1/0"),
+            Err(String::from("number_token - 1/0 has a zero denominator"))
+        );
+        assert_eq!(number_token("#'"), Ok(None));
+        assert_eq!(number_token("12."), Ok(Some(Token::Integer(12))));
+        assert_eq!(number_token("+5"), Ok(Some(Token::Integer(5))));
+        assert_eq!(number_token("3/6"), ratio(1, 2));
+        assert_eq!(
+            number_token("1/0"),
+            Err(String::from("number_token - 1/0 has a zero denominator"))
+        );
+        assert_eq!(number_token("A/0"), Ok(None));
+        assert_eq!(number_token("1/-2"), Ok(None));
+
+        assert_eq!(number_token("1D0"), Ok(Some(Token::Float(1.0))));
+        assert_eq!(number_token("1.5E-3"), Ok(Some(Token::Float(0.0015))));
+        assert_eq!(number_token("-.5"), Ok(Some(Token::Float(-0.5))));
+        assert_eq!(number_token("2.F2"), Ok(Some(Token::Float(200.0))));
+        assert_eq!(number_token("1.5L+1"), Ok(Some(Token::Float(15.0))));
+        assert_eq!(number_token("1E"), Ok(None));
+        // too large for a float is an error, not a symbol
+        assert_eq!(
+            number_token("1E400"),
+            Err(String::from(
+                "number_token - 1E400 is too large for a float"
+            ))
+        );
+        assert_eq!(
+            number_token("-1D400"),
+            Err(String::from(
+                "number_token - -1D400 is too large for a float"
+            ))
         );
+        assert_eq!(number_token("1E-400"), Ok(Some(Token::Float(0.0))));
+        // Rust's float spellings are symbols in Lisp
+        assert_eq!(number_token("INF"), Ok(None));
+        assert_eq!(number_token("NAN"), Ok(None));
+        assert_eq!(number_token("1+"), Ok(None));
+        assert_eq!(number_token("."), Ok(None));
+        assert_eq!(number_token("-"), Ok(None));
     }
 
     #[test]
@@ -352,7 +772,6 @@ mod tests {
         ];
         assert_eq!(result.unwrap(), expected);
 
-        /*
         input = vec![
             String::from("("),
             String::from("1.1"),
@@ -369,6 +788,26 @@ mod tests {
         ];
         assert!(compare_token_vectors(result.unwrap(), expected));
 
+        // a list after the dot continues the dotted list
+        input = vec![
+            String::from("("),
+            String::from("1"),
+            String::from("."),
+            String::from("("),
+            String::from("2"),
+            String::from(")"),
+            String::from(")"),
+        ];
+        result = tokenize_inputs(input);
+        expected = vec![
+            Token::LParen,
+            Token::Integer(1),
+            Token::Integer(2),
+            Token::Symbol(String::from("NIL")),
+            Token::RParen,
+        ];
+        assert_eq!(result.unwrap(), expected);
+
         input = vec![
             String::from("("),
             String::from("1.1"),
@@ -379,7 +818,7 @@ mod tests {
         assert_eq!(
             result,
             Err(String::from(
-                "tokenize_inputs: . must be followed by an atom"
+                "tokenize_list - . must be followed by an atom or list"
             ))
         );
 
@@ -394,9 +833,24 @@ mod tests {
         result = tokenize_inputs(input);
         assert_eq!(
             result,
-            Err(String::from("tokenize_inputs - atom must be followed by )"))
+            Err(String::from(
+                "tokenize_list - the atom or list after . must be followed by )"
+            ))
+        );
+
+        input = vec![
+            String::from("("),
+            String::from("."),
+            String::from("1"),
+            String::from(")"),
+        ];
+        result = tokenize_inputs(input);
+        assert_eq!(
+            result,
+            Err(String::from(
+                "tokenize_list - . must follow an atom or list"
+            ))
         );
-        */
     }
 
     #[test]
@@ -446,7 +900,18 @@ mod tests {
 
         input = String::from("\"hello\"");
         result = tokenize(&input);
-        expected = vec![Token::String(String::from("\"hello\""))];
+        expected = vec![Token::String(String::from("hello"))];
+        assert!(compare_token_vectors(result.unwrap(), expected));
+
+        input = String::from("#'car");
+        result = tokenize(&input);
+        expected = vec![
+            Token::LParen,
+            Token::Symbol(String::from("FUNCTION")),
+            Token::Symbol(String::from("CAR")),
+            Token::Symbol(String::from("NIL")),
+            Token::RParen,
+        ];
         assert!(compare_token_vectors(result.unwrap(), expected));
     }
 }
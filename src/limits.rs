@@ -0,0 +1,319 @@
+use crate::eval::*;
+use crate::parse::*;
+use crate::profiler::*;
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// what an evaluation may use before it is stopped, None means no limit
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Limits {
+    // calls to evaluate
+    pub max_steps: Option<u64>,
+    // evaluate calls nested in each other, which is what recursion costs,
+    // None is DEFAULT_MAX_DEPTH
+    pub max_depth: Option<usize>,
+    // heap allocations, every cons, string and binding is at least one
+    pub max_allocations: Option<u64>,
+    pub timeout: Option<Duration>,
+}
+
+impl Limits {
+    pub fn is_unlimited(&self) -> bool {
+        *self == Limits::default()
+    }
+}
+
+// the limit an evaluation ran into
+#[derive(Clone, Debug, PartialEq)]
+pub enum Limit {
+    Steps(u64),
+    Depth(usize),
+    Allocations(u64),
+    Timeout(Duration),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Limit::Steps(n) => write!(f, "step limit of {} reached", n),
+            Limit::Depth(n) => write!(f, "depth limit of {} reached", n),
+            Limit::Allocations(n) => write!(f, "allocation limit of {} reached", n),
+            Limit::Timeout(d) => write!(f, "timeout of {}ms reached", d.as_millis()),
+        }
+    }
+}
+
+struct Budget {
+    limits: Limits,
+    steps: u64,
+    depth: usize,
+    allocations: u64,
+    deadline: Option<Instant>,
+}
+
+impl Budget {
+    // the first limit this budget has reached, if any
+    fn exhausted(&self) -> Option<Limit> {
+        match &self.limits {
+            Limits {
+                max_steps: Some(n), ..
+            } if self.steps >= *n => Some(Limit::Steps(*n)),
+            Limits {
+                max_depth: Some(n), ..
+            } if self.depth >= *n => Some(Limit::Depth(*n)),
+            Limits {
+                max_allocations: Some(n),
+                ..
+            } if allocations() - self.allocations >= *n => Some(Limit::Allocations(*n)),
+            Limits {
+                timeout: Some(d), ..
+            } if self.deadline.is_some_and(|t| Instant::now() >= t) => Some(Limit::Timeout(*d)),
+            _ => None,
+        }
+    }
+
+    fn charge_step(&mut self) -> Result<(), Unwind> {
+        if let Some(limit) = self.exhausted() {
+            return Err(Unwind::ResourceExhausted(limit));
+        }
+        self.steps += 1;
+        Ok(())
+    }
+}
+
+// how deep evaluate can nest when no --max-depth says otherwise, evaluate
+// grows the stack as it goes so this is what stops runaway recursion
+pub const DEFAULT_MAX_DEPTH: usize = 10_000;
+
+// the stack left when evaluate grows it, and how much it grows by
+const RED_ZONE: usize = 256 * 1024;
+const STACK_SEGMENT: usize = 4 * 1024 * 1024;
+
+thread_local! {
+    static BUDGET: RefCell<Option<Budget>> = const { RefCell::new(None) };
+    // evaluate calls nested right now, counted with or without a budget
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+// called by evaluate before it does anything, every Ok is matched by a
+// leave_evaluate once the form is done
+pub fn enter_evaluate() -> Result<(), Unwind> {
+    let depth: usize = DEPTH.with(|d| d.get());
+    BUDGET.with(|b| match b.borrow_mut().as_mut() {
+        Some(budget) => {
+            budget.charge_step()?;
+            budget.depth += 1;
+            Ok(())
+        }
+        // runaway recursion ends even when nothing is limited
+        None if depth >= DEFAULT_MAX_DEPTH => {
+            Err(Unwind::ResourceExhausted(Limit::Depth(DEFAULT_MAX_DEPTH)))
+        }
+        None => Ok(()),
+    })?;
+    DEPTH.with(|d| d.set(depth + 1));
+    Ok(())
+}
+
+// called by loops on every iteration, one that evaluates nothing still
+// costs a step and still runs into the deadline
+pub fn loop_step() -> Result<(), Unwind> {
+    BUDGET.with(|b| match b.borrow_mut().as_mut() {
+        Some(budget) => budget.charge_step(),
+        None => Ok(()),
+    })
+}
+
+pub fn leave_evaluate() {
+    DEPTH.with(|d| d.set(d.get().saturating_sub(1)));
+    BUDGET.with(|b| {
+        if let Some(budget) = b.borrow_mut().as_mut() {
+            budget.depth = budget.depth.saturating_sub(1);
+        }
+    });
+}
+
+// runs f on a new stack segment when the thread's stack is running out, so
+// that only the depth limits end a deep recursion
+pub fn grow_stack<T>(f: impl FnOnce() -> T) -> T {
+    stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, f)
+}
+
+// evaluates v within limits, so that untrusted code can only end in a value
+// or an error, the host decides what to do with ResourceExhausted
+pub fn evaluate_with_limits(
+    v: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+    limits: &Limits,
+) -> Result<Sexpr, Unwind> {
+    if limits.is_unlimited() {
+        return evaluate(v, alist);
+    }
+
+    let budget = Budget {
+        limits: Limits {
+            max_depth: limits.max_depth.or(Some(DEFAULT_MAX_DEPTH)),
+            ..limits.clone()
+        },
+        steps: 0,
+        depth: 0,
+        allocations: allocations(),
+        deadline: limits.timeout.map(|d| Instant::now() + d),
+    };
+    let outer: Option<Budget> = BUDGET.with(|b| b.replace(Some(budget)));
+    let result = evaluate(v, alist);
+    BUDGET.with(|b| b.replace(outer));
+    result
+}
+
+fn flag_value<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    match value.map(|v| v.parse()) {
+        Some(Ok(n)) => Ok(n),
+        _ => Err(format!("{} needs a number", flag)),
+    }
+}
+
+// the limits given on the command line, --max-steps N --max-depth N
+// --max-allocations N --timeout MS
+pub fn parse_limits(args: &[String]) -> Result<Limits, String> {
+    let mut limits: Limits = Limits::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--max-steps" => limits.max_steps = Some(flag_value(flag, args.next())?),
+            "--max-depth" => limits.max_depth = Some(flag_value(flag, args.next())?),
+            "--max-allocations" => limits.max_allocations = Some(flag_value(flag, args.next())?),
+            "--timeout" => {
+                limits.timeout = Some(Duration::from_millis(flag_value(flag, args.next())?))
+            }
+            _ => return Err(format!("unknown option {}", flag)),
+        }
+    }
+    Ok(limits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::*;
+    use crate::test::*;
+
+    fn run_limited(input: &str, limits: &Limits) -> Result<Sexpr, Unwind> {
+        let mut tokens: Vec<Token> = tokenize(input)?;
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        evaluate_with_limits(parse(&mut tokens), &mut alist, limits)
+    }
+
+    #[test]
+    fn test_limits() {
+        let steps = Limits {
+            max_steps: Some(1000),
+            ..Limits::default()
+        };
+        assert_eq!(
+            run_limited("(loop (setq limits-test 1))", &steps),
+            Err(Unwind::ResourceExhausted(Limit::Steps(1000)))
+        );
+        assert_eq!(run_limited("(+ 1 2)", &steps), Ok(Sexpr::Integer(3)));
+
+        let depth = Limits {
+            max_depth: Some(100),
+            ..Limits::default()
+        };
+        run_limited(
+            "(defun limits-test-forever (n) (limits-test-forever n))",
+            &depth,
+        )
+        .unwrap();
+        assert_eq!(
+            run_limited("(limits-test-forever 1)", &depth),
+            Err(Unwind::ResourceExhausted(Limit::Depth(100)))
+        );
+
+        // far deeper than the native stack of a test thread goes
+        let deep = Limits {
+            max_depth: Some(5_000),
+            ..Limits::default()
+        };
+        run_limited(
+            "(defun limits-test-deep (n) (+ 1 (limits-test-deep n)))",
+            &deep,
+        )
+        .unwrap();
+        assert_eq!(
+            run_limited("(limits-test-deep 1)", &deep),
+            Err(Unwind::ResourceExhausted(Limit::Depth(5_000)))
+        );
+        // and nothing limited still stops at the default depth
+        assert_eq!(
+            run("(limits-test-deep 1)"),
+            Err(Unwind::ResourceExhausted(Limit::Depth(DEFAULT_MAX_DEPTH)))
+        );
+
+        let allocations = Limits {
+            max_allocations: Some(10000),
+            ..Limits::default()
+        };
+        assert_eq!(
+            run_limited("(loop (setq limits-test 1))", &allocations),
+            Err(Unwind::ResourceExhausted(Limit::Allocations(10000)))
+        );
+
+        let timeout = Limits {
+            timeout: Some(Duration::from_millis(10)),
+            ..Limits::default()
+        };
+        assert_eq!(
+            run_limited("(loop (setq limits-test 1))", &timeout),
+            Err(Unwind::ResourceExhausted(Limit::Timeout(
+                Duration::from_millis(10)
+            )))
+        );
+
+        // loops that evaluate nothing still use up steps and time
+        for input in ["(loop)", "(dotimes (i 100000000000))"] {
+            assert_eq!(
+                run_limited(input, &steps),
+                Err(Unwind::ResourceExhausted(Limit::Steps(1000)))
+            );
+            assert_eq!(
+                run_limited(input, &timeout),
+                Err(Unwind::ResourceExhausted(Limit::Timeout(
+                    Duration::from_millis(10)
+                )))
+            );
+        }
+
+        // handlers can't keep an exhausted evaluation going
+        assert_eq!(
+            run_limited("(ignore-errors (loop (setq limits-test 1)))", &steps),
+            Err(Unwind::ResourceExhausted(Limit::Steps(1000)))
+        );
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        let args: Vec<String> = ["--max-steps", "10", "--timeout", "500"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            parse_limits(&args),
+            Ok(Limits {
+                max_steps: Some(10),
+                timeout: Some(Duration::from_millis(500)),
+                ..Limits::default()
+            })
+        );
+        assert_eq!(
+            parse_limits(&[String::from("--max-depth")]),
+            Err(String::from("--max-depth needs a number"))
+        );
+        assert_eq!(
+            parse_limits(&[String::from("-x")]),
+            Err(String::from("unknown option -x"))
+        );
+    }
+}
@@ -1,131 +1,255 @@
+use crate::debugger::*;
 use crate::eval::*;
+use crate::limits::*;
+use crate::numbers::*;
 use crate::parse::*;
+use crate::profiler::*;
+use crate::streams::*;
 use crate::test::*;
 
+use num_bigint::BigInt;
+use num_traits::Signed;
 use once_cell::sync::Lazy;
+use std::cell::RefCell;
 use std::sync::Mutex;
 
-pub static OBLIST: Lazy<Mutex<Vec<(String, Sexpr)>>> = Lazy::new(|| Mutex::new(Vec::new()));
+pub static OBLIST: Lazy<Mutex<Vec<(String, Sexpr)>>> = Lazy::new(|| {
+    let mut globals: Vec<(String, Sexpr)> = standard_streams();
+    globals.extend(numeric_globals());
+    Mutex::new(globals)
+});
 
-pub fn quote(args: Sexpr) -> Result<Sexpr, String> {
+thread_local! {
+    // the values after the first of the form evaluated last, evaluate clears
+    // them so that only VALUES and the rounding builtins leave any behind
+    static EXTRA_VALUES: RefCell<Vec<Sexpr>> = const { RefCell::new(Vec::new()) };
+}
+
+pub fn set_extra_values(values: Vec<Sexpr>) {
+    EXTRA_VALUES.with(|v| *v.borrow_mut() = values);
+}
+
+pub fn clear_extra_values() {
+    EXTRA_VALUES.with(|v| v.borrow_mut().clear());
+}
+
+// every value of form, the first being what evaluate returned
+fn all_values(form: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Vec<Sexpr>, Unwind> {
+    let first: Sexpr = evaluate(form, alist)?;
+    let mut values: Vec<Sexpr> = vec![first];
+    values.extend(EXTRA_VALUES.with(|v| std::mem::take(&mut *v.borrow_mut())));
+    Ok(values)
+}
+
+pub fn quote(args: Sexpr) -> Result<Sexpr, Unwind> {
     match args {
         // just return the first argument as is
         Sexpr::List(l) => match l.first() {
             Some(s) => Ok(s.clone()),
-            None => Err(String::from("quote - list is empty")),
+            None => Err(Unwind::from("quote - list is empty")),
         },
-        _ => Err(String::from("quote - something went wrong")),
+        _ => Err(Unwind::from("quote - something went wrong")),
     }
 }
 
-pub fn car(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
-    let mut arg = match &args {
+pub fn car(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let arg = match &args {
         Sexpr::List(l) => match l.first() {
             Some(s) => s.clone(),
-            None => return Err(String::from("car - args list is empty")),
+            None => return Err(Unwind::from("car - args list is empty")),
         },
-        _ => return Err(String::from("car - args must be a list")),
-    };
-    arg = match evaluate(arg, alist.clone()) {
-        Ok(o) => o,
-        Err(e) => return Err(e),
+        _ => return Err(Unwind::from("car - args must be a list")),
     };
+    let arg = evaluate(arg, alist)?;
 
     match arg {
         Sexpr::List(l) => match l.first() {
             Some(s) => Ok(s.clone()),
-            None => Err(String::from("car - list len must be >=1")),
+            None => Err(Unwind::from("car - list len must be >=1")),
         },
-        _ => Err(String::from("car - arg must be list")),
+        _ => Err(Unwind::from("car - arg must be list")),
     }
 }
 
-pub fn cdr(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
-    let mut arg = match &args {
+pub fn cdr(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let arg = match &args {
         Sexpr::List(l) => match l.first() {
             Some(s) => s.clone(),
-            None => return Err(String::from("cdr - args list is empty")),
+            None => return Err(Unwind::from("cdr - args list is empty")),
         },
-        _ => return Err(String::from("cdr - args must be a list")),
-    };
-    arg = match evaluate(arg, alist.clone()) {
-        Ok(o) => o,
-        Err(e) => return Err(e),
+        _ => return Err(Unwind::from("cdr - args must be a list")),
     };
+    let arg = evaluate(arg, alist)?;
 
     match arg {
         Sexpr::List(l) => {
-            if l.len() >= 1 {
+            if !l.is_empty() {
                 Ok(Sexpr::List(l[1..].to_vec()))
             } else {
-                return Err(String::from("cdr - arg must be length >= 1"));
+                Err(Unwind::from("cdr - arg must be length >= 1"))
             }
         }
-        _ => Err(String::from("cdr - arg must be list")),
+        _ => Err(Unwind::from("cdr - arg must be list")),
+    }
+}
+
+// redefinitions replace the old binding instead of growing the OBLIST
+pub fn set_global(name: String, value: Sexpr) {
+    let mut oblist = OBLIST.lock().unwrap();
+    match oblist.iter_mut().find(|(s, _)| *s == name) {
+        Some(binding) => binding.1 = value,
+        None => oblist.push((name, value)),
+    }
+}
+
+// updates the innermost visible binding, locals first then the OBLIST
+pub fn assign(name: String, value: Sexpr, alist: &mut [(String, Sexpr)]) {
+    match alist.iter_mut().rev().find(|(s, _)| *s == name) {
+        Some(binding) => binding.1 = value,
+        None => set_global(name, value),
     }
 }
 
-pub fn setq(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
+pub fn setq(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
     let args: Vec<Sexpr> = match &args {
         Sexpr::List(l) => l.clone(),
-        _ => return Err(String::from("setq - args must be a list")),
+        _ => return Err(Unwind::from("setq - args must be a list")),
     };
+    if args.is_empty() {
+        return Err(Unwind::from("setq - no first arg"));
+    }
 
-    let symbol: Sexpr = match args.first() {
-        Some(s) => s.clone(),
-        None => return Err(String::from("setq - no first arg")),
-    };
-    let value: Sexpr = match evaluate(
-        match args.get(1) {
-            Some(s) => s.clone(),
-            None => return Err(String::from("setq - no second arg")),
-        },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
+    // (setq a 1 b 2) assigns each pair in order, a lone NIL is the end of the list
+    let mut value: Sexpr = Sexpr::Nil;
+    for pair in args.chunks(2) {
+        let (symbol, form) = match pair {
+            [symbol, form] => (symbol.clone(), form.clone()),
+            [Sexpr::Nil] => break,
+            _ => return Err(Unwind::from("setq - no second arg")),
+        };
 
-    match symbol {
+        value = evaluate(form, alist)?;
+        match symbol {
+            Sexpr::Symbol(s) => assign(s, value.clone(), alist),
+            Sexpr::Nil => return Err(Unwind::from("setq - NIL is not a valid symbol name")),
+            _ => return Err(Unwind::from("setq - first arg must be a symbol")),
+        }
+    }
+
+    Ok(value)
+}
+
+// writes value into place by rebuilding every list up to the variable the place is rooted at
+fn set_place(place: Sexpr, value: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<(), Unwind> {
+    let l: Vec<Sexpr> = match place {
         Sexpr::Symbol(s) => {
-            if s == String::from("NIL") {
-                return Err(String::from("setq - NIL is not a valid symbol name"));
+            assign(s, value, alist);
+            return Ok(());
+        }
+        Sexpr::Nil => return Err(Unwind::from("setf - NIL is not a valid place")),
+        Sexpr::List(l) => l,
+        _ => return Err(Unwind::from("setf - place must be a symbol or list")),
+    };
+
+    let accessor: String = match l.first() {
+        Some(Sexpr::Symbol(s)) => s.clone(),
+        _ => return Err(Unwind::from("setf - place must start with an accessor")),
+    };
+    match accessor.as_str() {
+        "CAR" | "CDR" => {
+            let inner: Sexpr = match l.get(1) {
+                Some(s) => s.clone(),
+                None => return Err(Unwind::from(accessor + ", setf - place has no arg")),
+            };
+            let mut list: Vec<Sexpr> = match evaluate(inner.clone(), alist)? {
+                Sexpr::List(v) if !v.is_empty() => v,
+                _ => return Err(Unwind::from(accessor + ", setf - place must hold a list")),
+            };
+            if accessor == "CAR" {
+                list[0] = value;
             } else {
-                OBLIST.lock().unwrap().push((s, value.clone()))
+                list.truncate(1);
+                match value {
+                    Sexpr::List(r) => list.extend(r),
+                    v => list.push(v),
+                }
             }
+            set_place(inner, Sexpr::List(list), alist)
         }
-        _ => return Err(String::from("setq - first arg must be a symbol")),
+        "NTH" => {
+            let index: usize = match evaluate(
+                match l.get(1) {
+                    Some(s) => s.clone(),
+                    None => return Err(Unwind::from("nth, setf - place has no index")),
+                },
+                alist,
+            )? {
+                Sexpr::Integer(i) if i >= 0 => i as usize,
+                _ => return Err(Unwind::from("nth, setf - index must be a non-negative int")),
+            };
+            let inner: Sexpr = match l.get(2) {
+                Some(s) => s.clone(),
+                None => return Err(Unwind::from("nth, setf - place has no list")),
+            };
+            let mut list: Vec<Sexpr> = match evaluate(inner.clone(), alist)? {
+                Sexpr::List(v) => v,
+                _ => return Err(Unwind::from("nth, setf - place must hold a list")),
+            };
+            // the last element is the terminator of the list, not an item
+            if index + 1 >= list.len() {
+                return Err(Unwind::from("nth, setf - index out of range"));
+            }
+            list[index] = value;
+            set_place(inner, Sexpr::List(list), alist)
+        }
+        _ => Err(Unwind::from(accessor + ", setf - not a settable place")),
+    }
+}
+
+pub fn setf(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => l.clone(),
+        _ => return Err(Unwind::from("setf - args must be a list")),
+    };
+    if args.is_empty() {
+        return Err(Unwind::from("setf - no first arg"));
+    }
+
+    let mut value: Sexpr = Sexpr::Nil;
+    for pair in args.chunks(2) {
+        let (place, form) = match pair {
+            [place, form] => (place.clone(), form.clone()),
+            [Sexpr::Nil] => break,
+            _ => return Err(Unwind::from("setf - no second arg")),
+        };
+
+        value = evaluate(form, alist)?;
+        set_place(place, value.clone(), alist)?;
     }
 
     Ok(value)
 }
 
-pub fn equal(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
+pub fn equal(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
     let args: Vec<Sexpr> = match &args {
         Sexpr::List(l) => l.clone(),
-        _ => return Err(String::from("equal - args must be a list")),
+        _ => return Err(Unwind::from("equal - args must be a list")),
     };
 
-    let left: Sexpr = match evaluate(
+    let left: Sexpr = evaluate(
         match args.first() {
             Some(s) => s.clone(),
-            None => return Err(String::from("equal - no first arg")),
+            None => return Err(Unwind::from("equal - no first arg")),
         },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
-    let right: Sexpr = match evaluate(
+        alist,
+    )?;
+    let right: Sexpr = evaluate(
         match args.get(1) {
             Some(s) => s.clone(),
-            None => return Err(String::from("equal - no second arg")),
+            None => return Err(Unwind::from("equal - no second arg")),
         },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
+        alist,
+    )?;
 
     if equal_sexprs(&left, &right) {
         Ok(Sexpr::T)
@@ -134,18 +258,15 @@ pub fn equal(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String>
     }
 }
 
-pub fn atom(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
-    let mut arg = match &args {
+pub fn atom(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let arg = match &args {
         Sexpr::List(l) => match l.first() {
             Some(s) => s.clone(),
-            None => return Err(String::from("atom - args list is empty")),
+            None => return Err(Unwind::from("atom - args list is empty")),
         },
-        _ => return Err(String::from("atom - args must be a list")),
-    };
-    arg = match evaluate(arg, alist.clone()) {
-        Ok(o) => o,
-        Err(e) => return Err(e),
+        _ => return Err(Unwind::from("atom - args must be a list")),
     };
+    let arg = evaluate(arg, alist)?;
 
     match arg {
         Sexpr::List(_) => Ok(Sexpr::Nil),
@@ -153,18 +274,15 @@ pub fn atom(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
     }
 }
 
-pub fn listp(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
-    let mut arg = match &args {
+pub fn listp(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let arg = match &args {
         Sexpr::List(l) => match l.first() {
             Some(s) => s.clone(),
-            None => return Err(String::from("listp - args list is empty")),
+            None => return Err(Unwind::from("listp - args list is empty")),
         },
-        _ => return Err(String::from("listp - args must be a list")),
-    };
-    arg = match evaluate(arg, alist.clone()) {
-        Ok(o) => o,
-        Err(e) => return Err(e),
+        _ => return Err(Unwind::from("listp - args must be a list")),
     };
+    let arg = evaluate(arg, alist)?;
 
     match arg {
         Sexpr::List(_) => Ok(Sexpr::T),
@@ -173,18 +291,15 @@ pub fn listp(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String>
     }
 }
 
-pub fn null(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
-    let mut arg = match &args {
+pub fn null(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let arg = match &args {
         Sexpr::List(l) => match l.first() {
             Some(s) => s.clone(),
-            None => return Err(String::from("null - args list is empty")),
+            None => return Err(Unwind::from("null - args list is empty")),
         },
-        _ => return Err(String::from("null - args must be a list")),
-    };
-    arg = match evaluate(arg, alist.clone()) {
-        Ok(o) => o,
-        Err(e) => return Err(e),
+        _ => return Err(Unwind::from("null - args must be a list")),
     };
+    let arg = evaluate(arg, alist)?;
 
     match arg {
         Sexpr::Nil => Ok(Sexpr::T),
@@ -192,273 +307,43 @@ pub fn null(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
     }
 }
 
-pub fn floor(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
-    let mut arg = match &args {
-        Sexpr::List(l) => match l.first() {
-            Some(s) => s.clone(),
-            None => return Err(String::from("floor - args list is empty")),
-        },
-        _ => return Err(String::from("floor - args must be a list")),
-    };
-    arg = match evaluate(arg, alist.clone()) {
-        Ok(o) => o,
-        Err(e) => return Err(e),
-    };
-
-    match arg {
-        Sexpr::Integer(i) => Ok(Sexpr::Integer(i)),
-        Sexpr::Float(f) => Ok(Sexpr::Integer(f as i64)),
-        _ => Err(String::from("floor - arg must be a int or float")),
-    }
-}
-
-pub fn add(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
-    let args: Vec<Sexpr> = match &args {
-        Sexpr::List(l) => l.clone(),
-        _ => return Err(String::from("add - args must be a list")),
-    };
-
-    let num1: Sexpr = match evaluate(
-        match args.first() {
-            Some(s) => s.clone(),
-            None => return Err(String::from("add - no first arg")),
-        },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
-    let num2: Sexpr = match evaluate(
-        match args.get(1) {
-            Some(s) => s.clone(),
-            None => return Err(String::from("add - no second arg")),
-        },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
-
-    match (num1, num2) {
-        (Sexpr::Integer(i1), Sexpr::Integer(i2)) => Ok(Sexpr::Integer(i1 + i2)),
-        (Sexpr::Float(f), Sexpr::Integer(i)) => Ok(Sexpr::Float(f + i as f64)),
-        (Sexpr::Integer(i), Sexpr::Float(f)) => Ok(Sexpr::Float(f + i as f64)),
-        (Sexpr::Float(f1), Sexpr::Float(f2)) => Ok(Sexpr::Float(f1 + f2)),
-        (_, _) => Err(String::from("add - both args must be nums")),
-    }
-}
-
-pub fn subtract(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
-    let args: Vec<Sexpr> = match &args {
-        Sexpr::List(l) => l.clone(),
-        _ => return Err(String::from("subtract - args must be a list")),
-    };
-
-    let num1: Sexpr = match evaluate(
-        match args.first() {
-            Some(s) => s.clone(),
-            None => return Err(String::from("subtract - no first arg")),
-        },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
-    let num2: Sexpr = match evaluate(
-        match args.get(1) {
-            Some(s) => s.clone(),
-            None => return Err(String::from("subtract - no second arg")),
-        },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
-
-    match (num1, num2) {
-        (Sexpr::Integer(i1), Sexpr::Integer(i2)) => Ok(Sexpr::Integer(i1 - i2)),
-        (Sexpr::Float(f), Sexpr::Integer(i)) => Ok(Sexpr::Float(f - i as f64)),
-        (Sexpr::Integer(i), Sexpr::Float(f)) => Ok(Sexpr::Float(f - i as f64)),
-        (Sexpr::Float(f1), Sexpr::Float(f2)) => Ok(Sexpr::Float(f1 - f2)),
-        (_, _) => Err(String::from("subtract - both args must be nums")),
-    }
-}
-
-pub fn multiply(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
-    let args: Vec<Sexpr> = match &args {
-        Sexpr::List(l) => l.clone(),
-        _ => return Err(String::from("multiply - args must be a list")),
-    };
-
-    let num1: Sexpr = match evaluate(
-        match args.first() {
-            Some(s) => s.clone(),
-            None => return Err(String::from("multiply - no first arg")),
-        },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
-    let num2: Sexpr = match evaluate(
-        match args.get(1) {
-            Some(s) => s.clone(),
-            None => return Err(String::from("multiply - no second arg")),
-        },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
-
-    match (num1, num2) {
-        (Sexpr::Integer(i1), Sexpr::Integer(i2)) => Ok(Sexpr::Integer(i1 * i2)),
-        (Sexpr::Float(f), Sexpr::Integer(i)) => Ok(Sexpr::Float(f * i as f64)),
-        (Sexpr::Integer(i), Sexpr::Float(f)) => Ok(Sexpr::Float(f * i as f64)),
-        (Sexpr::Float(f1), Sexpr::Float(f2)) => Ok(Sexpr::Float(f1 * f2)),
-        (_, _) => Err(String::from("multiply - both args must be nums")),
-    }
-}
-
-pub fn divide(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
-    let args: Vec<Sexpr> = match &args {
-        Sexpr::List(l) => l.clone(),
-        _ => return Err(String::from("divide - args must be a list")),
-    };
-
-    let num1: Sexpr = match evaluate(
-        match args.first() {
-            Some(s) => s.clone(),
-            None => return Err(String::from("divide - no first arg")),
-        },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
-    let num2: Sexpr = match evaluate(
-        match args.get(1) {
-            Some(s) => s.clone(),
-            None => return Err(String::from("divide - no second arg")),
-        },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
-
-    match (num1, num2) {
-        (Sexpr::Integer(i1), Sexpr::Integer(i2)) => Ok(Sexpr::Integer(i1 / i2)),
-        (Sexpr::Float(f), Sexpr::Integer(i)) => Ok(Sexpr::Float(f / i as f64)),
-        (Sexpr::Integer(i), Sexpr::Float(f)) => Ok(Sexpr::Float(f / i as f64)),
-        (Sexpr::Float(f1), Sexpr::Float(f2)) => Ok(Sexpr::Float(f1 / f2)),
-        (_, _) => Err(String::from("divide - both args must be nums")),
-    }
-}
-
-pub fn modulo(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
+pub fn eval(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
     let args: Vec<Sexpr> = match &args {
         Sexpr::List(l) => l.clone(),
-        _ => return Err(String::from("modulo - args must be a list")),
+        _ => return Err(Unwind::from("eval - args must be a list")),
     };
 
-    let num1: Sexpr = match evaluate(
+    let arg: Sexpr = evaluate(
         match args.first() {
             Some(s) => s.clone(),
-            None => return Err(String::from("modulo - no second arg")),
+            None => return Err(Unwind::from("eval - no arg")),
         },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
-    let num2: Sexpr = match evaluate(
-        match args.get(1) {
-            Some(s) => s.clone(),
-            None => return Err(String::from("modulo - no second arg")),
-        },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
-
-    match (num1, num2) {
-        (Sexpr::Integer(i1), Sexpr::Integer(i2)) => Ok(Sexpr::Integer(i1 % i2)),
-        (Sexpr::Float(f), Sexpr::Integer(i)) => Ok(Sexpr::Float(f % i as f64)),
-        (Sexpr::Integer(i), Sexpr::Float(f)) => Ok(Sexpr::Float(f % i as f64)),
-        (Sexpr::Float(f1), Sexpr::Float(f2)) => Ok(Sexpr::Float(f1 % f2)),
-        (_, _) => Err(String::from("modulo - both args must be nums")),
-    }
-}
-
-pub fn print(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
-    let args: Vec<Sexpr> = match &args {
-        Sexpr::List(l) => l.clone(),
-        _ => return Err(String::from("print - args must be a list")),
-    };
-
-    let arg: Sexpr = match evaluate(
-        match args.first() {
-            Some(s) => s.clone(),
-            None => return Err(String::from("print - no second arg")),
-        },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
-
-    print!("{}", sexpr_to_string(&arg));
-    Ok(Sexpr::String(String::new()))
-}
-
-pub fn eval(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
-    let args: Vec<Sexpr> = match &args {
-        Sexpr::List(l) => l.clone(),
-        _ => return Err(String::from("eval - args must be a list")),
-    };
-
-    let arg: Sexpr = match evaluate(
-        match args.first() {
-            Some(s) => s.clone(),
-            None => return Err(String::from("eval - no arg")),
-        },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
+        alist,
+    )?;
 
-    evaluate(arg, alist.clone())
+    evaluate(arg, alist)
 }
 
-pub fn cons(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
+pub fn cons(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
     let args: Vec<Sexpr> = match &args {
         Sexpr::List(l) => l.clone(),
-        _ => return Err(String::from("cons - args must be a list")),
+        _ => return Err(Unwind::from("cons - args must be a list")),
     };
 
-    let arg1: Sexpr = match evaluate(
+    let arg1: Sexpr = evaluate(
         match args.first() {
             Some(s) => s.clone(),
-            None => return Err(String::from("cons - no first arg")),
+            None => return Err(Unwind::from("cons - no first arg")),
         },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
-    let arg2: Sexpr = match evaluate(
+        alist,
+    )?;
+    let arg2: Sexpr = evaluate(
         match args.get(1) {
             Some(s) => s.clone(),
-            None => return Err(String::from("cons - no second arg")),
+            None => return Err(Unwind::from("cons - no second arg")),
         },
-        alist.clone(),
-    ) {
-        Ok(s) => s,
-        Err(e) => return Err(e),
-    };
+        alist,
+    )?;
 
     match (arg1, arg2) {
         (l, Sexpr::List(mut r)) => {
@@ -469,10 +354,10 @@ pub fn cons(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
     }
 }
 
-pub fn cond(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
+pub fn cond(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
     let clauses: Vec<Sexpr> = match &args {
         Sexpr::List(l) => l.clone(),
-        _ => return Err(String::from("cond - args must be a list")),
+        _ => return Err(Unwind::from("cond - args must be a list")),
     };
 
     //(
@@ -484,140 +369,1018 @@ pub fn cond(args: Sexpr, alist: Vec<(String, Sexpr)>) -> Result<Sexpr, String> {
             Sexpr::List(l) => {
                 if l.len() != 3 {
                     // 3 because NIL is added to the end
-                    return Err(String::from("cond - clause must be len 2"));
+                    return Err(Unwind::from("cond - clause must be len 2"));
                 }
 
                 let test: Sexpr = match l.first() {
                     Some(s) => s.clone(),
-                    None => return Err(String::from("cond - clause doesn't have a first arg")),
+                    None => return Err(Unwind::from("cond - clause doesn't have a first arg")),
                 };
 
                 let body: Sexpr = match l.get(1) {
                     Some(s) => s.clone(),
-                    None => return Err(String::from("cond - clause doesn't have a first arg")),
+                    None => return Err(Unwind::from("cond - clause doesn't have a first arg")),
                 };
 
                 (test, body)
             }
             Sexpr::Nil => break, // reached the end of the list
-            _ => return Err(String::from("cond - each clause must be a list")),
+            _ => return Err(Unwind::from("cond - each clause must be a list")),
         };
 
         // anything that isn't NIL is T
-        test = match evaluate(test, alist.clone()) {
-            Ok(o) => o,
-            e => return e, // error
-        };
+        test = evaluate(test, alist)?;
 
         match test {
             Sexpr::Nil => {}
-            _ => return evaluate(body, alist.clone()),
+            _ => return evaluate(body, alist),
         }
     }
 
     Ok(Sexpr::Nil)
 }
 
-pub fn defun(args: Sexpr) -> Result<Sexpr, String> {
+pub fn defun(args: Sexpr) -> Result<Sexpr, Unwind> {
     let args: Vec<Sexpr> = match &args {
         Sexpr::List(l) => l.clone(),
-        _ => return Err(String::from("defun - args must be a list")),
+        _ => return Err(Unwind::from("defun - args must be a list")),
     };
 
     let symbol: Sexpr = match args.first() {
         Some(s) => match s {
             Sexpr::Symbol(_) => s.clone(),
-            _ => return Err(String::from("defun - function name must be a symbol")),
-        },
-        None => return Err(String::from("defun - no function name arg")),
-    };
-    let params: Sexpr = match args.get(1) {
-        Some(s) => match s {
-            Sexpr::List(_) => s.clone(),
-            _ => return Err(String::from("defun - params must be a list")),
+            _ => return Err(Unwind::from("defun - function name must be a symbol")),
         },
-        None => return Err(String::from("defun - no params arg")),
+        None => return Err(Unwind::from("defun - no function name arg")),
     };
-    let body: Sexpr = match args.get(2) {
-        Some(s) => s.clone(),
-        None => return Err(String::from("defun - no body arg")),
+    match args.get(1) {
+        Some(Sexpr::List(_)) | Some(Sexpr::Nil) => {}
+        Some(_) => return Err(Unwind::from("defun - params must be a list")),
+        None => return Err(Unwind::from("defun - no params arg")),
     };
+    if args.len() < 3 {
+        return Err(Unwind::from("defun - no body arg"));
+    }
 
     match &symbol {
         Sexpr::Symbol(s) => {
-            if s.to_string() == String::from("NIL") {
-                return Err(String::from("defun - NIL is not a valid symbol name"));
+            if s == "NIL" {
+                return Err(Unwind::from("defun - NIL is not a valid symbol name"));
             } else {
-                OBLIST
-                    .lock()
-                    .unwrap()
-                    .push((s.to_string(), Sexpr::List(vec![params, body, Sexpr::Nil])))
+                // params followed by every body form
+                set_global(
+                    s.to_string(),
+                    Sexpr::Lambda(s.to_string(), args[1..].to_vec()),
+                )
             }
         }
-        _ => return Err(String::from("defun - first arg must be a symbol")),
+        _ => return Err(Unwind::from("defun - first arg must be a symbol")),
     }
 
     Ok(symbol)
 }
 
+pub fn lambda(args: Sexpr) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => l.clone(),
+        _ => return Err(Unwind::from("lambda - args must be a list")),
+    };
+
+    match args.first() {
+        Some(Sexpr::List(_)) | Some(Sexpr::Nil) => {}
+        Some(_) => return Err(Unwind::from("lambda - params must be a list")),
+        None => return Err(Unwind::from("lambda - no params arg")),
+    };
+
+    Ok(Sexpr::Lambda(String::from("LAMBDA"), args))
+}
+
+pub fn function(args: Sexpr, alist: &[(String, Sexpr)]) -> Result<Sexpr, Unwind> {
+    let arg = match &args {
+        Sexpr::List(l) => match l.first() {
+            Some(s) => s.clone(),
+            None => return Err(Unwind::from("function - args list is empty")),
+        },
+        _ => return Err(Unwind::from("function - args must be a list")),
+    };
+
+    match arg {
+        Sexpr::Symbol(s) => function_value(s, alist),
+        // #'(lambda (x) ...)
+        Sexpr::List(l) if l.first() == Some(&Sexpr::Symbol(String::from("LAMBDA"))) => {
+            lambda(Sexpr::List(l[1..].to_vec()))
+        }
+        _ => Err(Unwind::from("function - arg must be a symbol or lambda")),
+    }
+}
+
+pub fn funcall(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let mut args: Vec<Sexpr> = eval_args(args, alist)?;
+    if args.is_empty() {
+        return Err(Unwind::from("funcall - no function arg"));
+    }
+
+    let f: Sexpr = args.remove(0);
+    call_function(f, args, alist)
+}
+
+// (apply f 1 2 '(3 4)) calls f with 1 2 3 4
+pub fn apply_function(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let mut args: Vec<Sexpr> = eval_args(args, alist)?;
+    if args.len() < 2 {
+        return Err(Unwind::from("apply - needs a function and a list of args"));
+    }
+
+    let f: Sexpr = args.remove(0);
+    match args.pop() {
+        Some(Sexpr::List(l)) => match l.last() {
+            Some(Sexpr::Nil) => args.extend(list_items(&l).iter().cloned()),
+            _ => return Err(Unwind::from("apply - last arg must be a proper list")),
+        },
+        Some(Sexpr::Nil) => {}
+        _ => return Err(Unwind::from("apply - last arg must be a list")),
+    }
+    call_function(f, args, alist)
+}
+
+// a param spec is either VAR or (VAR DEFAULT SUPPLIED-P) with the last two optional
+fn param_spec(name: &str, spec: &Sexpr) -> Result<(String, Sexpr, Option<String>), Unwind> {
+    match spec {
+        Sexpr::Symbol(s) => Ok((s.clone(), Sexpr::Nil, None)),
+        Sexpr::List(l) => {
+            let l = list_items(l);
+            let var: String = match l.first() {
+                Some(Sexpr::Symbol(s)) => s.clone(),
+                _ => {
+                    return Err(Unwind::from(format!(
+                        "{}, func - param spec must start with a symbol",
+                        name
+                    )))
+                }
+            };
+            let default: Sexpr = l.get(1).cloned().unwrap_or(Sexpr::Nil);
+            let supplied: Option<String> = match l.get(2) {
+                Some(Sexpr::Symbol(s)) => Some(s.clone()),
+                Some(_) => {
+                    return Err(Unwind::from(format!(
+                        "{}, func - supplied-p must be a symbol",
+                        name
+                    )))
+                }
+                None => None,
+            };
+            Ok((var, default, supplied))
+        }
+        _ => Err(Unwind::from(format!(
+            "{}, func - each param must be a symbol",
+            name
+        ))),
+    }
+}
+
+#[derive(PartialEq)]
+enum ParamMode {
+    Required,
+    Optional,
+    Rest,
+    Key,
+}
+
+// binds a lambda list like (a &optional (b 1 b-p) &rest r &key (c 2)) to the
+// evaluated args, defaults are evaluated once the params before them are bound
+fn bind_params(
+    name: &str,
+    params: &[Sexpr],
+    args: &[Sexpr],
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<(), Unwind> {
+    let mut signature: Vec<Sexpr> = params.to_vec();
+    signature.push(Sexpr::Nil);
+    let signature: String = sexpr_to_string(&Sexpr::List(signature));
+
+    let mut mode = ParamMode::Required;
+    let mut next: usize = 0; // index of the first arg not bound yet
+    let mut has_rest = false;
+    let mut has_key = false;
+    let mut keys: Vec<String> = Vec::new();
+    let mut allow_other_keys = false;
+
+    for p in params {
+        if let Sexpr::Symbol(s) = p {
+            match s.as_str() {
+                "&OPTIONAL" => {
+                    mode = ParamMode::Optional;
+                    continue;
+                }
+                "&REST" => {
+                    mode = ParamMode::Rest;
+                    continue;
+                }
+                "&KEY" => {
+                    mode = ParamMode::Key;
+                    has_key = true;
+                    continue;
+                }
+                "&ALLOW-OTHER-KEYS" => {
+                    allow_other_keys = true;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        match mode {
+            ParamMode::Required => {
+                let var: String = match p {
+                    Sexpr::Symbol(s) => s.clone(),
+                    _ => {
+                        return Err(Unwind::from(format!(
+                            "{}, func - each param must be a symbol",
+                            name
+                        )))
+                    }
+                };
+                match args.get(next) {
+                    Some(a) => alist.push((var, a.clone())),
+                    None => {
+                        return Err(Unwind::from(format!(
+                            "{}, func - not enough args, expected {}",
+                            name, signature
+                        )))
+                    }
+                }
+                next += 1;
+            }
+            ParamMode::Optional => {
+                let (var, default, supplied) = param_spec(name, p)?;
+                let (value, supplied_p) = match args.get(next) {
+                    Some(a) => {
+                        next += 1;
+                        (a.clone(), Sexpr::T)
+                    }
+                    None => (evaluate(default, alist)?, Sexpr::Nil),
+                };
+                alist.push((var, value));
+                if let Some(s) = supplied {
+                    alist.push((s, supplied_p));
+                }
+            }
+            ParamMode::Rest => {
+                let var: String = match p {
+                    Sexpr::Symbol(s) => s.clone(),
+                    _ => {
+                        return Err(Unwind::from(format!(
+                            "{}, func - &rest param must be a symbol",
+                            name
+                        )))
+                    }
+                };
+                let rest: Sexpr = if next < args.len() {
+                    let mut rest: Vec<Sexpr> = args[next..].to_vec();
+                    rest.push(Sexpr::Nil);
+                    Sexpr::List(rest)
+                } else {
+                    Sexpr::Nil
+                };
+                alist.push((var, rest));
+                has_rest = true;
+            }
+            ParamMode::Key => {
+                let (var, default, supplied) = param_spec(name, p)?;
+                let keyword: String = format!(":{}", var);
+                let found: Option<Sexpr> = args[next.min(args.len())..]
+                    .chunks(2)
+                    .find(|pair| pair[0] == Sexpr::Symbol(keyword.clone()))
+                    .and_then(|pair| pair.get(1).cloned());
+                let (value, supplied_p) = match found {
+                    Some(a) => (a, Sexpr::T),
+                    None => (evaluate(default, alist)?, Sexpr::Nil),
+                };
+                alist.push((var, value));
+                if let Some(s) = supplied {
+                    alist.push((s, supplied_p));
+                }
+                keys.push(keyword);
+            }
+        }
+    }
+
+    if next >= args.len() {
+        return Ok(());
+    }
+    if has_key {
+        let rest: &[Sexpr] = &args[next..];
+        if !rest.len().is_multiple_of(2) {
+            return Err(Unwind::from(format!(
+                "{}, func - odd number of keyword args, expected {}",
+                name, signature
+            )));
+        }
+        for pair in rest.chunks(2) {
+            match &pair[0] {
+                Sexpr::Symbol(k) if keys.contains(k) || allow_other_keys => {}
+                k => {
+                    return Err(Unwind::from(format!(
+                        "{}, func - unknown keyword {}, expected {}",
+                        name,
+                        sexpr_to_string(k),
+                        signature
+                    )))
+                }
+            }
+        }
+    } else if !has_rest {
+        return Err(Unwind::from(format!(
+            "{}, func - too many args, expected {}",
+            name, signature
+        )));
+    }
+
+    Ok(())
+}
+
 pub fn func(
     name: String,
     lambda: Sexpr,
     args: Sexpr,
-    mut alist: Vec<(String, Sexpr)>,
-) -> Result<Sexpr, String> {
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Sexpr, Unwind> {
     let list: Vec<Sexpr> = match lambda {
         Sexpr::List(l) => l,
-        _ => return Err(String::from(name + ", func - is not a valid function")),
+        Sexpr::Lambda(_, l) => l,
+        _ => return Err(Unwind::from(name + ", func - is not a valid function")),
     };
 
     let params: Vec<Sexpr> = match list.first() {
         Some(s) => match s {
-            Sexpr::List(l) => l.to_vec(),
-            _ => return Err(String::from(name + ", func - params must be a list")),
+            Sexpr::List(l) => list_items(l).to_vec(),
+            Sexpr::Nil => Vec::new(),
+            _ => return Err(Unwind::from(name + ", func - params must be a list")),
         },
-        None => return Err(String::from(name + ", func - no params arg")),
+        None => return Err(Unwind::from(name + ", func - no params arg")),
     };
 
-    let body: Sexpr = match list.get(1) {
-        Some(s) => s.clone(),
-        None => return Err(String::from(name + ", func - no body")),
-    };
+    if list.len() < 2 {
+        return Err(Unwind::from(name + ", func - no body"));
+    }
+    let body: &[Sexpr] = list_items(&list[1..]);
 
     let args: Vec<Sexpr> = match &args {
-        Sexpr::List(l) => l.clone(),
-        _ => return Err(String::from(name + ", func - args must be a list")),
+        Sexpr::List(l) => list_items(l).to_vec(),
+        Sexpr::Nil => Vec::new(),
+        _ => return Err(Unwind::from(name + ", func - args must be a list")),
     };
 
-    if params.len() > args.len() {
-        return Err(String::from(name + ", func - not enough args"));
+    // the params are popped off again once the body is done, so that
+    // a setq inside the body never leaks into the caller's bindings
+    let depth = alist.len();
+    let frame: usize = push_frame(&name, &args, depth);
+    if let Err(e) = bind_params(&name, &params, &args, alist) {
+        alist.truncate(depth);
+        pop_frame(frame);
+        return Err(e);
     }
 
-    for (p, a) in params.iter().zip(args.iter()) {
-        let p = match p {
-            Sexpr::Symbol(s) => s,
-            Sexpr::Nil => break,
-            _ => return Err(String::from(name + ", func - each param must be a symbol")),
-        };
-        alist.push((p.clone(), a.clone()));
-    }
+    let traced: bool = trace_call(&name, &args, alist);
+    let profiled: bool = profile_enter(&name);
 
-    evaluate(body, alist.clone())
+    // a DEFUN body is a block named after the function, a bare LAMBDA's is not
+    let mut result: Result<Sexpr, Unwind> = progn_forms(body, alist);
+    if name != "LAMBDA" {
+        result = catch_return(&name, result);
+    }
+    alist.truncate(depth);
+    pop_frame(frame);
+    result = match result {
+        // :return in the debugger
+        Err(Unwind::Frame(i, v)) if i == frame => Ok(v),
+        r => r,
+    };
+    if profiled {
+        profile_exit();
+    }
+    if traced {
+        trace_return(&name, &result, alist);
+    }
+    result
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+// evaluates each form in order and returns the value of the last one
+pub fn progn_forms(forms: &[Sexpr], alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let mut result: Sexpr = Sexpr::Nil;
+    for form in forms {
+        result = evaluate(form.clone(), alist)?;
+    }
+    Ok(result)
+}
 
-    #[test]
-    fn test_quote() {
-        let mut arg: Sexpr = Sexpr::String(String::from("a"));
-        let mut args: Sexpr = Sexpr::List(vec![arg.clone()]);
-        assert!(equal_sexprs(&quote(args.clone()).unwrap(), &arg));
+pub fn progn(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match &args {
+        Sexpr::List(l) => progn_forms(list_items(l), alist),
+        _ => Err(Unwind::from("progn - args must be a list")),
+    }
+}
 
-        arg = Sexpr::List(Vec::new());
-        args = Sexpr::List(vec![arg.clone()]);
-        assert!(equal_sexprs(&quote(args.clone()).unwrap(), &arg));
+// (values a b c) is a, with b and c as extra values for MULTIPLE-VALUE-BIND
+pub fn values(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let mut values: Vec<Sexpr> = eval_args(args, alist)?;
+    if values.is_empty() {
+        return Ok(Sexpr::Nil);
+    }
+    let first: Sexpr = values.remove(0);
+    set_extra_values(values);
+    Ok(first)
+}
+
+pub fn multiple_value_list(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let form: Sexpr = match &args {
+        Sexpr::List(l) => match list_items(l) {
+            [form] => form.clone(),
+            _ => return Err(Unwind::from("multiple-value-list - takes exactly one form")),
+        },
+        _ => return Err(Unwind::from("multiple-value-list - args must be a list")),
+    };
+    let mut values: Vec<Sexpr> = all_values(form, alist)?;
+    values.push(Sexpr::Nil);
+    Ok(Sexpr::List(values))
+}
+
+// (multiple-value-bind (vars...) form body...) binds vars missing a value to NIL
+pub fn multiple_value_bind(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("multiple-value-bind - args must be a list")),
+    };
+    let vars: Vec<String> = match args.first() {
+        Some(Sexpr::List(l)) => list_items(l)
+            .iter()
+            .map(|v| match v {
+                Sexpr::Symbol(s) => Ok(s.clone()),
+                _ => Err(Unwind::from("multiple-value-bind - vars must be symbols")),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(Sexpr::Nil) => Vec::new(),
+        _ => {
+            return Err(Unwind::from(
+                "multiple-value-bind - first arg must be a list of vars",
+            ))
+        }
+    };
+    let form: Sexpr = match args.get(1) {
+        Some(form) => form.clone(),
+        None => return Err(Unwind::from("multiple-value-bind - no values form")),
+    };
+
+    let mut values = all_values(form, alist)?.into_iter();
+    let depth = alist.len();
+    for var in vars {
+        alist.push((var, values.next().unwrap_or(Sexpr::Nil)));
+    }
+    let result = progn_forms(&args[2..], alist);
+    alist.truncate(depth);
+    result
+}
+
+// a (return-from name value) that reaches the block it names becomes its value
+pub fn catch_return(name: &str, result: Result<Sexpr, Unwind>) -> Result<Sexpr, Unwind> {
+    match result {
+        Err(Unwind::Return(block, v)) if block == name => Ok(v),
+        r => r,
+    }
+}
+
+fn block_name(s: &Sexpr) -> Result<String, Unwind> {
+    match s {
+        Sexpr::Symbol(s) => Ok(s.clone()),
+        Sexpr::Nil => Ok(String::from("NIL")),
+        _ => Err(Unwind::from("return-from - block name must be a symbol")),
+    }
+}
+
+pub fn return_from(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("return-from - args must be a list")),
+    };
+
+    let name: String = match args.first() {
+        Some(s) => block_name(s)?,
+        None => return Err(Unwind::from("return-from - no block name arg")),
+    };
+    let value: Sexpr = match args.get(1) {
+        Some(s) => evaluate(s.clone(), alist)?,
+        None => Sexpr::Nil,
+    };
+
+    Err(Unwind::Return(name, value))
+}
+
+// (return value) leaves the innermost loop, which is a block named NIL
+pub fn return_nil(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let value: Sexpr = match &args {
+        Sexpr::List(l) => match list_items(l).first() {
+            Some(s) => evaluate(s.clone(), alist)?,
+            None => Sexpr::Nil,
+        },
+        _ => return Err(Unwind::from("return - args must be a list")),
+    };
+
+    Err(Unwind::Return(String::from("NIL"), value))
+}
+
+// (block name body...)
+pub fn block(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("block - args must be a list")),
+    };
+
+    let name: String = match args.first() {
+        Some(s) => block_name(s)?,
+        None => return Err(Unwind::from("block - no block name arg")),
+    };
+    let result = progn_forms(&args[1..], alist);
+    catch_return(&name, result)
+}
+
+// (catch tag body...), the tag is evaluated so any value can be thrown to
+pub fn catch(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("catch - args must be a list")),
+    };
+
+    let tag: Sexpr = match args.first() {
+        Some(s) => evaluate(s.clone(), alist)?,
+        None => return Err(Unwind::from("catch - no tag arg")),
+    };
+    match progn_forms(&args[1..], alist) {
+        Err(Unwind::Throw(thrown, v)) if equal_sexprs(&thrown, &tag) => Ok(v),
+        r => r,
+    }
+}
+
+pub fn throw(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("throw - args must be a list")),
+    };
+
+    let tag: Sexpr = match args.first() {
+        Some(s) => evaluate(s.clone(), alist)?,
+        None => return Err(Unwind::from("throw - no tag arg")),
+    };
+    let value: Sexpr = match args.get(1) {
+        Some(s) => evaluate(s.clone(), alist)?,
+        None => Sexpr::Nil,
+    };
+
+    Err(Unwind::Throw(tag, value))
+}
+
+// (unwind-protect form cleanup...), the cleanup forms run however form is left
+pub fn unwind_protect(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("unwind-protect - args must be a list")),
+    };
+
+    let result = match args.first() {
+        Some(s) => evaluate(s.clone(), alist),
+        None => return Err(Unwind::from("unwind-protect - no protected form")),
+    };
+    // an exit from the cleanup itself replaces the one in progress
+    progn_forms(&args[1..], alist)?;
+    result
+}
+
+// splits (head body...) where head is a list like (var count result)
+fn loop_head(name: &str, args: &Sexpr) -> Result<(Vec<Sexpr>, Vec<Sexpr>), Unwind> {
+    let args: &[Sexpr] = match args {
+        Sexpr::List(l) => list_items(l),
+        _ => return Err(Unwind::from(format!("{} - args must be a list", name))),
+    };
+
+    let head: Vec<Sexpr> = match args.first() {
+        Some(Sexpr::List(l)) => list_items(l).to_vec(),
+        Some(_) => return Err(Unwind::from(format!("{} - first arg must be a list", name))),
+        None => return Err(Unwind::from(format!("{} - no args", name))),
+    };
+    Ok((head, args[1..].to_vec()))
+}
+
+fn loop_var(name: &str, head: &[Sexpr]) -> Result<String, Unwind> {
+    match head.first() {
+        Some(Sexpr::Symbol(s)) => Ok(s.clone()),
+        _ => Err(Unwind::from(format!(
+            "{} - loop variable must be a symbol",
+            name
+        ))),
+    }
+}
+
+// loops bind their variables for the length of the loop and are a block named NIL
+fn run_loop(
+    alist: &mut Vec<(String, Sexpr)>,
+    body: impl FnOnce(&mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind>,
+) -> Result<Sexpr, Unwind> {
+    let depth = alist.len();
+    let result = catch_return("NIL", body(alist));
+    alist.truncate(depth);
+    result
+}
+
+// (dotimes (i n result) body...)
+pub fn dotimes(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (head, body) = loop_head("dotimes", &args)?;
+    let var: String = loop_var("dotimes", &head)?;
+    let count: i64 = match evaluate(head.get(1).cloned().unwrap_or(Sexpr::Nil), alist)? {
+        Sexpr::Integer(i) => i,
+        _ => return Err(Unwind::from("dotimes - count must be an int")),
+    };
+
+    run_loop(alist, |alist| {
+        alist.push((var, Sexpr::Integer(0)));
+        let slot = alist.len() - 1;
+        for i in 0..count {
+            loop_step()?;
+            alist[slot].1 = Sexpr::Integer(i);
+            progn_forms(&body, alist)?;
+        }
+        alist[slot].1 = Sexpr::Integer(count.max(0));
+        evaluate(head.get(2).cloned().unwrap_or(Sexpr::Nil), alist)
+    })
+}
+
+// (dolist (x list result) body...)
+pub fn dolist(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (head, body) = loop_head("dolist", &args)?;
+    let var: String = loop_var("dolist", &head)?;
+    let items: Vec<Sexpr> = match evaluate(head.get(1).cloned().unwrap_or(Sexpr::Nil), alist)? {
+        Sexpr::List(l) => list_items(&l).to_vec(),
+        Sexpr::Nil => Vec::new(),
+        _ => return Err(Unwind::from("dolist - arg must be a list")),
+    };
+
+    run_loop(alist, |alist| {
+        alist.push((var, Sexpr::Nil));
+        let slot = alist.len() - 1;
+        for item in items {
+            loop_step()?;
+            alist[slot].1 = item;
+            progn_forms(&body, alist)?;
+        }
+        alist[slot].1 = Sexpr::Nil;
+        evaluate(head.get(2).cloned().unwrap_or(Sexpr::Nil), alist)
+    })
+}
+
+// (do ((var init step)...) (end-test result...) body...), do* binds and steps in sequence
+pub fn do_loop(
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+    sequential: bool,
+) -> Result<Sexpr, Unwind> {
+    let name: &str = if sequential { "do*" } else { "do" };
+    let (specs, rest) = loop_head(name, &args)?;
+    let end: Vec<Sexpr> = match rest.first() {
+        Some(Sexpr::List(l)) => list_items(l).to_vec(),
+        _ => {
+            return Err(Unwind::from(format!(
+                "{} - second arg must be (end-test result...)",
+                name
+            )))
+        }
+    };
+    let body: Vec<Sexpr> = rest[1..].to_vec();
+
+    // (name init step) with init and step both optional
+    let mut vars: Vec<(String, Sexpr, Option<Sexpr>)> = Vec::new();
+    for spec in specs {
+        vars.push(match spec {
+            Sexpr::Symbol(s) => (s, Sexpr::Nil, None),
+            Sexpr::List(l) => {
+                let l = list_items(&l);
+                match l.first() {
+                    Some(Sexpr::Symbol(s)) => (
+                        s.clone(),
+                        l.get(1).cloned().unwrap_or(Sexpr::Nil),
+                        l.get(2).cloned(),
+                    ),
+                    _ => {
+                        return Err(Unwind::from(format!(
+                            "{} - variable must be a symbol",
+                            name
+                        )))
+                    }
+                }
+            }
+            _ => {
+                return Err(Unwind::from(format!(
+                    "{} - variable must be a symbol or list",
+                    name
+                )))
+            }
+        });
+    }
+
+    run_loop(alist, |alist| {
+        let base = alist.len();
+        if sequential {
+            for (var, init, _) in &vars {
+                let value = evaluate(init.clone(), alist)?;
+                alist.push((var.clone(), value));
+            }
+        } else {
+            let mut values: Vec<Sexpr> = Vec::new();
+            for (_, init, _) in &vars {
+                values.push(evaluate(init.clone(), alist)?);
+            }
+            for ((var, _, _), value) in vars.iter().zip(values) {
+                alist.push((var.clone(), value));
+            }
+        }
+
+        loop {
+            loop_step()?;
+            let test: Sexpr = evaluate(end.first().cloned().unwrap_or(Sexpr::Nil), alist)?;
+            if test != Sexpr::Nil {
+                return progn_forms(&end[1..], alist);
+            }
+            progn_forms(&body, alist)?;
+
+            let mut steps: Vec<(usize, Sexpr)> = Vec::new();
+            for (i, (_, _, step)) in vars.iter().enumerate() {
+                if let Some(step) = step {
+                    let value = evaluate(step.clone(), alist)?;
+                    if sequential {
+                        alist[base + i].1 = value;
+                    } else {
+                        steps.push((base + i, value));
+                    }
+                }
+            }
+            for (slot, value) in steps {
+                alist[slot].1 = value;
+            }
+        }
+    })
+}
+
+// one clause of the LOOP subset
+enum LoopClause {
+    ForIn(String, Sexpr),
+    ForFrom(String, Sexpr, Option<(bool, Sexpr)>, Sexpr), // var, from, (inclusive, limit), by
+    While(Sexpr),
+    Until(Sexpr),
+    Do(Vec<Sexpr>),
+    Collect(Sexpr),
+    Sum(Sexpr),
+}
+
+// the slot of the variable and what is left to step through for each for clause,
+// a for from clause steps its variable once it has been through the body
+enum ForState {
+    In(usize, Vec<Sexpr>),
+    From(usize, Option<(bool, BigInt)>, BigInt, bool),
+}
+
+fn is_loop_keyword(s: &Sexpr) -> bool {
+    match s {
+        Sexpr::Symbol(s) => matches!(
+            s.as_str(),
+            "FOR" | "WHILE" | "UNTIL" | "DO" | "COLLECT" | "SUM"
+        ),
+        _ => false,
+    }
+}
+
+fn parse_loop(forms: &[Sexpr]) -> Result<Vec<LoopClause>, Unwind> {
+    let mut clauses: Vec<LoopClause> = Vec::new();
+    let mut i = 0;
+    let next = |i: usize, what: &str| -> Result<Sexpr, Unwind> {
+        match forms.get(i) {
+            Some(s) => Ok(s.clone()),
+            None => Err(Unwind::from(format!("loop - {} is missing", what))),
+        }
+    };
+
+    while i < forms.len() {
+        let keyword: String = match &forms[i] {
+            Sexpr::Symbol(s) => s.clone(),
+            s => {
+                return Err(Unwind::from(format!(
+                    "loop - unknown clause {}",
+                    sexpr_to_string(s)
+                )))
+            }
+        };
+        i += 1;
+        match keyword.as_str() {
+            "FOR" => {
+                let var: String = match next(i, "for variable")? {
+                    Sexpr::Symbol(s) => s,
+                    _ => return Err(Unwind::from("loop - for variable must be a symbol")),
+                };
+                let kind: Sexpr = next(i + 1, "for clause")?;
+                i += 2;
+                match kind {
+                    Sexpr::Symbol(s) if s == "IN" => {
+                        clauses.push(LoopClause::ForIn(var, next(i, "for list")?));
+                        i += 1;
+                    }
+                    Sexpr::Symbol(s) if s == "FROM" => {
+                        let from: Sexpr = next(i, "for start")?;
+                        i += 1;
+                        let mut limit: Option<(bool, Sexpr)> = None;
+                        let mut by: Sexpr = Sexpr::Integer(1);
+                        while let Some(Sexpr::Symbol(s)) = forms.get(i) {
+                            match s.as_str() {
+                                "TO" => limit = Some((true, next(i + 1, "for limit")?)),
+                                "BELOW" => limit = Some((false, next(i + 1, "for limit")?)),
+                                "BY" => by = next(i + 1, "for step")?,
+                                _ => break,
+                            }
+                            i += 2;
+                        }
+                        clauses.push(LoopClause::ForFrom(var, from, limit, by));
+                    }
+                    _ => return Err(Unwind::from("loop - for must be followed by in or from")),
+                }
+            }
+            "WHILE" => {
+                clauses.push(LoopClause::While(next(i, "while test")?));
+                i += 1;
+            }
+            "UNTIL" => {
+                clauses.push(LoopClause::Until(next(i, "until test")?));
+                i += 1;
+            }
+            "COLLECT" => {
+                clauses.push(LoopClause::Collect(next(i, "collect form")?));
+                i += 1;
+            }
+            "SUM" => {
+                clauses.push(LoopClause::Sum(next(i, "sum form")?));
+                i += 1;
+            }
+            "DO" => {
+                let start = i;
+                while i < forms.len() && !is_loop_keyword(&forms[i]) {
+                    i += 1;
+                }
+                clauses.push(LoopClause::Do(forms[start..i].to_vec()));
+            }
+            _ => return Err(Unwind::from(format!("loop - unknown clause {}", keyword))),
+        }
+    }
+    Ok(clauses)
+}
+
+fn loop_int(s: Sexpr) -> Result<BigInt, Unwind> {
+    match s {
+        Sexpr::Integer(i) => Ok(BigInt::from(i)),
+        Sexpr::Bignum(b) => Ok(b),
+        _ => Err(Unwind::from("loop - for from/to/by must be ints")),
+    }
+}
+
+// (loop for x in list while test do (form) collect x sum x), or (loop body...) forever
+pub fn loop_macro(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let forms: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("loop - args must be a list")),
+    };
+
+    // the simple loop runs its body until something returns
+    if !forms.first().is_some_and(is_loop_keyword) {
+        return run_loop(alist, |alist| loop {
+            loop_step()?;
+            progn_forms(&forms, alist)?;
+        });
+    }
+
+    let clauses: Vec<LoopClause> = parse_loop(&forms)?;
+    run_loop(alist, |alist| {
+        let mut fors: Vec<ForState> = Vec::new();
+        for clause in &clauses {
+            match clause {
+                LoopClause::ForIn(var, list) => {
+                    let mut items: Vec<Sexpr> = match evaluate(list.clone(), alist)? {
+                        Sexpr::List(l) => list_items(&l).to_vec(),
+                        Sexpr::Nil => Vec::new(),
+                        _ => return Err(Unwind::from("loop - for in needs a list")),
+                    };
+                    items.reverse();
+                    alist.push((var.clone(), Sexpr::Nil));
+                    fors.push(ForState::In(alist.len() - 1, items));
+                }
+                LoopClause::ForFrom(var, from, limit, by) => {
+                    let from: BigInt = loop_int(evaluate(from.clone(), alist)?)?;
+                    let limit: Option<(bool, BigInt)> = match limit {
+                        Some((inclusive, l)) => {
+                            Some((*inclusive, loop_int(evaluate(l.clone(), alist)?)?))
+                        }
+                        None => None,
+                    };
+                    let by: BigInt = loop_int(evaluate(by.clone(), alist)?)?;
+                    if !by.is_positive() {
+                        return Err(Unwind::from("loop - for by must be a positive int"));
+                    }
+                    alist.push((var.clone(), normalize(from)));
+                    fors.push(ForState::From(alist.len() - 1, limit, by, false));
+                }
+                _ => {}
+            }
+        }
+
+        let mut collected: Vec<Sexpr> = Vec::new();
+        let mut sum: Option<Sexpr> = None;
+        if clauses.iter().any(|c| matches!(c, LoopClause::Sum(_))) {
+            sum = Some(Sexpr::Integer(0));
+        }
+        'iteration: loop {
+            loop_step()?;
+            let mut f = 0;
+            for clause in &clauses {
+                match clause {
+                    LoopClause::ForIn(_, _) | LoopClause::ForFrom(_, _, _, _) => {
+                        match &mut fors[f] {
+                            ForState::In(slot, items) => match items.pop() {
+                                Some(item) => alist[*slot].1 = item,
+                                None => break 'iteration,
+                            },
+                            ForState::From(slot, limit, by, stepped) => {
+                                let mut value: BigInt = loop_int(alist[*slot].1.clone())?;
+                                if *stepped {
+                                    value += &*by;
+                                }
+                                *stepped = true;
+                                match limit {
+                                    Some((true, l)) if value > *l => break 'iteration,
+                                    Some((false, l)) if value >= *l => break 'iteration,
+                                    _ => alist[*slot].1 = normalize(value),
+                                }
+                            }
+                        }
+                        f += 1;
+                    }
+                    LoopClause::While(test) => {
+                        if evaluate(test.clone(), alist)? == Sexpr::Nil {
+                            break 'iteration;
+                        }
+                    }
+                    LoopClause::Until(test) => {
+                        if evaluate(test.clone(), alist)? != Sexpr::Nil {
+                            break 'iteration;
+                        }
+                    }
+                    LoopClause::Do(forms) => {
+                        progn_forms(forms, alist)?;
+                    }
+                    LoopClause::Collect(form) => collected.push(evaluate(form.clone(), alist)?),
+                    LoopClause::Sum(form) => {
+                        let value: Sexpr = evaluate(form.clone(), alist)?;
+                        if let Some(total) = sum {
+                            sum = Some(call_function(
+                                Sexpr::Builtin(String::from("+")),
+                                vec![total, value],
+                                alist,
+                            )?);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(total) = sum {
+            Ok(total)
+        } else if clauses.iter().any(|c| matches!(c, LoopClause::Collect(_))) {
+            if collected.is_empty() {
+                Ok(Sexpr::Nil)
+            } else {
+                collected.push(Sexpr::Nil);
+                Ok(Sexpr::List(collected))
+            }
+        } else {
+            Ok(Sexpr::Nil)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        let mut arg: Sexpr = Sexpr::String(String::from("a"));
+        let mut args: Sexpr = Sexpr::List(vec![arg.clone()]);
+        assert!(equal_sexprs(&quote(args.clone()).unwrap(), &arg));
+
+        arg = Sexpr::List(Vec::new());
+        args = Sexpr::List(vec![arg.clone()]);
+        assert!(equal_sexprs(&quote(args.clone()).unwrap(), &arg));
     }
 
     #[test]
@@ -626,17 +1389,14 @@ mod tests {
             Sexpr::Symbol(String::from("QUOTE")),
             Sexpr::List(vec![Sexpr::Integer(1)]),
         ])]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(car(args, alist.clone()), Ok(Sexpr::Integer(1)));
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(car(args, &mut alist), Ok(Sexpr::Integer(1)));
 
         let args: Sexpr = Sexpr::List(vec![Sexpr::List(vec![
             Sexpr::Symbol(String::from("QUOTE")),
             Sexpr::List(vec![Sexpr::Symbol(String::from("X"))]),
         ])]);
-        assert_eq!(
-            car(args, alist.clone()),
-            Ok(Sexpr::Symbol(String::from("X")))
-        );
+        assert_eq!(car(args, &mut alist), Ok(Sexpr::Symbol(String::from("X"))));
     }
 
     #[test]
@@ -645,15 +1405,15 @@ mod tests {
             Sexpr::Symbol(String::from("QUOTE")),
             Sexpr::List(vec![Sexpr::Integer(1)]),
         ])]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(cdr(args, alist.clone()), Ok(Sexpr::List(Vec::new())));
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(cdr(args, &mut alist), Ok(Sexpr::List(Vec::new())));
 
         let args: Sexpr = Sexpr::List(vec![Sexpr::List(vec![
             Sexpr::Symbol(String::from("QUOTE")),
             Sexpr::List(vec![Sexpr::Symbol(String::from("X")), Sexpr::Integer(1)]),
         ])]);
         assert_eq!(
-            cdr(args, alist.clone()),
+            cdr(args, &mut alist),
             Ok(Sexpr::List(vec![Sexpr::Integer(1)]))
         );
     }
@@ -661,204 +1421,274 @@ mod tests {
     #[test]
     fn test_setq() {
         let args: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("X")), Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(setq(args, alist.clone()), Ok(Sexpr::Integer(1)));
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(setq(args, &mut alist), Ok(Sexpr::Integer(1)));
         let v: Sexpr = Sexpr::Symbol(String::from("X"));
-        let alist = OBLIST.lock().unwrap().clone();
-        assert_eq!(evaluate(v, alist.clone()), Ok(Sexpr::Integer(1)));
+        let mut alist = OBLIST.lock().unwrap().clone();
+        assert_eq!(evaluate(v, &mut alist), Ok(Sexpr::Integer(1)));
         // test for OBLIST change through cargo run
         // i can put this in the test_evaluate function in eval.rs later if needed
     }
 
+    #[test]
+    fn test_setq_scope() {
+        // a local binding is updated in place instead of creating a global
+        let args: Sexpr = Sexpr::List(vec![
+            Sexpr::Symbol(String::from("SETQ-LOCAL")),
+            Sexpr::Integer(5),
+            Sexpr::Nil,
+        ]);
+        let mut alist: Vec<(String, Sexpr)> = vec![(String::from("SETQ-LOCAL"), Sexpr::Integer(1))];
+        assert_eq!(setq(args, &mut alist), Ok(Sexpr::Integer(5)));
+        assert_eq!(alist[0].1, Sexpr::Integer(5));
+        assert!(!OBLIST
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(s, _)| s == "SETQ-LOCAL"));
+
+        // redefining a global replaces it
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        for i in 0..3 {
+            let args: Sexpr = Sexpr::List(vec![
+                Sexpr::Symbol(String::from("SETQ-GLOBAL")),
+                Sexpr::Integer(i),
+            ]);
+            assert_eq!(setq(args, &mut alist), Ok(Sexpr::Integer(i)));
+        }
+        let oblist = OBLIST.lock().unwrap().clone();
+        let bindings: Vec<&(String, Sexpr)> =
+            oblist.iter().filter(|(s, _)| s == "SETQ-GLOBAL").collect();
+        assert_eq!(
+            bindings,
+            vec![&(String::from("SETQ-GLOBAL"), Sexpr::Integer(2))]
+        );
+
+        // multiple pairs are assigned in order
+        let args: Sexpr = Sexpr::List(vec![
+            Sexpr::Symbol(String::from("SETQ-LOCAL")),
+            Sexpr::Integer(1),
+            Sexpr::Symbol(String::from("SETQ-OTHER")),
+            Sexpr::Symbol(String::from("SETQ-LOCAL")),
+            Sexpr::Nil,
+        ]);
+        let mut alist: Vec<(String, Sexpr)> = vec![
+            (String::from("SETQ-LOCAL"), Sexpr::Nil),
+            (String::from("SETQ-OTHER"), Sexpr::Nil),
+        ];
+        assert_eq!(setq(args, &mut alist), Ok(Sexpr::Integer(1)));
+        assert_eq!(alist[1].1, Sexpr::Integer(1));
+
+        // a param assigned inside a function body stays local to the call
+        let params = Sexpr::List(vec![Sexpr::Symbol(String::from("SETQ-PARAM")), Sexpr::Nil]);
+        let body = Sexpr::List(vec![
+            Sexpr::Symbol(String::from("SETQ")),
+            Sexpr::Symbol(String::from("SETQ-PARAM")),
+            Sexpr::Integer(5),
+            Sexpr::Nil,
+        ]);
+        let lambda = Sexpr::List(vec![params, body, Sexpr::Nil]);
+        let args = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Nil]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(
+            func(String::from("F"), lambda, args, &mut alist),
+            Ok(Sexpr::Integer(5))
+        );
+        assert!(alist.is_empty());
+        assert!(!OBLIST
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(s, _)| s == "SETQ-PARAM"));
+    }
+
+    #[test]
+    fn test_setf() {
+        let list = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(2), Sexpr::Nil]);
+        let mut alist: Vec<(String, Sexpr)> = vec![(String::from("L"), list)];
+
+        // (setf (car l) 9)
+        let args: Sexpr = Sexpr::List(vec![
+            Sexpr::List(vec![
+                Sexpr::Symbol(String::from("CAR")),
+                Sexpr::Symbol(String::from("L")),
+                Sexpr::Nil,
+            ]),
+            Sexpr::Integer(9),
+            Sexpr::Nil,
+        ]);
+        assert_eq!(setf(args, &mut alist), Ok(Sexpr::Integer(9)));
+        assert_eq!(
+            alist[0].1,
+            Sexpr::List(vec![Sexpr::Integer(9), Sexpr::Integer(2), Sexpr::Nil])
+        );
+
+        // (setf (nth 1 l) 7)
+        let args: Sexpr = Sexpr::List(vec![
+            Sexpr::List(vec![
+                Sexpr::Symbol(String::from("NTH")),
+                Sexpr::Integer(1),
+                Sexpr::Symbol(String::from("L")),
+                Sexpr::Nil,
+            ]),
+            Sexpr::Integer(7),
+            Sexpr::Nil,
+        ]);
+        assert_eq!(setf(args, &mut alist), Ok(Sexpr::Integer(7)));
+        assert_eq!(
+            alist[0].1,
+            Sexpr::List(vec![Sexpr::Integer(9), Sexpr::Integer(7), Sexpr::Nil])
+        );
+
+        // (setf (car (cdr l)) 3)
+        let args: Sexpr = Sexpr::List(vec![
+            Sexpr::List(vec![
+                Sexpr::Symbol(String::from("CAR")),
+                Sexpr::List(vec![
+                    Sexpr::Symbol(String::from("CDR")),
+                    Sexpr::Symbol(String::from("L")),
+                    Sexpr::Nil,
+                ]),
+                Sexpr::Nil,
+            ]),
+            Sexpr::Integer(3),
+            Sexpr::Nil,
+        ]);
+        assert_eq!(setf(args, &mut alist), Ok(Sexpr::Integer(3)));
+        assert_eq!(
+            alist[0].1,
+            Sexpr::List(vec![Sexpr::Integer(9), Sexpr::Integer(3), Sexpr::Nil])
+        );
+
+        // (setf (cdr l) 1) makes a dotted pair
+        let args: Sexpr = Sexpr::List(vec![
+            Sexpr::List(vec![
+                Sexpr::Symbol(String::from("CDR")),
+                Sexpr::Symbol(String::from("L")),
+                Sexpr::Nil,
+            ]),
+            Sexpr::Integer(1),
+            Sexpr::Nil,
+        ]);
+        assert_eq!(setf(args, &mut alist), Ok(Sexpr::Integer(1)));
+        assert_eq!(
+            alist[0].1,
+            Sexpr::List(vec![Sexpr::Integer(9), Sexpr::Integer(1)])
+        );
+
+        // (setf (nth 5 l) 1) is out of range
+        let args: Sexpr = Sexpr::List(vec![
+            Sexpr::List(vec![
+                Sexpr::Symbol(String::from("NTH")),
+                Sexpr::Integer(5),
+                Sexpr::Symbol(String::from("L")),
+                Sexpr::Nil,
+            ]),
+            Sexpr::Integer(1),
+            Sexpr::Nil,
+        ]);
+        assert_eq!(
+            setf(args, &mut alist),
+            Err(Unwind::from("nth, setf - index out of range"))
+        );
+    }
+
     #[test]
     fn test_equal() {
         let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(equal(args, alist), Ok(Sexpr::T));
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(equal(args, &mut alist), Ok(Sexpr::T));
 
         let args: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("X")), Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(setq(args, alist.clone()), Ok(Sexpr::Integer(1)));
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(setq(args, &mut alist), Ok(Sexpr::Integer(1)));
         let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Symbol(String::from("X"))]);
-        let alist = OBLIST.lock().unwrap().clone();
-        assert_eq!(equal(args, alist), Ok(Sexpr::T));
+        let mut alist = OBLIST.lock().unwrap().clone();
+        assert_eq!(equal(args, &mut alist), Ok(Sexpr::T));
     }
 
     #[test]
     fn test_atom() {
         let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(atom(args, alist), Ok(Sexpr::T));
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(atom(args, &mut alist), Ok(Sexpr::T));
 
         let args: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("X")), Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(setq(args, alist.clone()), Ok(Sexpr::Integer(1)));
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(setq(args, &mut alist), Ok(Sexpr::Integer(1)));
         let args: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("X"))]);
-        let alist = OBLIST.lock().unwrap().clone();
-        assert_eq!(atom(args, alist), Ok(Sexpr::T));
+        let mut alist = OBLIST.lock().unwrap().clone();
+        assert_eq!(atom(args, &mut alist), Ok(Sexpr::T));
 
         let args: Sexpr = Sexpr::List(vec![Sexpr::List(vec![
             Sexpr::Symbol(String::from("QUOTE")),
             Sexpr::List(vec![Sexpr::Symbol(String::from("X")), Sexpr::Integer(1)]),
         ])]);
-        let alist = Vec::new();
-        assert_eq!(atom(args, alist), Ok(Sexpr::Nil));
+        let mut alist = Vec::new();
+        assert_eq!(atom(args, &mut alist), Ok(Sexpr::Nil));
     }
 
     #[test]
     fn test_listp() {
         let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(listp(args, alist), Ok(Sexpr::Nil));
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(listp(args, &mut alist), Ok(Sexpr::Nil));
 
         let args: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("X")), Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(setq(args, alist.clone()), Ok(Sexpr::Integer(1)));
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(setq(args, &mut alist), Ok(Sexpr::Integer(1)));
         let args: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("X"))]);
-        let alist = OBLIST.lock().unwrap().clone();
-        assert_eq!(listp(args, alist), Ok(Sexpr::Nil));
+        let mut alist = OBLIST.lock().unwrap().clone();
+        assert_eq!(listp(args, &mut alist), Ok(Sexpr::Nil));
 
         let args: Sexpr = Sexpr::List(vec![Sexpr::List(vec![
             Sexpr::Symbol(String::from("QUOTE")),
             Sexpr::List(vec![Sexpr::Symbol(String::from("X")), Sexpr::Integer(1)]),
         ])]);
-        let alist = Vec::new();
-        assert_eq!(listp(args, alist), Ok(Sexpr::T));
+        let mut alist = Vec::new();
+        assert_eq!(listp(args, &mut alist), Ok(Sexpr::T));
 
         let args: Sexpr = Sexpr::List(vec![Sexpr::Nil]);
-        let alist = Vec::new();
-        assert_eq!(listp(args, alist), Ok(Sexpr::T));
+        let mut alist = Vec::new();
+        assert_eq!(listp(args, &mut alist), Ok(Sexpr::T));
     }
 
     #[test]
     fn test_null() {
         let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(null(args, alist), Ok(Sexpr::Nil));
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(null(args, &mut alist), Ok(Sexpr::Nil));
 
         let args: Sexpr = Sexpr::List(vec![Sexpr::Nil]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(null(args, alist), Ok(Sexpr::T));
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(null(args, &mut alist), Ok(Sexpr::T));
 
         let args: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("X")), Sexpr::Nil]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(setq(args, alist.clone()), Ok(Sexpr::Nil));
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("X"))]);
-        let alist = OBLIST.lock().unwrap().clone();
-        assert_eq!(null(args, alist), Ok(Sexpr::T));
-    }
-
-    #[test]
-    fn test_floor() {
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(floor(args, alist), Ok(Sexpr::Integer(1)));
-
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(1.1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(floor(args, alist), Ok(Sexpr::Integer(1)));
-
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("X")), Sexpr::Float(6.7)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(setq(args, alist.clone()), Ok(Sexpr::Float(6.7)));
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(setq(args, &mut alist), Ok(Sexpr::Nil));
         let args: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("X"))]);
-        let alist = OBLIST.lock().unwrap().clone();
-        assert_eq!(floor(args, alist), Ok(Sexpr::Integer(6)));
-    }
-
-    #[test]
-    fn test_add() {
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(add(args, alist), Ok(Sexpr::Integer(2)));
-
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(1.1), Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(add(args, alist), Ok(Sexpr::Float(2.1)));
-
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(1.1), Sexpr::Float(1.1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(add(args, alist), Ok(Sexpr::Float(2.2)));
-    }
-
-    #[test]
-    fn test_subtract() {
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(subtract(args, alist), Ok(Sexpr::Integer(0)));
-
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(100.0), Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(subtract(args, alist), Ok(Sexpr::Float(99.0)));
-
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(1.1), Sexpr::Float(1.1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(subtract(args, alist), Ok(Sexpr::Float(0.0)));
-    }
-
-    #[test]
-    fn test_multiply() {
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(multiply(args, alist), Ok(Sexpr::Integer(1)));
-
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(100.0), Sexpr::Integer(2)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(multiply(args, alist), Ok(Sexpr::Float(200.0)));
-
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(10.0), Sexpr::Float(1.1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(multiply(args, alist), Ok(Sexpr::Float(11.0)));
-    }
-
-    #[test]
-    fn test_divide() {
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(divide(args, alist), Ok(Sexpr::Integer(1)));
-
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(100.0), Sexpr::Integer(2)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(divide(args, alist), Ok(Sexpr::Float(50.0)));
-
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(10.0), Sexpr::Float(2.0)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(divide(args, alist), Ok(Sexpr::Float(5.0)));
-    }
-
-    #[test]
-    fn test_modulo() {
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(modulo(args, alist), Ok(Sexpr::Integer(0)));
-
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(51.0), Sexpr::Integer(50)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(modulo(args, alist), Ok(Sexpr::Float(1.0)));
-
-        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(10.0), Sexpr::Float(2.0)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(modulo(args, alist), Ok(Sexpr::Float(0.0)));
+        let mut alist = OBLIST.lock().unwrap().clone();
+        assert_eq!(null(args, &mut alist), Ok(Sexpr::T));
     }
 
     #[test]
     fn test_eval() {
         let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1)]);
         let mut alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(eval(args, alist.clone()), Ok(Sexpr::Integer(1)));
+        assert_eq!(eval(args, &mut alist), Ok(Sexpr::Integer(1)));
 
         let args: Sexpr = Sexpr::List(vec![Sexpr::Nil]);
-        assert_eq!(eval(args, alist.clone()), Ok(Sexpr::Nil));
+        assert_eq!(eval(args, &mut alist), Ok(Sexpr::Nil));
 
         let args: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("X"))]);
         alist.push((String::from("X"), Sexpr::Integer(1)));
-        assert_eq!(eval(args, alist.clone()), Ok(Sexpr::Integer(1)));
+        assert_eq!(eval(args, &mut alist), Ok(Sexpr::Integer(1)));
 
         let args: Sexpr = Sexpr::List(vec![Sexpr::List(vec![
             Sexpr::Symbol(String::from("QUOTE")),
             Sexpr::Symbol(String::from("X")),
         ])]);
-        assert_eq!(eval(args, alist.clone()), Ok(Sexpr::Integer(1)));
+        assert_eq!(eval(args, &mut alist), Ok(Sexpr::Integer(1)));
     }
 
     #[test]
@@ -866,9 +1696,9 @@ mod tests {
         let mut arg1: Sexpr = Sexpr::Integer(1);
         let mut arg2: Sexpr = Sexpr::Integer(1);
         let mut args: Sexpr = Sexpr::List(vec![arg1, arg2]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
         let mut result: Sexpr = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(1)]);
-        assert_eq!(cons(args, alist.clone()), Ok(result));
+        assert_eq!(cons(args, &mut alist), Ok(result));
 
         arg1 = Sexpr::Integer(1);
         arg2 = Sexpr::List(vec![
@@ -877,7 +1707,7 @@ mod tests {
         ]);
         args = Sexpr::List(vec![arg1, arg2]);
         result = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(1), Sexpr::Nil]);
-        assert_eq!(cons(args, alist.clone()), Ok(result));
+        assert_eq!(cons(args, &mut alist), Ok(result));
 
         arg1 = Sexpr::List(vec![
             Sexpr::Symbol(String::from("QUOTE")),
@@ -889,7 +1719,7 @@ mod tests {
             Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Nil]),
             Sexpr::Integer(1),
         ]);
-        assert_eq!(cons(args, alist.clone()), Ok(result));
+        assert_eq!(cons(args, &mut alist), Ok(result));
     }
 
     #[test]
@@ -900,7 +1730,7 @@ mod tests {
             Sexpr::Nil,
         ])]);
         let mut alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(cond(args, alist.clone()), Ok(Sexpr::Integer(1)));
+        assert_eq!(cond(args, &mut alist), Ok(Sexpr::Integer(1)));
 
         args = Sexpr::List(vec![Sexpr::List(vec![
             Sexpr::T,
@@ -908,10 +1738,10 @@ mod tests {
             Sexpr::Nil,
         ])]);
         alist.push((String::from("X"), Sexpr::Float(1.11)));
-        assert_eq!(cond(args, alist.clone()), Ok(Sexpr::Float(1.11)));
+        assert_eq!(cond(args, &mut alist), Ok(Sexpr::Float(1.11)));
 
         args = Sexpr::List(vec![Sexpr::Nil]);
-        assert_eq!(cond(args, alist.clone()), Ok(Sexpr::Nil));
+        assert_eq!(cond(args, &mut alist), Ok(Sexpr::Nil));
     }
 
     #[test]
@@ -937,8 +1767,414 @@ mod tests {
             Sexpr::Symbol(String::from("X")),
         ]);
         let lambda = Sexpr::List(vec![params, body]);
+        let args = Sexpr::List(vec![Sexpr::Integer(1)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(
+            func(name.clone(), lambda.clone(), args, &mut alist),
+            Ok(Sexpr::Integer(2))
+        );
+
+        // extra args are an error instead of being ignored
         let args = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(1)]);
-        let alist: Vec<(String, Sexpr)> = Vec::new();
-        assert_eq!(func(name, lambda, args, alist), Ok(Sexpr::Integer(2)));
+        assert_eq!(
+            func(name, lambda, args, &mut alist),
+            Err(Unwind::from("ADD, func - too many args, expected (X)"))
+        );
+        assert!(alist.is_empty());
+    }
+
+    #[test]
+    fn test_lambda_list() {
+        assert_eq!(
+            run("(defun lambda-list-opt (a &optional (b 10 b-p)) (cons a (cons b b-p)))"),
+            Ok(Sexpr::Symbol(String::from("LAMBDA-LIST-OPT")))
+        );
+        assert_eq!(
+            run("(lambda-list-opt 1)"),
+            Ok(Sexpr::List(vec![
+                Sexpr::Integer(1),
+                Sexpr::Integer(10),
+                Sexpr::Nil
+            ]))
+        );
+        assert_eq!(
+            run("(lambda-list-opt (+ 1 1) 3)"),
+            Ok(Sexpr::List(vec![
+                Sexpr::Integer(2),
+                Sexpr::Integer(3),
+                Sexpr::T
+            ]))
+        );
+        assert_eq!(
+            run("(lambda-list-opt)"),
+            Err(Unwind::from(
+                "LAMBDA-LIST-OPT, func - not enough args, expected (A &OPTIONAL (B 10 B-P))"
+            ))
+        );
+        assert_eq!(
+            run("(lambda-list-opt 1 2 3)"),
+            Err(Unwind::from(
+                "LAMBDA-LIST-OPT, func - too many args, expected (A &OPTIONAL (B 10 B-P))"
+            ))
+        );
+
+        // defaults can refer to the params before them
+        assert_eq!(
+            run("((lambda (a &optional (b a)) (+ a b)) 4)"),
+            Ok(Sexpr::Integer(8))
+        );
+
+        assert_eq!(
+            run("((lambda (a &rest r) r) 1 2 3)"),
+            Ok(Sexpr::List(vec![
+                Sexpr::Integer(2),
+                Sexpr::Integer(3),
+                Sexpr::Nil
+            ]))
+        );
+        assert_eq!(run("((lambda (a &rest r) r) 1)"), Ok(Sexpr::Nil));
+
+        assert_eq!(
+            run("((lambda (&key (a 1) (b 2 b-p)) (cons a (cons b b-p))) :b 5)"),
+            Ok(Sexpr::List(vec![
+                Sexpr::Integer(1),
+                Sexpr::Integer(5),
+                Sexpr::T
+            ]))
+        );
+        assert_eq!(
+            run("((lambda (&key a) a) :c 5)"),
+            Err(Unwind::from(
+                "LAMBDA, func - unknown keyword :C, expected (&KEY A)"
+            ))
+        );
+        assert_eq!(
+            run("((lambda (&key a) a) :a)"),
+            Err(Unwind::from(
+                "LAMBDA, func - odd number of keyword args, expected (&KEY A)"
+            ))
+        );
+        assert_eq!(
+            run("((lambda (&rest r &key a) r) :a 1)"),
+            Ok(Sexpr::List(vec![
+                Sexpr::Symbol(String::from(":A")),
+                Sexpr::Integer(1),
+                Sexpr::Nil
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_function() {
+        assert_eq!(run("#'car"), Ok(Sexpr::Builtin(String::from("CAR"))));
+        assert_eq!(run("(function +)"), Ok(Sexpr::Builtin(String::from("+"))));
+        assert_eq!(
+            run("#'function-not-defined"),
+            Err(Unwind::from("FUNCTION-NOT-DEFINED function not found"))
+        );
+        assert_eq!(run("#'cond"), Err(Unwind::from("COND function not found")));
+
+        let f: Sexpr = run("#'(lambda (x) x)").unwrap();
+        assert!(matches!(f, Sexpr::Lambda(_, _)));
+    }
+
+    #[test]
+    fn test_funcall() {
+        assert_eq!(run("(funcall #'+ 1 2)"), Ok(Sexpr::Integer(3)));
+        assert_eq!(run("(funcall '+ 1 2)"), Ok(Sexpr::Integer(3)));
+        assert_eq!(run("(funcall #'car '(1 2))"), Ok(Sexpr::Integer(1)));
+        assert_eq!(
+            run("(funcall (lambda (x &optional (y 2)) (* x y)) 5)"),
+            Ok(Sexpr::Integer(10))
+        );
+
+        // a function stored in a variable
+        run("(setq funcall-fn #'cons)").unwrap();
+        assert_eq!(
+            run("(funcall funcall-fn 1 nil)"),
+            Ok(Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Nil]))
+        );
+        assert_eq!(
+            run("(funcall 1 2)"),
+            Err(Unwind::from("1 is not a function"))
+        );
+    }
+
+    #[test]
+    fn test_apply_function() {
+        assert_eq!(run("(apply #'+ '(1 2))"), Ok(Sexpr::Integer(3)));
+        assert_eq!(run("(apply #'+ 1 '(2))"), Ok(Sexpr::Integer(3)));
+        assert_eq!(
+            run("(apply (lambda (&rest r) r) 1 2 '(3))"),
+            Ok(Sexpr::List(vec![
+                Sexpr::Integer(1),
+                Sexpr::Integer(2),
+                Sexpr::Integer(3),
+                Sexpr::Nil
+            ]))
+        );
+        assert_eq!(
+            run("(apply #'funcall #'car '((1 2)))"),
+            Ok(Sexpr::Integer(1))
+        );
+        assert_eq!(
+            run("(apply #'+ 1 2)"),
+            Err(Unwind::from("apply - last arg must be a list"))
+        );
+    }
+
+    fn list_of(items: Vec<Sexpr>) -> Sexpr {
+        let mut items = items;
+        items.push(Sexpr::Nil);
+        Sexpr::List(items)
+    }
+
+    #[test]
+    fn test_progn() {
+        assert_eq!(run("(progn 1 2 3)"), Ok(Sexpr::Integer(3)));
+        assert_eq!(run("(progn)"), Ok(Sexpr::Nil));
+    }
+
+    #[test]
+    fn test_return_from() {
+        run("(defun return-from-find (l) (dolist (x l) (cond ((equal x 2) (return-from return-from-find 'found)))) 'missing)").unwrap();
+        assert_eq!(
+            run("(return-from-find '(1 2 3))"),
+            Ok(Sexpr::Symbol(String::from("FOUND")))
+        );
+        assert_eq!(
+            run("(return-from-find '(1))"),
+            Ok(Sexpr::Symbol(String::from("MISSING")))
+        );
+        assert_eq!(
+            run("(return 1)"),
+            Err(Unwind::Return(String::from("NIL"), Sexpr::Integer(1)))
+        );
+    }
+
+    #[test]
+    fn test_dotimes() {
+        assert_eq!(
+            run("(progn (setq dotimes-sum 0) (dotimes (i 5 dotimes-sum) (setq dotimes-sum (+ dotimes-sum i))))"),
+            Ok(Sexpr::Integer(10))
+        );
+        assert_eq!(
+            run("(dotimes (i 10) (cond ((equal i 3) (return i))))"),
+            Ok(Sexpr::Integer(3))
+        );
+        assert_eq!(run("(dotimes (i 3 i))"), Ok(Sexpr::Integer(3)));
+    }
+
+    #[test]
+    fn test_dolist() {
+        assert_eq!(
+            run("(dolist (x '(1 2 3) 'done))"),
+            Ok(Sexpr::Symbol(String::from("DONE")))
+        );
+        assert_eq!(
+            run("(progn (setq dolist-acc nil) (dolist (x '(1 2 3) dolist-acc) (setq dolist-acc (cons x dolist-acc))))"),
+            Ok(list_of(vec![
+                Sexpr::Integer(3),
+                Sexpr::Integer(2),
+                Sexpr::Integer(1)
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_do_loop() {
+        assert_eq!(
+            run("(do ((i 0 (+ i 1)) (acc nil (cons i acc))) ((equal i 3) acc))"),
+            Ok(list_of(vec![
+                Sexpr::Integer(2),
+                Sexpr::Integer(1),
+                Sexpr::Integer(0)
+            ]))
+        );
+        // do steps in parallel, do* in sequence
+        assert_eq!(
+            run("(do ((i 0 (+ i 1)) (j 0 i)) ((equal i 2) j))"),
+            Ok(Sexpr::Integer(1))
+        );
+        assert_eq!(
+            run("(do* ((i 0 (+ i 1)) (j i i)) ((equal i 2) j))"),
+            Ok(Sexpr::Integer(2))
+        );
+    }
+
+    #[test]
+    fn test_loop_macro() {
+        assert_eq!(
+            run("(loop for x in '(1 2 3) collect (* x x))"),
+            Ok(list_of(vec![
+                Sexpr::Integer(1),
+                Sexpr::Integer(4),
+                Sexpr::Integer(9)
+            ]))
+        );
+        assert_eq!(
+            run("(loop for i from 1 to 4 sum i)"),
+            Ok(Sexpr::Integer(10))
+        );
+        assert_eq!(
+            run("(loop for i from 0 below 6 by 2 collect i)"),
+            Ok(list_of(vec![
+                Sexpr::Integer(0),
+                Sexpr::Integer(2),
+                Sexpr::Integer(4)
+            ]))
+        );
+        assert_eq!(
+            run("(loop for i from 9223372036854775806 to 9223372036854775807 collect i)"),
+            Ok(list_of(vec![
+                Sexpr::Integer(i64::MAX - 1),
+                Sexpr::Integer(i64::MAX)
+            ]))
+        );
+        assert_eq!(
+            run("(loop for i from 9223372036854775807 below 9223372036854775810 by 2 collect i)"),
+            Ok(list_of(vec![
+                Sexpr::Integer(i64::MAX),
+                Sexpr::Bignum(BigInt::from(i64::MAX) + 2)
+            ]))
+        );
+        assert_eq!(
+            run("(loop for i from 1 to 3 by 0 collect i)"),
+            Err(Unwind::from("loop - for by must be a positive int"))
+        );
+        assert_eq!(
+            run("(loop for i from 1 to 3 by -1 collect i)"),
+            Err(Unwind::from("loop - for by must be a positive int"))
+        );
+        assert_eq!(
+            run("(loop for x in '(1 2 3 4) while (null (equal x 3)) collect x)"),
+            Ok(list_of(vec![Sexpr::Integer(1), Sexpr::Integer(2)]))
+        );
+        assert_eq!(
+            run("(loop for x in '(1 2) for i from 10 collect (cons x i))"),
+            Ok(list_of(vec![
+                Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(10)]),
+                Sexpr::List(vec![Sexpr::Integer(2), Sexpr::Integer(11)])
+            ]))
+        );
+        assert_eq!(
+            run("(progn (setq loop-n 0) (loop (setq loop-n (+ loop-n 1)) (cond ((equal loop-n 5) (return loop-n)))))"),
+            Ok(Sexpr::Integer(5))
+        );
+        assert_eq!(
+            run("(loop for x in '(1 2 3) do (cond ((equal x 2) (return 'two))))"),
+            Ok(Sexpr::Symbol(String::from("TWO")))
+        );
+        assert_eq!(
+            run("(loop for i from 1 to 3 frob i)"),
+            Err(Unwind::from("loop - unknown clause FROB"))
+        );
+    }
+
+    #[test]
+    fn test_block() {
+        assert_eq!(
+            run("(block outer (block inner (return-from outer 1)) 2)"),
+            Ok(Sexpr::Integer(1))
+        );
+        assert_eq!(
+            run("(block outer (block inner (return-from inner 1)) 2)"),
+            Ok(Sexpr::Integer(2))
+        );
+        assert_eq!(run("(block nil (return 3) 4)"), Ok(Sexpr::Integer(3)));
+        assert_eq!(
+            run("(return-from nowhere 1)"),
+            Err(Unwind::Return(String::from("NOWHERE"), Sexpr::Integer(1)))
+        );
+    }
+
+    #[test]
+    fn test_catch_throw() {
+        assert_eq!(
+            run("(catch 'done (dotimes (i 10) (cond ((equal i 4) (throw 'done i)))) 'never)"),
+            Ok(Sexpr::Integer(4))
+        );
+        // throws pass through functions and catches with other tags
+        run("(defun catch-throw-deep (n) (cond ((equal n 0) (throw 'deep 'bottom)) (t (catch-throw-deep (- n 1)))))").unwrap();
+        assert_eq!(
+            run("(catch 'deep (catch 'other (catch-throw-deep 5)))"),
+            Ok(Sexpr::Symbol(String::from("BOTTOM")))
+        );
+        assert_eq!(
+            run("(throw 'lost 1)"),
+            Err(Unwind::Throw(
+                Sexpr::Symbol(String::from("LOST")),
+                Sexpr::Integer(1)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_unwind_protect() {
+        assert_eq!(
+            run("(progn (setq unwind-protect-log nil) (catch 'tag (unwind-protect (throw 'tag 1) (setq unwind-protect-log 'cleaned))))"),
+            Ok(Sexpr::Integer(1))
+        );
+        assert_eq!(
+            run("unwind-protect-log"),
+            Ok(Sexpr::Symbol(String::from("CLEANED")))
+        );
+
+        // errors still run the cleanup and then keep unwinding
+        assert_eq!(
+            run("(unwind-protect (car 1) (setq unwind-protect-error 'cleaned))"),
+            Err(Unwind::from("car - arg must be list"))
+        );
+        assert_eq!(
+            run("unwind-protect-error"),
+            Ok(Sexpr::Symbol(String::from("CLEANED")))
+        );
+        assert_eq!(run("(unwind-protect 1 2 3)"), Ok(Sexpr::Integer(1)));
+    }
+
+    #[test]
+    fn test_multiple_values() {
+        let list = |items: Vec<Sexpr>| {
+            let mut items = items;
+            items.push(Sexpr::Nil);
+            Ok(Sexpr::List(items))
+        };
+        assert_eq!(run("(values 1 2)"), Ok(Sexpr::Integer(1)));
+        assert_eq!(run("(values)"), Ok(Sexpr::Nil));
+        assert_eq!(
+            run("(multiple-value-list (values 1 2 3))"),
+            list(vec![
+                Sexpr::Integer(1),
+                Sexpr::Integer(2),
+                Sexpr::Integer(3)
+            ])
+        );
+        assert_eq!(
+            run("(multiple-value-bind (a b c) (values 1 2) b)"),
+            Ok(Sexpr::Integer(2))
+        );
+        assert_eq!(
+            run("(multiple-value-bind (a b c) (values 1 2) c)"),
+            Ok(Sexpr::Nil)
+        );
+
+        // progn and function calls pass on the values of their last form
+        run("(defun values-test () (values 'a 'b))").unwrap();
+        assert_eq!(
+            run("(multiple-value-list (progn 1 (values-test)))"),
+            list(vec![
+                Sexpr::Symbol(String::from("A")),
+                Sexpr::Symbol(String::from("B"))
+            ])
+        );
+        // anything else only has its own value
+        assert_eq!(
+            run("(multiple-value-list (car (cons (values 1 2) nil)))"),
+            list(vec![Sexpr::Integer(1)])
+        );
+        assert_eq!(
+            run("(multiple-value-list (progn (setq values-test-x (values 1 2)) values-test-x))"),
+            list(vec![Sexpr::Integer(1)])
+        );
     }
 }
@@ -0,0 +1,460 @@
+use crate::eval::*;
+use crate::parse::*;
+
+// a list is its items and the tail after the last of them, NIL for a proper
+// list; anything that isn't a list is a list of no items with itself as tail
+
+pub fn list_parts(v: &Sexpr) -> (Vec<Sexpr>, Sexpr) {
+    match v {
+        Sexpr::List(l) if !l.is_empty() => (l[..l.len() - 1].to_vec(), l[l.len() - 1].clone()),
+        Sexpr::List(_) => (Vec::new(), Sexpr::Nil),
+        v => (Vec::new(), v.clone()),
+    }
+}
+
+// the items of a proper list, an error naming the function for anything else
+pub fn proper_items(name: &str, v: &Sexpr) -> Result<Vec<Sexpr>, Unwind> {
+    match list_parts(v) {
+        (items, Sexpr::Nil) => Ok(items),
+        _ => Err(Unwind::from(format!(
+            "{} - {} is not a proper list",
+            name,
+            sexpr_to_string(v)
+        ))),
+    }
+}
+
+// items followed by tail, which is spliced in when it is itself a list
+pub fn build_list(mut items: Vec<Sexpr>, tail: Sexpr) -> Sexpr {
+    if items.is_empty() {
+        return tail;
+    }
+    match tail {
+        Sexpr::List(rest) => items.extend(rest),
+        tail => items.push(tail),
+    }
+    Sexpr::List(items)
+}
+
+fn count_arg(name: &str, v: &Sexpr) -> Result<usize, Unwind> {
+    match v {
+        Sexpr::Integer(i) if *i >= 0 => Ok(*i as usize),
+        v => Err(Unwind::from(format!(
+            "{} - {} is not a non-negative integer",
+            name,
+            sexpr_to_string(v)
+        ))),
+    }
+}
+
+pub fn list(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    Ok(make_list(eval_args(args, alist)?))
+}
+
+// (list* 1 2 '(3)) is (1 2 3), the last arg is the tail
+pub fn list_star(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let mut args: Vec<Sexpr> = eval_args(args, alist)?;
+    match args.pop() {
+        Some(tail) => Ok(build_list(args, tail)),
+        None => Err(Unwind::from("list* - needs at least one arg")),
+    }
+}
+
+// every list but the last is copied, the last becomes the tail as it is
+pub fn append(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let mut args: Vec<Sexpr> = eval_args(args, alist)?;
+    let tail: Sexpr = args.pop().unwrap_or(Sexpr::Nil);
+    let mut items: Vec<Sexpr> = Vec::new();
+    for l in &args {
+        items.extend(proper_items("append", l)?);
+    }
+    Ok(build_list(items, tail))
+}
+
+pub fn reverse(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [Sexpr::String(s)] => Ok(Sexpr::String(s.chars().rev().collect())),
+        [l] => {
+            let mut items: Vec<Sexpr> = proper_items("reverse", l)?;
+            items.reverse();
+            Ok(make_list(items))
+        }
+        _ => Err(Unwind::from("reverse - takes exactly one arg")),
+    }
+}
+
+// (nthcdr n list), past the end of a proper list is NIL
+pub fn nthcdr(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (n, l) = match eval_args(args, alist)?.as_slice() {
+        [n, l] => (count_arg("nthcdr", n)?, l.clone()),
+        _ => return Err(Unwind::from("nthcdr - takes an index and a list")),
+    };
+    let (items, tail) = list_parts(&l);
+    match (n <= items.len(), &tail) {
+        (true, _) => Ok(build_list(items[n..].to_vec(), tail)),
+        (false, Sexpr::Nil) => Ok(Sexpr::Nil),
+        (false, _) => Err(Unwind::from(format!(
+            "nthcdr - {} is too short for {}",
+            sexpr_to_string(&l),
+            n
+        ))),
+    }
+}
+
+// (nth n list), NIL past the end
+pub fn nth(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (n, l) = match eval_args(args, alist)?.as_slice() {
+        [n, l] => (count_arg("nth", n)?, l.clone()),
+        _ => return Err(Unwind::from("nth - takes an index and a list")),
+    };
+    let (items, tail) = list_parts(&l);
+    match (items.get(n), &tail) {
+        (Some(item), _) => Ok(item.clone()),
+        (None, Sexpr::Nil) => Ok(Sexpr::Nil),
+        (None, _) => Err(Unwind::from(format!(
+            "nth - {} is too short for {}",
+            sexpr_to_string(&l),
+            n
+        ))),
+    }
+}
+
+// (name list [n]), n defaulting to 1
+fn list_and_count(
+    name: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<(Sexpr, usize), Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [l] => Ok((l.clone(), 1)),
+        [l, n] => Ok((l.clone(), count_arg(name, n)?)),
+        _ => Err(Unwind::from(format!(
+            "{} - takes a list and an optional count",
+            name
+        ))),
+    }
+}
+
+// (last list [n]) is the last n conses of list, tail included
+pub fn last(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (l, n) = list_and_count("last", args, alist)?;
+    let (items, tail) = list_parts(&l);
+    let start: usize = items.len().saturating_sub(n);
+    Ok(build_list(items[start..].to_vec(), tail))
+}
+
+// (butlast list [n]) is a copy of list without its last n items
+pub fn butlast(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (l, n) = list_and_count("butlast", args, alist)?;
+    let (items, _) = list_parts(&l);
+    let end: usize = items.len().saturating_sub(n);
+    Ok(make_list(items[..end].to_vec()))
+}
+
+// items are compared structurally, there being no identity to compare
+
+// (member item list) is the rest of list from the first item equal to item
+pub fn member(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (item, l) = match eval_args(args, alist)?.as_slice() {
+        [item, l] => (item.clone(), l.clone()),
+        _ => return Err(Unwind::from("member - takes an item and a list")),
+    };
+    let (items, tail) = list_parts(&l);
+    Ok(match items.iter().position(|i| *i == item) {
+        Some(i) => build_list(items[i..].to_vec(), tail),
+        None => Sexpr::Nil,
+    })
+}
+
+// the first pair in an association list whose car, or cdr for rassoc,
+// matches; NILs in the list are skipped
+fn find_pair(
+    name: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+    part: fn(Vec<Sexpr>, Sexpr) -> Sexpr,
+) -> Result<Sexpr, Unwind> {
+    let (key, pairs) = match eval_args(args, alist)?.as_slice() {
+        [key, pairs] => (key.clone(), proper_items(name, pairs)?),
+        _ => return Err(Unwind::from(format!("{} - takes a key and a list", name))),
+    };
+    for pair in pairs {
+        match &pair {
+            Sexpr::Nil => continue,
+            Sexpr::List(_) => {
+                let (items, tail) = list_parts(&pair);
+                if part(items, tail) == key {
+                    return Ok(pair);
+                }
+            }
+            p => {
+                return Err(Unwind::from(format!(
+                    "{} - {} is not a cons",
+                    name,
+                    sexpr_to_string(p)
+                )))
+            }
+        }
+    }
+    Ok(Sexpr::Nil)
+}
+
+pub fn assoc_fn(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    find_pair("assoc", args, alist, |items, tail| {
+        items.into_iter().next().unwrap_or(tail)
+    })
+}
+
+pub fn rassoc(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    find_pair("rassoc", args, alist, |items, tail| {
+        build_list(items[1..].to_vec(), tail)
+    })
+}
+
+pub fn copy_list(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [l] => {
+            let (items, tail) = list_parts(l);
+            Ok(build_list(items, tail))
+        }
+        _ => Err(Unwind::from("copy-list - takes exactly one arg")),
+    }
+}
+
+// (remove item seq) is seq without the items equal to item
+pub fn remove(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [Sexpr::Char(c), Sexpr::String(s)] => {
+            Ok(Sexpr::String(s.chars().filter(|x| x != c).collect()))
+        }
+        [_, Sexpr::String(s)] => Ok(Sexpr::String(s.clone())),
+        [item, l] => Ok(make_list(
+            proper_items("remove", l)?
+                .into_iter()
+                .filter(|x| x != item)
+                .collect(),
+        )),
+        _ => Err(Unwind::from("remove - takes an item and a sequence")),
+    }
+}
+
+fn substitute(new: &Sexpr, old: &Sexpr, tree: &Sexpr) -> Sexpr {
+    if tree == old {
+        return new.clone();
+    }
+    match tree {
+        Sexpr::List(_) => {
+            let (items, tail) = list_parts(tree);
+            build_list(
+                items.iter().map(|i| substitute(new, old, i)).collect(),
+                substitute(new, old, &tail),
+            )
+        }
+        atom => atom.clone(),
+    }
+}
+
+// (subst new old tree) replaces every subtree equal to old, the tails of
+// lists included, so (subst 'x nil '(1)) is (1 . X)
+pub fn subst(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [new, old, tree] => Ok(substitute(new, old, tree)),
+        _ => Err(Unwind::from("subst - takes new, old and a tree")),
+    }
+}
+
+// CADR, CDDDR and the rest, up to four A's and D's
+pub fn is_cxr(s: &str) -> bool {
+    match s.strip_prefix('C').and_then(|s| s.strip_suffix('R')) {
+        Some(path) => (2..=4).contains(&path.len()) && path.chars().all(|c| c == 'A' || c == 'D'),
+        None => false,
+    }
+}
+
+// the A's and D's are taken right to left, (cadr l) is (car (cdr l)), and
+// both of NIL are NIL
+pub fn cxr(s: &str, args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let name: String = s.to_lowercase();
+    let mut v: Sexpr = match eval_args(args, alist)?.as_slice() {
+        [v] => v.clone(),
+        _ => return Err(Unwind::from(format!("{} - takes exactly one arg", name))),
+    };
+    for step in s[1..s.len() - 1].chars().rev() {
+        if v == Sexpr::Nil {
+            return Ok(Sexpr::Nil);
+        }
+        if !matches!(v, Sexpr::List(_)) {
+            return Err(Unwind::from(format!(
+                "{} - {} is not a list",
+                name,
+                sexpr_to_string(&v)
+            )));
+        }
+        let (items, tail) = list_parts(&v);
+        v = match step {
+            'A' => items.into_iter().next().unwrap_or(tail),
+            _ if items.is_empty() => Sexpr::Nil,
+            _ => build_list(items[1..].to_vec(), tail),
+        };
+    }
+    Ok(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+
+    fn ints(items: &[i64]) -> Sexpr {
+        make_list(items.iter().map(|i| Sexpr::Integer(*i)).collect())
+    }
+
+    // a list of items ending in tail
+    fn dotted(items: &[i64], tail: i64) -> Sexpr {
+        let mut l: Vec<Sexpr> = items.iter().map(|i| Sexpr::Integer(*i)).collect();
+        l.push(Sexpr::Integer(tail));
+        Sexpr::List(l)
+    }
+
+    #[test]
+    fn test_list_and_list_star() {
+        assert_eq!(run("(list 1 (+ 1 1) 3)"), Ok(ints(&[1, 2, 3])));
+        assert_eq!(run("(list)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(list* 1 2 '(3 4))"), Ok(ints(&[1, 2, 3, 4])));
+        assert_eq!(run("(list* 1 2 3)"), Ok(dotted(&[1, 2], 3)));
+        assert_eq!(run("(list* 1)"), Ok(Sexpr::Integer(1)));
+    }
+
+    #[test]
+    fn test_append_and_reverse() {
+        assert_eq!(
+            run("(append '(1 2) '(3) nil '(4))"),
+            Ok(ints(&[1, 2, 3, 4]))
+        );
+        assert_eq!(run("(append)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(append '(1) 2)"), Ok(dotted(&[1], 2)));
+        assert_eq!(run("(append '(1) '(2 . 3))"), Ok(dotted(&[1, 2], 3)));
+        assert_eq!(
+            run("(append '(1 . 2) '(3))"),
+            Err(Unwind::from("append - (1 . 2) is not a proper list"))
+        );
+        assert_eq!(run("(reverse '(1 2 3))"), Ok(ints(&[3, 2, 1])));
+        assert_eq!(run("(reverse nil)"), Ok(Sexpr::Nil));
+        assert_eq!(
+            run("(reverse \"aλ\")"),
+            Ok(Sexpr::String(String::from("λa")))
+        );
+        assert_eq!(
+            run("(reverse '(1 . 2))"),
+            Err(Unwind::from("reverse - (1 . 2) is not a proper list"))
+        );
+    }
+
+    #[test]
+    fn test_length() {
+        assert_eq!(run("(length '(1 2 3))"), Ok(Sexpr::Integer(3)));
+        assert_eq!(run("(length nil)"), Ok(Sexpr::Integer(0)));
+        assert_eq!(run("(length (cdr '(1)))"), Ok(Sexpr::Integer(0)));
+        assert_eq!(
+            run("(length '(1 . 2))"),
+            Err(Unwind::from(
+                "length - (1 . 2) is not a string or proper list"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_nth_and_nthcdr() {
+        assert_eq!(run("(nth 1 '(1 2 3))"), Ok(Sexpr::Integer(2)));
+        assert_eq!(run("(nth 5 '(1 2 3))"), Ok(Sexpr::Nil));
+        assert_eq!(run("(nth 1 '(1 2 . 3))"), Ok(Sexpr::Integer(2)));
+        assert_eq!(
+            run("(nth 2 '(1 2 . 3))"),
+            Err(Unwind::from("nth - (1 2 . 3) is too short for 2"))
+        );
+        assert_eq!(run("(nthcdr 1 '(1 2 3))"), Ok(ints(&[2, 3])));
+        assert_eq!(run("(nthcdr 3 '(1 2 3))"), Ok(Sexpr::Nil));
+        assert_eq!(run("(nthcdr 9 '(1 2 3))"), Ok(Sexpr::Nil));
+        assert_eq!(run("(nthcdr 2 '(1 2 . 3))"), Ok(Sexpr::Integer(3)));
+        assert_eq!(
+            run("(nth -1 '(1))"),
+            Err(Unwind::from("nth - -1 is not a non-negative integer"))
+        );
+    }
+
+    #[test]
+    fn test_last_and_butlast() {
+        assert_eq!(run("(last '(1 2 3))"), Ok(ints(&[3])));
+        assert_eq!(run("(last '(1 2 3) 2)"), Ok(ints(&[2, 3])));
+        assert_eq!(run("(last '(1 2 3) 0)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(last '(1 2 . 3))"), Ok(dotted(&[2], 3)));
+        assert_eq!(run("(last nil)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(butlast '(1 2 3))"), Ok(ints(&[1, 2])));
+        assert_eq!(run("(butlast '(1 2 3) 5)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(butlast '(1 2 . 3))"), Ok(ints(&[1])));
+    }
+
+    #[test]
+    fn test_member_and_assoc() {
+        assert_eq!(run("(member 2 '(1 2 3))"), Ok(ints(&[2, 3])));
+        assert_eq!(run("(member 4 '(1 2 3))"), Ok(Sexpr::Nil));
+        assert_eq!(run("(member 2 '(1 2 . 3))"), Ok(dotted(&[2], 3)));
+        assert_eq!(run("(member '(1) '(0 (1)))"), Ok(run("'((1))").unwrap()));
+        assert_eq!(run("(assoc 'b '((a . 1) nil (b . 2)))"), run("'(b . 2)"));
+        assert_eq!(run("(assoc 'c '((a . 1)))"), Ok(Sexpr::Nil));
+        assert_eq!(run("(assoc 'b '((a 1) (b 2)))"), run("'(b 2)"));
+        assert_eq!(run("(rassoc 2 '((a . 1) (b . 2)))"), run("'(b . 2)"));
+        assert_eq!(run("(rassoc '(2) '((a 1) (b 2)))"), run("'(b 2)"));
+        // equal tells a dotted list from a proper one, just like member does
+        assert_eq!(run("(equal '(1 . 2) '(1 2))"), Ok(Sexpr::Nil));
+        assert_eq!(run("(equal '((1 . 2)) '((1 2)))"), Ok(Sexpr::Nil));
+        assert_eq!(run("(equal '(1 2 . 3) '(1 2 . 3))"), Ok(Sexpr::T));
+        assert_eq!(run("(member '(1 . 2) '((1 2)))"), Ok(Sexpr::Nil));
+        assert_eq!(
+            run("(assoc 'a '(1))"),
+            Err(Unwind::from("assoc - 1 is not a cons"))
+        );
+        assert_eq!(
+            run("(assoc 'a '((a . 1) . 2))"),
+            Err(Unwind::from("assoc - ((A . 1) . 2) is not a proper list"))
+        );
+    }
+
+    #[test]
+    fn test_copy_remove_subst() {
+        assert_eq!(run("(copy-list '(1 2 . 3))"), Ok(dotted(&[1, 2], 3)));
+        assert_eq!(run("(copy-list nil)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(remove 2 '(1 2 3 2))"), Ok(ints(&[1, 3])));
+        assert_eq!(run("(remove 1 '(1 1))"), Ok(Sexpr::Nil));
+        assert_eq!(
+            run("(remove #\\a \"banana\")"),
+            Ok(Sexpr::String(String::from("bnn")))
+        );
+        assert_eq!(
+            run("(remove 1 '(1 . 2))"),
+            Err(Unwind::from("remove - (1 . 2) is not a proper list"))
+        );
+        assert_eq!(run("(subst 9 1 '(1 (2 1) . 1))"), run("'(9 (2 9) . 9)"));
+        assert_eq!(run("(subst 'x nil '(1 2))"), run("'(1 2 . x)"));
+        assert_eq!(run("(subst '(a b) 2 '(1 2))"), run("'(1 (a b))"));
+        assert_eq!(run("(subst '(a b) 2 '(1 . 2))"), run("'(1 a b)"));
+    }
+
+    #[test]
+    fn test_cxr() {
+        assert!(is_cxr("CADR") && is_cxr("CDDDDR"));
+        assert!(!is_cxr("CAR") && !is_cxr("CADDDDR") && !is_cxr("CXR"));
+        assert_eq!(run("(cadr '(1 2 3))"), Ok(Sexpr::Integer(2)));
+        assert_eq!(run("(cddr '(1 2 3))"), Ok(ints(&[3])));
+        assert_eq!(run("(caddr '(1 2 3))"), Ok(Sexpr::Integer(3)));
+        assert_eq!(run("(cadddr '(1 2 3))"), Ok(Sexpr::Nil));
+        assert_eq!(run("(caar '((1) 2))"), Ok(Sexpr::Integer(1)));
+        assert_eq!(run("(cdar '((1 . 4) 2))"), Ok(Sexpr::Integer(4)));
+        assert_eq!(run("(cddr '(1 2 . 3))"), Ok(Sexpr::Integer(3)));
+        assert_eq!(run("(funcall #'cadr '(1 2))"), Ok(Sexpr::Integer(2)));
+        assert_eq!(
+            run("(caddr '(1 2 . 3))"),
+            Err(Unwind::from("caddr - 3 is not a list"))
+        );
+    }
+}
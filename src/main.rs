@@ -1,20 +1,53 @@
+mod chars;
+mod condition;
+mod debugger;
 mod eval;
+mod files;
 mod lexer;
+mod limits;
 mod lisp;
+mod lists;
+mod numbers;
 mod parse;
+mod profiler;
+mod sandbox;
+mod sequences;
 mod shell;
+mod stepper;
+mod streams;
+mod strings;
 mod test;
+mod types;
 
+use condition::*;
+use debugger::*;
 use eval::*;
 use lexer::*;
-use lisp::*;
+use limits::*;
 use parse::*;
+use profiler::*;
+use sandbox::*;
 use shell::*;
+use streams::*;
+
+use std::io::{stderr, stdout};
+
+#[global_allocator]
+static ALLOCATOR: CountingAlloc = CountingAlloc;
 
 const EXIT: i8 = -1;
 const CONTINUE: i8 = 0;
 
-fn match_command(command: String) -> i8 {
+// REPL commands that stand for a lisp form, :profile expr is (profile expr)
+fn expand_command(command: String) -> String {
+    match command.trim_start().split_once(char::is_whitespace) {
+        Some((c, rest)) if c.eq_ignore_ascii_case(":profile") => format!("(profile {})", rest),
+        _ => command,
+    }
+}
+
+fn match_command(command: String, limits: &Limits) -> i8 {
+    let command: String = expand_command(command);
     match command.replace(" ", "").to_ascii_uppercase().as_str() {
         "EXIT" => EXIT,
         "" => CONTINUE,
@@ -27,9 +60,14 @@ fn match_command(command: String) -> i8 {
                 }
             };
             let symbols: Sexpr = parse(&mut tokens);
-            let alist = OBLIST.lock().unwrap().clone();
-            match evaluate(symbols, alist) {
-                Ok(s) => println!("{}", sexpr_to_string(&s)),
+            // globals live in the OBLIST, so the top level starts without locals
+            let mut alist: Vec<(String, Sexpr)> = Vec::new();
+            match evaluate_with_limits(symbols, &mut alist, limits) {
+                Ok(s) => match print_base("repl", &alist) {
+                    Ok(base) => println!("{}", sexpr_to_string_radix(&s, base)),
+                    Err(e) => println!("Error: {}", e),
+                },
+                Err(Unwind::Abort) => println!("Aborted"),
                 Err(e) => println!("Error: {}", e),
             }
             CONTINUE
@@ -37,16 +75,54 @@ fn match_command(command: String) -> i8 {
     }
 }
 
+// --sandbox takes the builtin groups to allow, everything else is a limit
+fn parse_options(args: &[String]) -> Result<(Limits, Option<Vec<Group>>), String> {
+    let mut sandbox: Option<Vec<Group>> = None;
+    let mut rest: Vec<String> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--sandbox" {
+            match args.next() {
+                Some(groups) => sandbox = Some(parse_groups(groups)?),
+                None => return Err(String::from("--sandbox needs a list of groups")),
+            }
+        } else {
+            rest.push(arg.clone());
+        }
+    }
+    Ok((parse_limits(&rest)?, sandbox))
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (limits, sandbox): (Limits, Option<Vec<Group>>) = match parse_options(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            println!("Error: {}", e);
+            println!("usage: pure_lisp_interpreter [--max-steps N] [--max-depth N] [--max-allocations N] [--timeout MS] [--sandbox pure,io,filesystem,process,time,random]");
+            return;
+        }
+    };
+
     pure_lisp_interpreter_message();
+    // the REPL's output is the terminal's, *error-output* going to stderr so
+    // that it stays apart from results when stdout is redirected
+    set_output_sink(STANDARD_OUTPUT, Box::new(stdout()));
+    set_output_sink(ERROR_OUTPUT, Box::new(stderr()));
+    *DEBUGGER_HOOK.lock().unwrap() = Some(break_loop);
 
     loop {
         match get_command() {
             Ok(command) => {
-                if match_command(command) == EXIT {
+                let status: i8 = match &sandbox {
+                    Some(groups) => with_sandbox(groups, || match_command(command, &limits)),
+                    None => match_command(command, &limits),
+                };
+                if status == EXIT {
                     break;
                 }
             }
+            Err(error) if error == END_OF_INPUT => break,
             Err(error) => println!("Error: {}", error),
         }
     }
@@ -0,0 +1,1209 @@
+use crate::condition::*;
+use crate::eval::*;
+use crate::parse::*;
+
+use crate::lisp::*;
+
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, One, Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+
+// the numeric tower, every arithmetic builtin combines two numbers at a time
+// through arithmetic so contagion is the same everywhere: integers stay
+// integers, dividing them gives a ratio when it isn't exact, and a float
+// anywhere makes the result a float
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+}
+
+pub fn is_number(v: &Sexpr) -> bool {
+    matches!(
+        v,
+        Sexpr::Integer(_) | Sexpr::Bignum(_) | Sexpr::Ratio(_) | Sexpr::Float(_)
+    )
+}
+
+fn is_rational(v: &Sexpr) -> bool {
+    matches!(v, Sexpr::Integer(_) | Sexpr::Bignum(_) | Sexpr::Ratio(_))
+}
+
+fn to_bigint(v: &Sexpr) -> Option<BigInt> {
+    match v {
+        Sexpr::Integer(i) => Some(BigInt::from(*i)),
+        Sexpr::Bignum(b) => Some(b.clone()),
+        _ => None,
+    }
+}
+
+fn to_rational(v: &Sexpr) -> Option<BigRational> {
+    match v {
+        Sexpr::Ratio(r) => Some(*r.clone()),
+        v => to_bigint(v).map(BigRational::from_integer),
+    }
+}
+
+fn to_float(v: &Sexpr) -> f64 {
+    match v {
+        Sexpr::Integer(i) => *i as f64,
+        Sexpr::Bignum(b) => b.to_f64().unwrap_or(f64::NAN),
+        Sexpr::Ratio(r) => r.to_f64().unwrap_or(f64::NAN),
+        Sexpr::Float(f) => *f,
+        _ => f64::NAN,
+    }
+}
+
+// integers that fit in i64 are always Integers, so EQUAL never has to
+// compare an Integer with a Bignum
+pub fn normalize(b: BigInt) -> Sexpr {
+    match b.to_i64() {
+        Some(i) => Sexpr::Integer(i),
+        None => Sexpr::Bignum(b),
+    }
+}
+
+// ratios are kept in lowest terms and never have a denominator of 1
+pub fn normalize_ratio(r: BigRational) -> Sexpr {
+    if r.is_integer() {
+        normalize(r.to_integer())
+    } else {
+        Sexpr::Ratio(Box::new(r))
+    }
+}
+
+fn not_a_number(name: &str, v: &Sexpr) -> Unwind {
+    Unwind::from(format!("{} - {} is not a number", name, sexpr_to_string(v)))
+}
+
+// arithmetic has no bindings to signal with, so the condition comes back
+// unsignaled and the builtin passes it through signaled
+fn division_by_zero(name: &str) -> Unwind {
+    Unwind::Condition(make_condition(
+        "DIVISION-BY-ZERO",
+        format!("{} - division by zero", name),
+    ))
+}
+
+fn floating_point_overflow(name: &str) -> Unwind {
+    Unwind::Condition(make_condition(
+        "FLOATING-POINT-OVERFLOW",
+        format!("{} - floating point overflow", name),
+    ))
+}
+
+// a float too large for a float is infinite, which is never a result
+fn finite(name: &str, f: f64) -> Result<Sexpr, Unwind> {
+    if f.is_infinite() {
+        Err(floating_point_overflow(name))
+    } else {
+        Ok(Sexpr::Float(f))
+    }
+}
+
+fn signaled(
+    result: Result<Sexpr, Unwind>,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Sexpr, Unwind> {
+    result.map_err(|e| match e {
+        Unwind::Condition(c) => signal_error(c, alist),
+        e => e,
+    })
+}
+
+fn is_zero(v: &Sexpr) -> bool {
+    match v {
+        Sexpr::Integer(i) => *i == 0,
+        Sexpr::Float(f) => *f == 0.0,
+        // normalized, so Bignums and Ratios are never zero
+        _ => false,
+    }
+}
+
+// None when the result doesn't fit in i64
+fn integer_arithmetic(op: Op, x: i64, y: i64) -> Option<i64> {
+    match op {
+        Op::Add => x.checked_add(y),
+        Op::Subtract => x.checked_sub(y),
+        Op::Multiply => x.checked_mul(y),
+        // inexact division makes a ratio
+        Op::Divide if x.checked_rem(y) == Some(0) => x.checked_div(y),
+        Op::Divide => None,
+        // mod takes the sign of the divisor, like floor does
+        Op::Modulo => x.checked_rem(y).map(|r| {
+            if r != 0 && (r < 0) != (y < 0) {
+                r + y
+            } else {
+                r
+            }
+        }),
+    }
+}
+
+fn bignum_arithmetic(op: Op, x: BigInt, y: BigInt) -> Sexpr {
+    match op {
+        Op::Add => normalize(x + y),
+        Op::Subtract => normalize(x - y),
+        Op::Multiply => normalize(x * y),
+        Op::Divide => normalize_ratio(BigRational::new(x, y)),
+        Op::Modulo => normalize(x.mod_floor(&y)),
+    }
+}
+
+fn rational_arithmetic(op: Op, x: BigRational, y: BigRational) -> Sexpr {
+    normalize_ratio(match op {
+        Op::Add => x + y,
+        Op::Subtract => x - y,
+        Op::Multiply => x * y,
+        Op::Divide => x / y,
+        Op::Modulo => x.clone() - y.clone() * (x / y).floor(),
+    })
+}
+
+fn float_arithmetic(op: Op, x: f64, y: f64) -> f64 {
+    match op {
+        Op::Add => x + y,
+        Op::Subtract => x - y,
+        Op::Multiply => x * y,
+        Op::Divide => x / y,
+        Op::Modulo => {
+            let r: f64 = x % y;
+            if r != 0.0 && (r < 0.0) != (y < 0.0) {
+                r + y
+            } else {
+                r
+            }
+        }
+    }
+}
+
+pub fn arithmetic(name: &str, op: Op, a: &Sexpr, b: &Sexpr) -> Result<Sexpr, Unwind> {
+    if !is_number(a) {
+        return Err(not_a_number(name, a));
+    }
+    if !is_number(b) {
+        return Err(not_a_number(name, b));
+    }
+    if matches!(op, Op::Divide | Op::Modulo) && is_zero(b) {
+        return Err(division_by_zero(name));
+    }
+
+    match (a, b) {
+        (Sexpr::Integer(x), Sexpr::Integer(y)) => match integer_arithmetic(op, *x, *y) {
+            Some(i) => Ok(Sexpr::Integer(i)),
+            None => Ok(bignum_arithmetic(op, BigInt::from(*x), BigInt::from(*y))),
+        },
+        _ => match (to_bigint(a), to_bigint(b)) {
+            (Some(x), Some(y)) => Ok(bignum_arithmetic(op, x, y)),
+            _ => match (to_rational(a), to_rational(b)) {
+                (Some(x), Some(y)) => Ok(rational_arithmetic(op, x, y)),
+                _ => finite(name, float_arithmetic(op, to_float(a), to_float(b))),
+            },
+        },
+    }
+}
+
+// None when either is NaN, which is unordered against everything
+pub fn compare(name: &str, a: &Sexpr, b: &Sexpr) -> Result<Option<Ordering>, Unwind> {
+    match (a, b) {
+        (Sexpr::Integer(x), Sexpr::Integer(y)) => Ok(Some(x.cmp(y))),
+        _ if !is_number(a) => Err(not_a_number(name, a)),
+        _ if !is_number(b) => Err(not_a_number(name, b)),
+        _ => match (to_rational(a), to_rational(b)) {
+            (Some(x), Some(y)) => Ok(Some(x.cmp(&y))),
+            _ => Ok(to_float(a).partial_cmp(&to_float(b))),
+        },
+    }
+}
+
+// (+ a b c) is ((a + b) + c), with no args it is the identity and with one
+// it is (identity op a), so (- x) negates and (/ x) inverts
+fn fold(
+    name: &str,
+    op: Op,
+    identity: i64,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Sexpr, Unwind> {
+    let result = match eval_args(args, alist)?.as_slice() {
+        [] if matches!(op, Op::Add | Op::Multiply) => Ok(Sexpr::Integer(identity)),
+        [] => Err(Unwind::from(format!("{} - needs at least one arg", name))),
+        [x] => arithmetic(name, op, &Sexpr::Integer(identity), x),
+        [first, rest @ ..] => rest
+            .iter()
+            .try_fold(first.clone(), |acc, x| arithmetic(name, op, &acc, x)),
+    };
+    signaled(result, alist)
+}
+
+pub fn add(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    fold("+", Op::Add, 0, args, alist)
+}
+
+pub fn subtract(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    fold("-", Op::Subtract, 0, args, alist)
+}
+
+pub fn multiply(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    fold("*", Op::Multiply, 1, args, alist)
+}
+
+pub fn divide(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    fold("/", Op::Divide, 1, args, alist)
+}
+
+// (mod number divisor) has exactly two args, as in CL
+pub fn modulo(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let result = match eval_args(args, alist)?.as_slice() {
+        [x, y] => arithmetic("mod", Op::Modulo, x, y),
+        _ => Err(Unwind::from("mod - takes exactly two args")),
+    };
+    signaled(result, alist)
+}
+
+// (< a b c) holds when every neighbouring pair does
+fn compare_chain(
+    name: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+    holds: fn(Ordering) -> bool,
+) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    if args.is_empty() {
+        return Err(Unwind::from(format!("{} - needs at least one arg", name)));
+    }
+
+    let mut result: bool = true;
+    for pair in args.windows(2) {
+        // the rest are still type checked after the answer is known
+        result &= compare(name, &pair[0], &pair[1])?.is_some_and(holds);
+    }
+    if let [x] = args.as_slice() {
+        compare(name, x, x)?;
+    }
+    Ok(if result { Sexpr::T } else { Sexpr::Nil })
+}
+
+pub fn num_equal(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    compare_chain("=", args, alist, Ordering::is_eq)
+}
+
+pub fn less(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    compare_chain("<", args, alist, Ordering::is_lt)
+}
+
+pub fn greater(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    compare_chain(">", args, alist, Ordering::is_gt)
+}
+
+pub fn less_equal(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    compare_chain("<=", args, alist, Ordering::is_le)
+}
+
+pub fn greater_equal(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    compare_chain(">=", args, alist, Ordering::is_ge)
+}
+
+// (/= a b c) holds when no two args are equal, not just neighbouring ones
+pub fn num_not_equal(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    if args.is_empty() {
+        return Err(Unwind::from("/= - needs at least one arg"));
+    }
+
+    let mut result: bool = true;
+    for (i, a) in args.iter().enumerate() {
+        for b in &args[i + 1..] {
+            result &= !compare("/=", a, b)?.is_some_and(Ordering::is_eq);
+        }
+    }
+    if let [x] = args.as_slice() {
+        compare("/=", x, x)?;
+    }
+    Ok(if result { Sexpr::T } else { Sexpr::Nil })
+}
+
+// the arg that is first by ordering, keeping its own type
+fn extremum(
+    name: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+    wanted: Ordering,
+) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let mut best: Sexpr = match args.first() {
+        Some(x) => x.clone(),
+        None => return Err(Unwind::from(format!("{} - needs at least one arg", name))),
+    };
+    compare(name, &best, &best)?;
+    for x in &args[1..] {
+        if compare(name, x, &best)? == Some(wanted) {
+            best = x.clone();
+        }
+    }
+    Ok(best)
+}
+
+pub fn min(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    extremum("min", args, alist, Ordering::Less)
+}
+
+pub fn max(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    extremum("max", args, alist, Ordering::Greater)
+}
+
+fn one_number(name: &str, args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [x] if is_number(x) => Ok(x.clone()),
+        [x] => Err(not_a_number(name, x)),
+        _ => Err(Unwind::from(format!("{} - takes exactly one arg", name))),
+    }
+}
+
+pub fn abs(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match one_number("abs", args, alist)? {
+        Sexpr::Integer(i) => Ok(match i.checked_abs() {
+            Some(a) => Sexpr::Integer(a),
+            None => normalize(BigInt::from(i).abs()),
+        }),
+        Sexpr::Bignum(b) => Ok(normalize(b.abs())),
+        Sexpr::Ratio(r) => Ok(Sexpr::Ratio(Box::new(r.abs()))),
+        x => Ok(Sexpr::Float(to_float(&x).abs())),
+    }
+}
+
+pub fn one_plus(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let x: Sexpr = one_number("1+", args, alist)?;
+    signaled(arithmetic("1+", Op::Add, &x, &Sexpr::Integer(1)), alist)
+}
+
+pub fn one_minus(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let x: Sexpr = one_number("1-", args, alist)?;
+    signaled(
+        arithmetic("1-", Op::Subtract, &x, &Sexpr::Integer(1)),
+        alist,
+    )
+}
+
+// zerop, plusp and minusp compare with 0, which a NaN never is
+fn sign_test(
+    name: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+    holds: fn(Ordering) -> bool,
+) -> Result<Sexpr, Unwind> {
+    let x: Sexpr = one_number(name, args, alist)?;
+    Ok(bool_sexpr(
+        compare(name, &x, &Sexpr::Integer(0))?.is_some_and(holds),
+    ))
+}
+
+pub fn zerop(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    sign_test("zerop", args, alist, Ordering::is_eq)
+}
+
+pub fn plusp(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    sign_test("plusp", args, alist, Ordering::is_gt)
+}
+
+pub fn minusp(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    sign_test("minusp", args, alist, Ordering::is_lt)
+}
+
+pub fn evenp(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let n: BigInt = one_integer("evenp", &one_number("evenp", args, alist)?)?;
+    Ok(bool_sexpr(n.is_even()))
+}
+
+pub fn oddp(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let n: BigInt = one_integer("oddp", &one_number("oddp", args, alist)?)?;
+    Ok(bool_sexpr(n.is_odd()))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Rounding {
+    Floor,
+    Ceiling,
+    Truncate,
+    // to the nearest integer, halfway cases to the even one
+    Round,
+}
+
+fn round_rational(r: &BigRational, rounding: Rounding) -> BigInt {
+    match rounding {
+        Rounding::Floor => r.floor().to_integer(),
+        Rounding::Ceiling => r.ceil().to_integer(),
+        Rounding::Truncate => r.trunc().to_integer(),
+        Rounding::Round => {
+            let floor: BigInt = r.floor().to_integer();
+            let half: BigRational = BigRational::new(BigInt::one(), BigInt::from(2));
+            match (r - BigRational::from_integer(floor.clone())).cmp(&half) {
+                Ordering::Less => floor,
+                Ordering::Greater => floor + 1,
+                Ordering::Equal if floor.is_even() => floor,
+                Ordering::Equal => floor + 1,
+            }
+        }
+    }
+}
+
+fn round_float(f: f64, rounding: Rounding) -> f64 {
+    match rounding {
+        Rounding::Floor => f.floor(),
+        Rounding::Ceiling => f.ceil(),
+        Rounding::Truncate => f.trunc(),
+        Rounding::Round => f.round_ties_even(),
+    }
+}
+
+// (floor number divisor) is the quotient rounded toward negative infinity,
+// with number - quotient * divisor as its second value
+fn divide_rounding(
+    name: &str,
+    rounding: Rounding,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Sexpr, Unwind> {
+    let (number, divisor): (Sexpr, Sexpr) = match eval_args(args, alist)?.as_slice() {
+        [n] => (n.clone(), Sexpr::Integer(1)),
+        [n, d] => (n.clone(), d.clone()),
+        _ => return Err(Unwind::from(format!("{} - takes one or two args", name))),
+    };
+    if !is_number(&number) {
+        return Err(not_a_number(name, &number));
+    }
+    if !is_number(&divisor) {
+        return Err(not_a_number(name, &divisor));
+    }
+    if is_zero(&divisor) {
+        return signaled(Err(division_by_zero(name)), alist);
+    }
+
+    let (quotient, remainder): (Sexpr, Sexpr) = match (to_rational(&number), to_rational(&divisor))
+    {
+        (Some(n), Some(d)) => {
+            let q: BigInt = round_rational(&(n.clone() / d.clone()), rounding);
+            let r: BigRational = n - d * BigRational::from_integer(q.clone());
+            (normalize(q), normalize_ratio(r))
+        }
+        _ => {
+            let (n, d): (f64, f64) = (to_float(&number), to_float(&divisor));
+            let q: f64 = round_float(n / d, rounding);
+            match BigInt::from_f64(q) {
+                Some(i) => (normalize(i), Sexpr::Float(n - q * d)),
+                None => {
+                    return Err(Unwind::from(format!(
+                        "{} - {} has no integer value",
+                        name,
+                        sexpr_to_string(&number)
+                    )))
+                }
+            }
+        }
+    };
+    set_extra_values(vec![remainder]);
+    Ok(quotient)
+}
+
+pub fn floor(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    divide_rounding("floor", Rounding::Floor, args, alist)
+}
+
+pub fn ceiling(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    divide_rounding("ceiling", Rounding::Ceiling, args, alist)
+}
+
+pub fn truncate(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    divide_rounding("truncate", Rounding::Truncate, args, alist)
+}
+
+pub fn round(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    divide_rounding("round", Rounding::Round, args, alist)
+}
+
+fn one_rational(
+    name: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<BigRational, Unwind> {
+    match one_number(name, args, alist)? {
+        x if is_rational(&x) => Ok(to_rational(&x).unwrap()),
+        x => Err(Unwind::from(format!(
+            "{} - {} is not a rational",
+            name,
+            sexpr_to_string(&x)
+        ))),
+    }
+}
+
+pub fn numerator(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    Ok(normalize(
+        one_rational("numerator", args, alist)?.numer().clone(),
+    ))
+}
+
+pub fn denominator(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    Ok(normalize(
+        one_rational("denominator", args, alist)?.denom().clone(),
+    ))
+}
+
+fn float_rational(name: &str, f: f64) -> Result<BigRational, Unwind> {
+    match BigRational::from_float(f) {
+        Some(r) => Ok(r),
+        None => Err(Unwind::from(format!(
+            "{} - {} has no rational value",
+            name, f
+        ))),
+    }
+}
+
+// (rational 0.1) is exactly the float, 3602879701896397/36028797018963968
+pub fn rational(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match one_number("rational", args, alist)? {
+        Sexpr::Float(f) => Ok(normalize_ratio(float_rational("rational", f)?)),
+        x => Ok(x),
+    }
+}
+
+// (rationalize 0.1) is the simplest ratio that reads back as the float, 1/10,
+// found by taking continued fraction convergents until one does
+pub fn rationalize(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let f: f64 = match one_number("rationalize", args, alist)? {
+        Sexpr::Float(f) => f,
+        x => return Ok(x),
+    };
+
+    let mut x: BigRational = float_rational("rationalize", f)?;
+    // the last two convergents, h/k
+    let (mut h0, mut h1): (BigInt, BigInt) = (BigInt::zero(), BigInt::one());
+    let (mut k0, mut k1): (BigInt, BigInt) = (BigInt::one(), BigInt::zero());
+    loop {
+        let a: BigInt = x.floor().to_integer();
+        (h0, h1) = (h1.clone(), &a * &h1 + h0);
+        (k0, k1) = (k1.clone(), &a * &k1 + k0);
+        let convergent: BigRational = BigRational::new(h1.clone(), k1.clone());
+        let fraction: BigRational = x - BigRational::from_integer(a);
+        if convergent.to_f64() == Some(f) || fraction.is_zero() {
+            return Ok(normalize_ratio(convergent));
+        }
+        x = fraction.recip();
+    }
+}
+
+// the globals this module defines, for the OBLIST to start with
+pub fn numeric_globals() -> Vec<(String, Sexpr)> {
+    vec![
+        (String::from("PI"), Sexpr::Float(std::f64::consts::PI)),
+        (String::from("*PRINT-BASE*"), Sexpr::Integer(10)),
+    ]
+}
+
+// real functions of real args only, a result that would be complex is an error
+// and one that is infinite overflowed
+fn real_result(name: &str, f: f64, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    if f.is_nan() {
+        Err(Unwind::from(format!(
+            "{} - the result is not a real number",
+            name
+        )))
+    } else {
+        signaled(finite(name, f), alist)
+    }
+}
+
+fn float_function(
+    name: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+    f: fn(f64) -> f64,
+) -> Result<Sexpr, Unwind> {
+    let x: Sexpr = one_number(name, args, alist)?;
+    real_result(name, f(to_float(&x)), alist)
+}
+
+pub fn sqrt(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    float_function("sqrt", args, alist, f64::sqrt)
+}
+
+pub fn exp(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    float_function("exp", args, alist, f64::exp)
+}
+
+pub fn sin(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    float_function("sin", args, alist, f64::sin)
+}
+
+pub fn cos(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    float_function("cos", args, alist, f64::cos)
+}
+
+pub fn tan(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    float_function("tan", args, alist, f64::tan)
+}
+
+pub fn asin(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    float_function("asin", args, alist, f64::asin)
+}
+
+pub fn acos(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    float_function("acos", args, alist, f64::acos)
+}
+
+fn numbers(name: &str, args: &[Sexpr]) -> Result<(), Unwind> {
+    match args.iter().find(|x| !is_number(x)) {
+        Some(x) => Err(not_a_number(name, x)),
+        None => Ok(()),
+    }
+}
+
+// (atan y x) is the angle of the point (x, y), (atan y) that of (1, y)
+pub fn atan(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    numbers("atan", &args)?;
+    match args.as_slice() {
+        [y] => real_result("atan", to_float(y).atan(), alist),
+        [y, x] => real_result("atan", to_float(y).atan2(to_float(x)), alist),
+        _ => Err(Unwind::from("atan - takes one or two args")),
+    }
+}
+
+// (log x) is the natural logarithm, (log x base) in any base
+pub fn log(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    numbers("log", &args)?;
+    match args.as_slice() {
+        // the log of 0 is -infinity, and every log in base 1 divides by 0
+        [x] | [x, _] if is_zero(x) => signaled(Err(division_by_zero("log")), alist),
+        [_, base] if to_float(base) == 1.0 => signaled(Err(division_by_zero("log")), alist),
+        [x] => real_result("log", to_float(x).ln(), alist),
+        [x, base] => real_result("log", to_float(x).ln() / to_float(base).ln(), alist),
+        _ => Err(Unwind::from("log - takes one or two args")),
+    }
+}
+
+// a rational to an integer power stays exact, anything else is a float
+pub fn expt(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (base, power): (Sexpr, Sexpr) = match eval_args(args, alist)?.as_slice() {
+        [base, power] => (base.clone(), power.clone()),
+        _ => return Err(Unwind::from("expt - takes exactly two args")),
+    };
+    numbers("expt", &[base.clone(), power.clone()])?;
+
+    let exact: Option<(BigRational, BigInt)> = match (to_rational(&base), to_bigint(&power)) {
+        (Some(b), Some(p)) => Some((b, p)),
+        _ => None,
+    };
+    let (b, p): (BigRational, BigInt) = match exact {
+        Some(exact) => exact,
+        None => return real_result("expt", to_float(&base).powf(to_float(&power)), alist),
+    };
+
+    if b.is_zero() && p.is_negative() {
+        return signaled(Err(division_by_zero("expt")), alist);
+    }
+    let magnitude: u32 = match p.abs().to_u32() {
+        Some(m) => m,
+        // 0, 1 and -1 only depend on whether the power is odd
+        None if b.abs().is_one() || b.is_zero() => {
+            if p.is_odd() {
+                1
+            } else {
+                2
+            }
+        }
+        None => return Err(Unwind::from("expt - the power is too large")),
+    };
+    let raised: BigRational = BigRational::new(b.numer().pow(magnitude), b.denom().pow(magnitude));
+    Ok(normalize_ratio(if p.is_negative() {
+        raised.recip()
+    } else {
+        raised
+    }))
+}
+
+fn one_integer(name: &str, v: &Sexpr) -> Result<BigInt, Unwind> {
+    match to_bigint(v) {
+        Some(i) => Ok(i),
+        None => Err(Unwind::from(format!(
+            "{} - {} is not an integer",
+            name,
+            sexpr_to_string(v)
+        ))),
+    }
+}
+
+// the largest integer whose square is no more than n
+pub fn isqrt(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let n: BigInt = one_integer("isqrt", &one_number("isqrt", args, alist)?)?;
+    if n.is_negative() {
+        return Err(Unwind::from(format!("isqrt - {} is negative", n)));
+    }
+    Ok(normalize(n.sqrt()))
+}
+
+// (gcd) is 0, the identity of gcd
+pub fn gcd(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let mut result: BigInt = BigInt::zero();
+    for x in eval_args(args, alist)? {
+        result = result.gcd(&one_integer("gcd", &x)?);
+    }
+    Ok(normalize(result))
+}
+
+// (lcm) is 1, the identity of lcm
+pub fn lcm(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let mut result: BigInt = BigInt::one();
+    for x in eval_args(args, alist)? {
+        result = result.lcm(&one_integer("lcm", &x)?);
+    }
+    Ok(normalize(result))
+}
+
+// (float x) converts any real to a float, the optional prototype can only be
+// a float since there is just the one float type
+pub fn float(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    numbers("float", &args)?;
+    match args.as_slice() {
+        [x] | [x, Sexpr::Float(_)] => signaled(finite("float", to_float(x)), alist),
+        [_, prototype] => Err(Unwind::from(format!(
+            "float - prototype {} is not a float",
+            sexpr_to_string(prototype)
+        ))),
+        _ => Err(Unwind::from("float - takes one or two args")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+
+    #[test]
+    fn test_add() {
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(1)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(add(args, &mut alist), Ok(Sexpr::Integer(2)));
+
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(1.1), Sexpr::Integer(1)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(add(args, &mut alist), Ok(Sexpr::Float(2.1)));
+
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(1.1), Sexpr::Float(1.1)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(add(args, &mut alist), Ok(Sexpr::Float(2.2)));
+
+        assert_eq!(run("(+)"), Ok(Sexpr::Integer(0)));
+        assert_eq!(run("(+ 5)"), Ok(Sexpr::Integer(5)));
+        assert_eq!(run("(+ 1 2 3 4)"), Ok(Sexpr::Integer(10)));
+        assert_eq!(run("(+ 1 2 0.5)"), Ok(Sexpr::Float(3.5)));
+        assert_eq!(run("(+ 1 'a)"), Err(Unwind::from("+ - A is not a number")));
+    }
+
+    #[test]
+    fn test_subtract() {
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(1)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(subtract(args, &mut alist), Ok(Sexpr::Integer(0)));
+
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(100.0), Sexpr::Integer(1)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(subtract(args, &mut alist), Ok(Sexpr::Float(99.0)));
+
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(1.1), Sexpr::Float(1.1)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(subtract(args, &mut alist), Ok(Sexpr::Float(0.0)));
+
+        assert_eq!(run("(- 5)"), Ok(Sexpr::Integer(-5)));
+        assert_eq!(run("(- 10 1 2 3)"), Ok(Sexpr::Integer(4)));
+        assert_eq!(run("(- 1 0.5)"), Ok(Sexpr::Float(0.5)));
+        assert_eq!(run("(-)"), Err(Unwind::from("- - needs at least one arg")));
+    }
+
+    #[test]
+    fn test_multiply() {
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(1)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(multiply(args, &mut alist), Ok(Sexpr::Integer(1)));
+
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(100.0), Sexpr::Integer(2)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(multiply(args, &mut alist), Ok(Sexpr::Float(200.0)));
+
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(10.0), Sexpr::Float(1.1)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(multiply(args, &mut alist), Ok(Sexpr::Float(11.0)));
+
+        assert_eq!(run("(*)"), Ok(Sexpr::Integer(1)));
+        assert_eq!(run("(* 2 3 4)"), Ok(Sexpr::Integer(24)));
+    }
+
+    #[test]
+    fn test_divide() {
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(1)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(divide(args, &mut alist), Ok(Sexpr::Integer(1)));
+
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(100.0), Sexpr::Integer(2)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(divide(args, &mut alist), Ok(Sexpr::Float(50.0)));
+
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(10.0), Sexpr::Float(2.0)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(divide(args, &mut alist), Ok(Sexpr::Float(5.0)));
+
+        assert_eq!(run("(/ 2.0)"), Ok(Sexpr::Float(0.5)));
+        assert_eq!(run("(/ 100 2 5)"), Ok(Sexpr::Integer(10)));
+        assert_eq!(run("(/ 1 4.0)"), Ok(Sexpr::Float(0.25)));
+    }
+
+    #[test]
+    fn test_modulo() {
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1), Sexpr::Integer(1)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(modulo(args, &mut alist), Ok(Sexpr::Integer(0)));
+
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(51.0), Sexpr::Integer(50)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(modulo(args, &mut alist), Ok(Sexpr::Float(1.0)));
+
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(10.0), Sexpr::Float(2.0)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(modulo(args, &mut alist), Ok(Sexpr::Float(0.0)));
+
+        assert_eq!(run("(mod -7 2)"), Ok(Sexpr::Integer(1)));
+        assert_eq!(run("(mod 7 -2)"), Ok(Sexpr::Integer(-1)));
+        assert_eq!(run("(mod -7.0 2)"), Ok(Sexpr::Float(1.0)));
+        assert_eq!(
+            run("(mod 1 2 3)"),
+            Err(Unwind::from("mod - takes exactly two args"))
+        );
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!(run("(< 1 2 3)"), Ok(Sexpr::T));
+        assert_eq!(run("(< 1 3 2)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(> 3 2.5 1)"), Ok(Sexpr::T));
+        assert_eq!(run("(<= 1 1 2)"), Ok(Sexpr::T));
+        assert_eq!(run("(>= 2 2 3)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(= 1 1.0 1)"), Ok(Sexpr::T));
+        assert_eq!(run("(= 1)"), Ok(Sexpr::T));
+        assert_eq!(run("(/= 1 2 3)"), Ok(Sexpr::T));
+        // /= compares every pair, not just neighbours
+        assert_eq!(run("(/= 1 2 1)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(< 1 'a)"), Err(Unwind::from("< - A is not a number")));
+        assert_eq!(run("(= 'a)"), Err(Unwind::from("= - A is not a number")));
+        assert_eq!(run("(<)"), Err(Unwind::from("< - needs at least one arg")));
+    }
+
+    #[test]
+    fn test_floor() {
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Integer(1)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(floor(args, &mut alist), Ok(Sexpr::Integer(1)));
+
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Float(1.1)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(floor(args, &mut alist), Ok(Sexpr::Integer(1)));
+
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("X")), Sexpr::Float(6.7)]);
+        let mut alist: Vec<(String, Sexpr)> = Vec::new();
+        assert_eq!(setq(args, &mut alist), Ok(Sexpr::Float(6.7)));
+        let args: Sexpr = Sexpr::List(vec![Sexpr::Symbol(String::from("X"))]);
+        let mut alist = OBLIST.lock().unwrap().clone();
+        assert_eq!(floor(args, &mut alist), Ok(Sexpr::Integer(6)));
+    }
+
+    fn ratio(n: i64, d: i64) -> Sexpr {
+        Sexpr::Ratio(Box::new(BigRational::new(BigInt::from(n), BigInt::from(d))))
+    }
+
+    #[test]
+    fn test_ratios() {
+        assert_eq!(run("(/ 1 3)"), Ok(ratio(1, 3)));
+        assert_eq!(run("(/ 4 -6)"), Ok(ratio(-2, 3)));
+        assert_eq!(run("(/ 3)"), Ok(ratio(1, 3)));
+        assert_eq!(run("1/3"), Ok(ratio(1, 3)));
+        assert_eq!(run("-4/6"), Ok(ratio(-2, 3)));
+        assert_eq!(run("4/2"), Ok(Sexpr::Integer(2)));
+        assert_eq!(run("(+ 1/3 2/3)"), Ok(Sexpr::Integer(1)));
+        assert_eq!(run("(* 1/3 3/4)"), Ok(ratio(1, 4)));
+        assert_eq!(run("(- 1/2 1)"), Ok(ratio(-1, 2)));
+        assert_eq!(run("(+ 1/2 0.25)"), Ok(Sexpr::Float(0.75)));
+        assert_eq!(run("(mod 7/2 1)"), Ok(ratio(1, 2)));
+        assert_eq!(run("(< 1/3 0.34 1/2)"), Ok(Sexpr::T));
+        assert_eq!(run("(= 1/2 0.5)"), Ok(Sexpr::T));
+        assert_eq!(run("(abs -1/2)"), Ok(ratio(1, 2)));
+        assert_eq!(sexpr_to_string(&run("(/ 2 6)").unwrap()), "1/3");
+
+        assert_eq!(run("(numerator 6/4)"), Ok(Sexpr::Integer(3)));
+        assert_eq!(run("(denominator 6/4)"), Ok(Sexpr::Integer(2)));
+        assert_eq!(run("(denominator 5)"), Ok(Sexpr::Integer(1)));
+        assert_eq!(
+            run("(numerator 0.5)"),
+            Err(Unwind::from("numerator - 0.5 is not a rational"))
+        );
+        assert_eq!(run("(rational 0.5)"), Ok(ratio(1, 2)));
+        assert_eq!(
+            run("(rational 0.1)"),
+            Ok(ratio(3602879701896397, 36028797018963968))
+        );
+        assert_eq!(run("(rationalize 0.1)"), Ok(ratio(1, 10)));
+        assert_eq!(run("(rationalize -0.75)"), Ok(ratio(-3, 4)));
+        assert_eq!(run("(rationalize 3.0)"), Ok(Sexpr::Integer(3)));
+    }
+
+    #[test]
+    fn test_rounding() {
+        let both = |form: &str| run(&format!("(multiple-value-list {})", form));
+        let list = |q: Sexpr, r: Sexpr| Ok(Sexpr::List(vec![q, r, Sexpr::Nil]));
+
+        assert_eq!(
+            both("(floor 7 2)"),
+            list(Sexpr::Integer(3), Sexpr::Integer(1))
+        );
+        assert_eq!(
+            both("(floor -7 2)"),
+            list(Sexpr::Integer(-4), Sexpr::Integer(1))
+        );
+        assert_eq!(
+            both("(ceiling 7 2)"),
+            list(Sexpr::Integer(4), Sexpr::Integer(-1))
+        );
+        assert_eq!(
+            both("(truncate -7 2)"),
+            list(Sexpr::Integer(-3), Sexpr::Integer(-1))
+        );
+        assert_eq!(
+            both("(round 5 2)"),
+            list(Sexpr::Integer(2), Sexpr::Integer(1))
+        );
+        assert_eq!(
+            both("(round 7 2)"),
+            list(Sexpr::Integer(4), Sexpr::Integer(-1))
+        );
+        assert_eq!(both("(floor 7/2)"), list(Sexpr::Integer(3), ratio(1, 2)));
+        assert_eq!(both("(round -5/2)"), list(Sexpr::Integer(-2), ratio(-1, 2)));
+        assert_eq!(
+            both("(floor 1/2 1/3)"),
+            list(Sexpr::Integer(1), ratio(1, 6))
+        );
+        assert_eq!(
+            both("(floor -1.5)"),
+            list(Sexpr::Integer(-2), Sexpr::Float(0.5))
+        );
+        assert_eq!(
+            both("(round 2.5)"),
+            list(Sexpr::Integer(2), Sexpr::Float(0.5))
+        );
+        assert_eq!(
+            both("(truncate 7.5 2)"),
+            list(Sexpr::Integer(3), Sexpr::Float(1.5))
+        );
+        assert_eq!(
+            run("(floor 1e30)"),
+            Ok(Sexpr::Bignum(BigInt::from_f64(1e30).unwrap()))
+        );
+        assert!(matches!(
+            run("(floor 1 0)"),
+            Err(Unwind::Condition(Sexpr::Condition(t, _))) if t == "DIVISION-BY-ZERO"
+        ));
+    }
+
+    fn bignum(s: &str) -> Sexpr {
+        Sexpr::Bignum(s.parse().unwrap())
+    }
+
+    #[test]
+    fn test_bignums() {
+        assert_eq!(
+            run("(+ 9223372036854775807 1)"),
+            Ok(bignum("9223372036854775808"))
+        );
+        assert_eq!(
+            run("(* 4294967296 4294967296 4294967296)"),
+            Ok(bignum("79228162514264337593543950336"))
+        );
+        assert_eq!(
+            run("(- -9223372036854775808)"),
+            Ok(bignum("9223372036854775808"))
+        );
+        assert_eq!(
+            run("(/ -9223372036854775808 -1)"),
+            Ok(bignum("9223372036854775808"))
+        );
+        // back to an Integer once it fits
+        assert_eq!(
+            run("(- 100000000000000000000 99999999999999999999)"),
+            Ok(Sexpr::Integer(1))
+        );
+        assert_eq!(run("(mod -100000000000000000000 7)"), Ok(Sexpr::Integer(5)));
+        assert_eq!(run("(< 1 100000000000000000000 1e30)"), Ok(Sexpr::T));
+        assert_eq!(run("(= 1e20 100000000000000000000)"), Ok(Sexpr::T));
+        assert_eq!(
+            run("(abs -100000000000000000000)"),
+            Ok(bignum("100000000000000000000"))
+        );
+        assert_eq!(
+            sexpr_to_string(&run("(* 100000000000000000000 -1)").unwrap()),
+            "-100000000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        assert_eq!(
+            run("(handler-case (/ 1 0) (division-by-zero () 'caught))"),
+            Ok(Sexpr::Symbol(String::from("CAUGHT")))
+        );
+        assert_eq!(
+            run("(handler-case (mod 1.5 0.0) (arithmetic-error () 'caught))"),
+            Ok(Sexpr::Symbol(String::from("CAUGHT")))
+        );
+        assert!(matches!(
+            run("(/ 1 2 0)"),
+            Err(Unwind::Condition(Sexpr::Condition(t, _))) if t == "DIVISION-BY-ZERO"
+        ));
+    }
+
+    #[test]
+    fn test_float_overflow() {
+        for input in [
+            "(exp 1000)",
+            "(expt 2.0 2000)",
+            "(float (expt 10 400))",
+            "(* 1e308 10)",
+            "(+ 1.5 (expt 10 400))",
+            "(- -1e308 1e308)",
+            "(/ 1e308 1e-10)",
+        ] {
+            assert!(
+                matches!(
+                    run(input),
+                    Err(Unwind::Condition(Sexpr::Condition(t, _))) if t == "FLOATING-POINT-OVERFLOW"
+                ),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            run("(handler-case (exp 1000) (arithmetic-error () 'caught))"),
+            Ok(Sexpr::Symbol(String::from("CAUGHT")))
+        );
+        for input in ["(log 0)", "(log 0.0 10)", "(log 10 1)"] {
+            assert!(
+                matches!(
+                    run(input),
+                    Err(Unwind::Condition(Sexpr::Condition(t, _))) if t == "DIVISION-BY-ZERO"
+                ),
+                "{}",
+                input
+            );
+        }
+    }
+
+    fn float_near(input: &str, expected: f64) {
+        match run(input) {
+            Ok(Sexpr::Float(f)) => assert!((f - expected).abs() < 1e-12, "{} is {}", input, f),
+            r => panic!("{} is {:?}", input, r),
+        }
+    }
+
+    #[test]
+    fn test_math() {
+        assert_eq!(run("(expt 2 10)"), Ok(Sexpr::Integer(1024)));
+        assert_eq!(
+            run("(expt 2 100)"),
+            Ok(bignum("1267650600228229401496703205376"))
+        );
+        assert_eq!(run("(expt 2 -2)"), Ok(ratio(1, 4)));
+        assert_eq!(run("(expt 2/3 2)"), Ok(ratio(4, 9)));
+        assert_eq!(run("(expt -1 100000000001)"), Ok(Sexpr::Integer(-1)));
+        assert_eq!(run("(expt 2.0 3)"), Ok(Sexpr::Float(8.0)));
+        assert_eq!(run("(expt 4 1/2)"), Ok(Sexpr::Float(2.0)));
+        assert!(matches!(
+            run("(expt 0 -1)"),
+            Err(Unwind::Condition(Sexpr::Condition(t, _))) if t == "DIVISION-BY-ZERO"
+        ));
+
+        assert_eq!(run("(sqrt 16)"), Ok(Sexpr::Float(4.0)));
+        assert_eq!(run("(sqrt 1/4)"), Ok(Sexpr::Float(0.5)));
+        assert_eq!(
+            run("(sqrt -1)"),
+            Err(Unwind::from("sqrt - the result is not a real number"))
+        );
+        assert_eq!(run("(isqrt 17)"), Ok(Sexpr::Integer(4)));
+        assert_eq!(
+            run("(isqrt 100000000000000000000)"),
+            Ok(Sexpr::Integer(10000000000))
+        );
+        assert_eq!(
+            run("(isqrt 2.0)"),
+            Err(Unwind::from("isqrt - 2.0 is not an integer"))
+        );
+
+        float_near("(exp 1)", std::f64::consts::E);
+        float_near("(log 100 10)", 2.0);
+        float_near("(log (exp 2))", 2.0);
+        float_near("(sin (/ pi 2))", 1.0);
+        float_near("(cos pi)", -1.0);
+        float_near("(tan 0)", 0.0);
+        float_near("(asin 1)", std::f64::consts::FRAC_PI_2);
+        float_near("(acos 1)", 0.0);
+        float_near("(atan 1)", std::f64::consts::FRAC_PI_4);
+        float_near("(atan 1 -1)", 3.0 * std::f64::consts::FRAC_PI_4);
+
+        assert_eq!(run("(gcd 12 18 27)"), Ok(Sexpr::Integer(3)));
+        assert_eq!(run("(gcd -4 6)"), Ok(Sexpr::Integer(2)));
+        assert_eq!(run("(gcd)"), Ok(Sexpr::Integer(0)));
+        assert_eq!(run("(lcm 4 6)"), Ok(Sexpr::Integer(12)));
+        assert_eq!(run("(lcm)"), Ok(Sexpr::Integer(1)));
+
+        assert_eq!(run("(float 1/2)"), Ok(Sexpr::Float(0.5)));
+        assert_eq!(run("(float 3 1.0)"), Ok(Sexpr::Float(3.0)));
+        assert_eq!(run("(ceiling 2.5)"), Ok(Sexpr::Integer(3)));
+        assert_eq!(run("(round 3.5)"), Ok(Sexpr::Integer(4)));
+        assert_eq!(run("(truncate -2.5)"), Ok(Sexpr::Integer(-2)));
+    }
+
+    #[test]
+    fn test_min_max_abs() {
+        assert_eq!(run("(min 3 1 2)"), Ok(Sexpr::Integer(1)));
+        assert_eq!(run("(max 3 1 4.5)"), Ok(Sexpr::Float(4.5)));
+        assert_eq!(run("(max 7)"), Ok(Sexpr::Integer(7)));
+        assert_eq!(run("(abs -3)"), Ok(Sexpr::Integer(3)));
+        assert_eq!(run("(abs -2.5)"), Ok(Sexpr::Float(2.5)));
+        assert_eq!(run("(1+ 1)"), Ok(Sexpr::Integer(2)));
+        assert_eq!(run("(1- 1.5)"), Ok(Sexpr::Float(0.5)));
+        assert_eq!(run("(1+ 'a)"), Err(Unwind::from("1+ - A is not a number")));
+    }
+
+    #[test]
+    fn test_number_predicates() {
+        assert_eq!(run("(zerop 0)"), Ok(Sexpr::T));
+        assert_eq!(run("(zerop -0.0)"), Ok(Sexpr::T));
+        assert_eq!(run("(zerop 1/2)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(plusp 1/2)"), Ok(Sexpr::T));
+        assert_eq!(run("(plusp 0)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(minusp -1.5)"), Ok(Sexpr::T));
+        assert_eq!(run("(minusp 100000000000000000000)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(evenp 10)"), Ok(Sexpr::T));
+        assert_eq!(run("(evenp -3)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(oddp 100000000000000000001)"), Ok(Sexpr::T));
+        assert_eq!(
+            run("(evenp 2.0)"),
+            Err(Unwind::from("evenp - 2.0 is not an integer"))
+        );
+        assert_eq!(
+            run("(zerop 'a)"),
+            Err(Unwind::from("zerop - A is not a number"))
+        );
+    }
+}
@@ -1,49 +1,139 @@
 use crate::lexer::*;
 
+use num_bigint::BigInt;
+use num_rational::BigRational;
+
 // clone makes a deep copy of Sexpr
 #[derive(Debug, Clone, PartialEq)]
 pub enum Sexpr {
     Integer(i64),
+    // an integer outside of i64, arithmetic makes these when it overflows
+    // and turns them back into Integers once they fit again
+    Bignum(BigInt),
+    // an exact fraction in lowest terms, never with a denominator of 1, boxed
+    // to keep Sexpr small
+    Ratio(Box<BigRational>),
     Float(f64),
+    Char(char),
     String(String),
     Symbol(String),
     List(Vec<Sexpr>),
     T,
     Nil,
+    // name and (params body...) of a DEFUN or LAMBDA, called through func
+    Lambda(String, Vec<Sexpr>),
+    // a function implemented in lisp.rs, named by its symbol
+    Builtin(String),
+    // a condition type and its data, a message string or a list of initargs
+    Condition(String, Box<Sexpr>),
+    // an open stream, by its id in STREAMS
+    Stream(usize),
+}
+
+// the items of a list without the NIL that terminates proper lists
+pub fn list_items(l: &[Sexpr]) -> &[Sexpr] {
+    match l.last() {
+        Some(Sexpr::Nil) => &l[..l.len() - 1],
+        _ => l,
+    }
+}
+
+// a proper list of items, NIL when there are none
+pub fn make_list(mut items: Vec<Sexpr>) -> Sexpr {
+    if items.is_empty() {
+        return Sexpr::Nil;
+    }
+    items.push(Sexpr::Nil);
+    Sexpr::List(items)
+}
+
+pub fn bool_sexpr(b: bool) -> Sexpr {
+    if b {
+        Sexpr::T
+    } else {
+        Sexpr::Nil
+    }
+}
+
+// #\\ and the char, or its name if it has one or can't be seen
+pub fn char_to_string(c: char) -> String {
+    match c {
+        ' ' => String::from("#\\Space"),
+        '\n' => String::from("#\\Newline"),
+        '\t' => String::from("#\\Tab"),
+        '\r' => String::from("#\\Return"),
+        '\x0c' => String::from("#\\Page"),
+        '\x08' => String::from("#\\Backspace"),
+        '\x7f' => String::from("#\\Rubout"),
+        '\x1b' => String::from("#\\Escape"),
+        '\0' => String::from("#\\Null"),
+        c if c.is_control() || c.is_whitespace() => format!("#\\U+{:04X}", c as u32),
+        c => format!("#\\{}", c),
+    }
+}
+
+// in quotes with \ before any " or \ inside, the way the reader takes it back
+pub fn string_to_string(s: &str) -> String {
+    let mut str: String = String::from("\"");
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            str.push('\\');
+        }
+        str.push(c);
+    }
+    str.push('"');
+    str
 }
+
 pub fn sexpr_to_string(v: &Sexpr) -> String {
+    sexpr_to_string_radix(v, 10)
+}
+
+// integers and ratios are written in base, which is what *print-base* sets,
+// with the digits past 9 in upper case like the reader expects
+pub fn sexpr_to_string_radix(v: &Sexpr, base: u32) -> String {
+    let radix = |i: &BigInt| i.to_str_radix(base).to_uppercase();
     match v {
-        Sexpr::Integer(i) => i.to_string(),
-        Sexpr::Float(f) => f.to_string(),
-        Sexpr::String(s) => String::from(s),
+        Sexpr::Integer(i) if base == 10 => i.to_string(),
+        Sexpr::Integer(i) => radix(&BigInt::from(*i)),
+        Sexpr::Bignum(b) => radix(b),
+        Sexpr::Ratio(r) => format!("{}/{}", radix(r.numer()), radix(r.denom())),
+        // always with a point or an exponent, so that it reads back as a float
+        Sexpr::Float(f) => format!("{:?}", f),
+        Sexpr::Char(c) => char_to_string(*c),
+        Sexpr::String(s) => string_to_string(s),
         Sexpr::Symbol(s) => String::from(s),
         Sexpr::T => String::from("T"),
         Sexpr::Nil => String::from("NIL"),
+        Sexpr::Lambda(name, _) => format!("#<FUNCTION {}>", name),
+        Sexpr::Builtin(name) => format!("#<FUNCTION {}>", name),
+        Sexpr::Stream(id) => format!("#<STREAM {}>", id),
+        Sexpr::Condition(t, data) => format!("#<{} {}>", t, sexpr_to_string_radix(data, base)),
         Sexpr::List(l) => {
             let mut str: String = String::from("(");
 
             if l.len() < 2 {
                 for sexpr in &l[0..l.len() - 1] {
-                    str += sexpr_to_string(sexpr).as_str();
+                    str += sexpr_to_string_radix(sexpr, base).as_str();
                     str += " ";
                 }
                 let last = &l[l.len() - 1];
                 if last != &Sexpr::Nil {
                     str += " . ";
-                    str += sexpr_to_string(last).as_str();
+                    str += sexpr_to_string_radix(last, base).as_str();
                 }
                 str += ")";
             } else {
                 // you can't have a list with only non nil
                 for sexpr in &l[0..l.len() - 2] {
-                    str += sexpr_to_string(sexpr).as_str();
+                    str += sexpr_to_string_radix(sexpr, base).as_str();
                     str += " ";
                 }
-                str += sexpr_to_string(&l[l.len() - 2]).as_str();
+                str += sexpr_to_string_radix(&l[l.len() - 2], base).as_str();
                 let last = &l[l.len() - 1];
                 if last != &Sexpr::Nil {
                     str += " . ";
-                    str += sexpr_to_string(last).as_str();
+                    str += sexpr_to_string_radix(last, base).as_str();
                 }
                 str += ")";
             }
@@ -55,7 +145,10 @@ pub fn sexpr_to_string(v: &Sexpr) -> String {
 fn parse_atom(token: &Token) -> Sexpr {
     match token {
         Token::Integer(i) => Sexpr::Integer(*i),
+        Token::Bignum(b) => Sexpr::Bignum(b.clone()),
+        Token::Ratio(r) => Sexpr::Ratio(Box::new(r.clone())),
         Token::Float(f) => Sexpr::Float(*f),
+        Token::Char(c) => Sexpr::Char(*c),
         Token::String(s) => Sexpr::String(s.clone()),
         Token::Symbol(s) => match s.as_str() {
             "T" => Sexpr::T,
@@ -65,11 +158,8 @@ fn parse_atom(token: &Token) -> Sexpr {
         _ => Sexpr::Nil, // should never be reached
     }
 }
-fn is_list(tokens: &Vec<Token>) -> bool {
-    match tokens.first().unwrap() {
-        Token::LParen => true,
-        _ => false,
-    }
+fn is_list(tokens: &[Token]) -> bool {
+    matches!(tokens.first().unwrap(), Token::LParen)
 }
 fn parse_list(tokens: &mut Vec<Token>) -> Sexpr {
     let _ = tokens.pop(); // remove first LParen
@@ -142,6 +232,12 @@ mod tests {
         ]);
         expected = String::from("((1) . 1)");
         assert_eq!(sexpr_to_string(&input), expected);
+
+        // floats keep their point so they read back as floats
+        assert_eq!(sexpr_to_string(&Sexpr::Float(2.0)), "2.0");
+        assert_eq!(sexpr_to_string(&Sexpr::Float(1e30)), "1e30");
+        assert_eq!(sexpr_to_string_radix(&Sexpr::Integer(-255), 16), "-FF");
+        assert_eq!(sexpr_to_string_radix(&Sexpr::Integer(35), 36), "Z");
     }
 
     #[test]
@@ -183,7 +279,7 @@ mod tests {
 
     fn unpack_list(list: &Sexpr) -> Result<&Vec<Sexpr>, &str> {
         match list {
-            Sexpr::List(vec) => Ok(&vec),
+            Sexpr::List(vec) => Ok(vec),
             _ => Err("unpack_list - arg was not a list"),
         }
     }
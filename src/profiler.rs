@@ -0,0 +1,283 @@
+use crate::eval::*;
+use crate::parse::*;
+use crate::sandbox::*;
+use crate::streams::*;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+// the system allocator, counting the allocations made on each thread so
+// that the profiler can tell how much a function allocates
+pub struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+    static PROFILE: RefCell<Option<Profile>> = const { RefCell::new(None) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|a| a.set(a.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|a| a.set(a.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+pub fn allocations() -> u64 {
+    ALLOCATIONS.with(|a| a.get())
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Stats {
+    calls: u64,
+    inclusive: Duration,
+    exclusive: Duration,
+    allocations: u64,
+}
+
+// a call that has not returned yet
+struct Call {
+    name: String,
+    start: Instant,
+    allocations: u64,
+    // time and allocations of the calls made from this one
+    child_time: Duration,
+    child_allocations: u64,
+}
+
+#[derive(Default)]
+struct Profile {
+    stats: HashMap<String, Stats>,
+    stack: Vec<Call>,
+    // exclusive microseconds of every call stack, A;B;C
+    folded: HashMap<String, u128>,
+}
+
+// called by apply_builtin and func on the way in, true if it was recorded
+pub fn profile_enter(name: &str) -> bool {
+    PROFILE.with(|p| match p.borrow_mut().as_mut() {
+        Some(profile) => {
+            profile.stack.push(Call {
+                name: String::from(name),
+                start: Instant::now(),
+                allocations: allocations(),
+                child_time: Duration::ZERO,
+                child_allocations: 0,
+            });
+            true
+        }
+        None => false,
+    })
+}
+
+// and on the way out, only if profile_enter returned true
+pub fn profile_exit() {
+    let now: Instant = Instant::now();
+    let allocated: u64 = allocations();
+    PROFILE.with(|p| {
+        let mut p = p.borrow_mut();
+        let profile: &mut Profile = match p.as_mut() {
+            Some(profile) => profile,
+            None => return,
+        };
+        let call: Call = match profile.stack.pop() {
+            Some(call) => call,
+            None => return,
+        };
+
+        let inclusive: Duration = now - call.start;
+        let exclusive: Duration = inclusive.saturating_sub(call.child_time);
+        let allocations: u64 = allocated - call.allocations;
+
+        let mut path: Vec<&str> = profile.stack.iter().map(|c| c.name.as_str()).collect();
+        // recursive calls are already counted by the outermost one
+        let recursive: bool = path.contains(&call.name.as_str());
+        path.push(&call.name);
+        *profile.folded.entry(path.join(";")).or_insert(0) += exclusive.as_micros();
+
+        let stats: &mut Stats = profile.stats.entry(call.name.clone()).or_default();
+        stats.calls += 1;
+        stats.exclusive += exclusive;
+        stats.allocations += allocations.saturating_sub(call.child_allocations);
+        if !recursive {
+            stats.inclusive += inclusive;
+        }
+
+        if let Some(parent) = profile.stack.last_mut() {
+            parent.child_time += inclusive;
+            parent.child_allocations += allocations;
+        }
+    });
+}
+
+fn sorted_stats(stats: &HashMap<String, Stats>) -> Vec<(String, Stats)> {
+    let mut sorted: Vec<(String, Stats)> = stats
+        .iter()
+        .map(|(name, s)| (name.clone(), s.clone()))
+        .collect();
+    sorted.sort_by(|(a_name, a), (b_name, b)| {
+        b.exclusive
+            .cmp(&a.exclusive)
+            .then(b.calls.cmp(&a.calls))
+            .then(a_name.cmp(b_name))
+    });
+    sorted
+}
+
+fn millis(d: Duration) -> String {
+    format!("{:.3}", d.as_secs_f64() * 1000.0)
+}
+
+fn profile_table(stats: &[(String, Stats)]) -> String {
+    let mut table: String = format!(
+        "{:<20} {:>10} {:>12} {:>12} {:>10}\n",
+        "function", "calls", "incl ms", "excl ms", "allocs"
+    );
+    for (name, s) in stats {
+        table += &format!(
+            "{:<20} {:>10} {:>12} {:>12} {:>10}\n",
+            name,
+            s.calls,
+            millis(s.inclusive),
+            millis(s.exclusive),
+            s.allocations
+        );
+    }
+    table
+}
+
+fn write_folded(path: &str, folded: &HashMap<String, u128>) -> Result<(), Unwind> {
+    let mut lines: Vec<String> = folded
+        .iter()
+        .map(|(stack, micros)| format!("{} {}", stack, micros))
+        .collect();
+    lines.sort();
+
+    let mut file: File = File::create(path)
+        .map_err(|e| Unwind::from(format!("profile - can't create {}: {}", path, e)))?;
+    for line in lines {
+        writeln!(file, "{}", line)
+            .map_err(|e| Unwind::from(format!("profile - can't write {}: {}", path, e)))?;
+    }
+    Ok(())
+}
+
+// (profile form) or (profile form :folded "file") evaluates form and prints
+// where the time went, the folded stacks are what flame graph tools read
+pub fn profile(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("profile - args must be a list")),
+    };
+    let form: Sexpr = match args.first() {
+        Some(form) => form.clone(),
+        None => return Err(Unwind::from("profile - no form arg")),
+    };
+    let folded: Option<String> = match &args[1..] {
+        [] => None,
+        [Sexpr::Symbol(k), file] if k == ":FOLDED" => match evaluate(file.clone(), alist)? {
+            Sexpr::String(s) => Some(s),
+            _ => return Err(Unwind::from("profile - :folded must be a file name")),
+        },
+        _ => return Err(Unwind::from("profile - the only option is :folded")),
+    };
+
+    // a profile inside a profile is part of the outer one
+    if PROFILE.with(|p| p.borrow().is_some()) {
+        return evaluate(form, alist);
+    }
+
+    PROFILE.with(|p| p.replace(Some(Profile::default())));
+    let result = evaluate(form, alist);
+    let profile: Profile = PROFILE.with(|p| p.take()).unwrap_or_default();
+
+    write_standard_output(
+        "profile",
+        &profile_table(&sorted_stats(&profile.stats)),
+        alist,
+    )?;
+    if let Some(path) = folded {
+        if !group_allowed(Group::Filesystem) {
+            return Err(Unwind::from("profile - writing files is not allowed here"));
+        }
+        write_folded(&path, &profile.folded)?;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+
+    fn stats(calls: u64, exclusive: u64) -> Stats {
+        Stats {
+            calls,
+            inclusive: Duration::from_millis(exclusive),
+            exclusive: Duration::from_millis(exclusive),
+            allocations: 0,
+        }
+    }
+
+    #[test]
+    fn test_allocations() {
+        let before = allocations();
+        let v: Vec<u8> = Vec::with_capacity(16);
+        assert!(allocations() > before);
+        drop(v);
+    }
+
+    #[test]
+    fn test_sorted_stats() {
+        let mut s: HashMap<String, Stats> = HashMap::new();
+        s.insert(String::from("A"), stats(1, 1));
+        s.insert(String::from("B"), stats(5, 3));
+        s.insert(String::from("C"), stats(2, 1));
+        let names: Vec<String> = sorted_stats(&s).into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, vec!["B", "C", "A"]);
+
+        let table = profile_table(&sorted_stats(&s));
+        assert_eq!(table.lines().count(), 4);
+        assert!(table.lines().nth(1).unwrap().starts_with("B "));
+    }
+
+    #[test]
+    fn test_profile() {
+        run("(defun profile-test (n) (cond ((equal n 0) 0) (t (+ n (profile-test (- n 1))))))")
+            .unwrap();
+
+        PROFILE.with(|p| p.replace(Some(Profile::default())));
+        assert_eq!(run("(profile-test 3)"), Ok(Sexpr::Integer(6)));
+        let profile: Profile = PROFILE.with(|p| p.take()).unwrap();
+
+        assert_eq!(profile.stats["PROFILE-TEST"].calls, 4);
+        assert_eq!(profile.stats["+"].calls, 3);
+        assert_eq!(profile.stats["EQUAL"].calls, 4);
+        assert!(profile.stack.is_empty());
+        assert!(profile
+            .folded
+            .contains_key("PROFILE-TEST;+;PROFILE-TEST;EQUAL"));
+
+        // the form's value comes back and nothing is recorded afterwards
+        assert_eq!(run("(profile (profile-test 2))"), Ok(Sexpr::Integer(3)));
+        assert!(!profile_enter("PROFILE-TEST"));
+
+        // and the table goes wherever *standard-output* does
+        match run("(with-output-to-string (*standard-output*) (profile (profile-test 2)))") {
+            Ok(Sexpr::String(table)) => assert!(table.contains("PROFILE-TEST")),
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+}
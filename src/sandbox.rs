@@ -0,0 +1,123 @@
+use std::cell::RefCell;
+
+// what a builtin can reach outside of the interpreter, a sandbox allows a
+// set of these and every builtin outside of them is unbound
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Group {
+    Pure,
+    Io,
+    Filesystem,
+    Process,
+    Time,
+    Random,
+}
+
+thread_local! {
+    // None when nothing is sandboxed
+    static SANDBOX: RefCell<Option<Vec<Group>>> = const { RefCell::new(None) };
+}
+
+pub fn builtin_group(name: &str) -> Group {
+    match name {
+        "PRINT" | "PRIN1" | "PRINC" | "TERPRI" | "READ" | "READ-LINE" | "READ-CHAR"
+        | "PEEK-CHAR" | "STEP" | "TRACE" | "UNTRACE" | "PROFILE" => Group::Io,
+        "OPEN" | "CLOSE" | "WITH-OPEN-FILE" | "PROBE-FILE" | "DELETE-FILE" | "RENAME-FILE"
+        | "DIRECTORY" | "FILE-LENGTH" => Group::Filesystem,
+        _ => Group::Pure,
+    }
+}
+
+pub fn group_allowed(group: Group) -> bool {
+    SANDBOX.with(|s| match s.borrow().as_ref() {
+        Some(groups) => group == Group::Pure || groups.contains(&group),
+        None => true,
+    })
+}
+
+// whether the builtin or special form called name can be used right now
+pub fn allowed(name: &str) -> bool {
+    group_allowed(builtin_group(name))
+}
+
+// runs f with only the builtins of groups available, pure ones always are
+pub fn with_sandbox<T>(groups: &[Group], f: impl FnOnce() -> T) -> T {
+    let outer: Option<Vec<Group>> = SANDBOX.with(|s| s.replace(Some(groups.to_vec())));
+    let result: T = f();
+    SANDBOX.with(|s| s.replace(outer));
+    result
+}
+
+// a comma separated list of groups like "pure,io" for --sandbox
+pub fn parse_groups(groups: &str) -> Result<Vec<Group>, String> {
+    groups
+        .split(',')
+        .filter(|g| !g.trim().is_empty())
+        .map(|g| match g.trim().to_ascii_lowercase().as_str() {
+            "pure" => Ok(Group::Pure),
+            "io" => Ok(Group::Io),
+            "filesystem" => Ok(Group::Filesystem),
+            "process" => Ok(Group::Process),
+            "time" => Ok(Group::Time),
+            "random" => Ok(Group::Random),
+            g => Err(format!("unknown builtin group {}", g)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::*;
+    use crate::parse::*;
+    use crate::test::*;
+
+    #[test]
+    fn test_parse_groups() {
+        assert_eq!(parse_groups("pure, IO"), Ok(vec![Group::Pure, Group::Io]));
+        assert_eq!(parse_groups(""), Ok(vec![]));
+        assert_eq!(
+            parse_groups("pure,network"),
+            Err(String::from("unknown builtin group network"))
+        );
+    }
+
+    #[test]
+    fn test_sandbox() {
+        assert!(allowed("PRINT"));
+        with_sandbox(&[Group::Io], || {
+            assert!(allowed("CAR"));
+            assert!(allowed("PRINT"));
+            assert!(allowed("STEP"));
+
+            // sandboxes nest
+            with_sandbox(&[], || assert!(!allowed("PRINT")));
+            assert!(allowed("PRINT"));
+        });
+
+        with_sandbox(&[], || {
+            assert!(allowed("CAR"));
+            assert!(!allowed("PRINT"));
+
+            // left out builtins are unbound, as values too
+            assert_eq!(
+                run("(print 1)"),
+                Err(Unwind::from("PRINT function not found"))
+            );
+            assert_eq!(
+                run("#'print"),
+                Err(Unwind::from("PRINT function not found"))
+            );
+            assert_eq!(run("(car '(1))"), Ok(Sexpr::Integer(1)));
+        });
+        assert!(allowed("PRINT"));
+
+        // a builtin value from outside can't be called inside either
+        run("(setq sandbox-test-print #'print)").unwrap();
+        with_sandbox(&[], || {
+            assert_eq!(
+                run("(funcall sandbox-test-print 1)"),
+                Err(Unwind::from("PRINT function not found"))
+            );
+        });
+    }
+}
@@ -0,0 +1,433 @@
+use crate::eval::*;
+use crate::lists::*;
+use crate::parse::*;
+use crate::strings::*;
+
+// functions are anything FUNCALL takes, so #'car, 'car and lambdas alike
+
+fn call(f: &Sexpr, args: Vec<Sexpr>, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    call_function(f.clone(), args, alist)
+}
+
+// items back into the kind of sequence they came from
+fn same_sequence(name: &str, seq: &Sexpr, items: Vec<Sexpr>) -> Result<Sexpr, Unwind> {
+    match seq {
+        Sexpr::String(_) => Ok(Sexpr::String(chars_to_string(name, &items)?)),
+        _ => Ok(make_list(items)),
+    }
+}
+
+// (name f list...) as the function and the items of each list
+fn function_and_lists(
+    name: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<(Sexpr, Vec<Vec<Sexpr>>), Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    match args.as_slice() {
+        [f, lists @ ..] if !lists.is_empty() => Ok((
+            f.clone(),
+            lists
+                .iter()
+                .map(|l| proper_items(name, l))
+                .collect::<Result<_, _>>()?,
+        )),
+        _ => Err(Unwind::from(format!(
+            "{} - takes a function and at least one list",
+            name
+        ))),
+    }
+}
+
+// the args of each call, the nth items of every list, up to the shortest
+fn across(lists: &[Vec<Sexpr>]) -> Vec<Vec<Sexpr>> {
+    let n: usize = lists.iter().map(|l| l.len()).min().unwrap_or(0);
+    (0..n)
+        .map(|i| lists.iter().map(|l| l[i].clone()).collect())
+        .collect()
+}
+
+pub fn mapcar(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (f, lists) = function_and_lists("mapcar", args, alist)?;
+    let mut results: Vec<Sexpr> = Vec::new();
+    for args in across(&lists) {
+        results.push(call(&f, args, alist)?);
+    }
+    Ok(make_list(results))
+}
+
+// mapcar for the side effects, returning the first list
+pub fn mapc(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (f, lists) = function_and_lists("mapc", args, alist)?;
+    for args in across(&lists) {
+        call(&f, args, alist)?;
+    }
+    Ok(make_list(lists[0].clone()))
+}
+
+// f gets the lists and then each of their successive cdrs
+pub fn maplist(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (f, lists) = function_and_lists("maplist", args, alist)?;
+    let n: usize = lists.iter().map(|l| l.len()).min().unwrap_or(0);
+    let mut results: Vec<Sexpr> = Vec::new();
+    for i in 0..n {
+        let tails: Vec<Sexpr> = lists.iter().map(|l| make_list(l[i..].to_vec())).collect();
+        results.push(call(&f, tails, alist)?);
+    }
+    Ok(make_list(results))
+}
+
+// the lists f returns joined like APPEND joins them
+pub fn mapcan(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (f, lists) = function_and_lists("mapcan", args, alist)?;
+    let mut results: Vec<Sexpr> = Vec::new();
+    for args in across(&lists) {
+        results.push(call(&f, args, alist)?);
+    }
+    let tail: Sexpr = results.pop().unwrap_or(Sexpr::Nil);
+    let mut items: Vec<Sexpr> = Vec::new();
+    for l in &results {
+        items.extend(proper_items("mapcan", l)?);
+    }
+    Ok(build_list(items, tail))
+}
+
+// (reduce f seq &key initial-value from-end) folds from the left, or from
+// the right with f taking the item first; an empty seq without an initial
+// value is what f returns with no args
+pub fn reduce(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let (f, mut items) = match args.as_slice() {
+        [f, seq, ..] => (f.clone(), sequence_items("reduce", seq)?),
+        _ => return Err(Unwind::from("reduce - takes a function and a sequence")),
+    };
+    let keys: &[Sexpr] = &args[2..];
+    if !keys.len().is_multiple_of(2) {
+        return Err(Unwind::from("reduce - odd number of keyword args"));
+    }
+    let mut initial: Option<Sexpr> = None;
+    let mut from_end: bool = false;
+    for pair in keys.chunks(2) {
+        match &pair[0] {
+            Sexpr::Symbol(k) if k == ":INITIAL-VALUE" => initial = Some(pair[1].clone()),
+            Sexpr::Symbol(k) if k == ":FROM-END" => from_end = pair[1] != Sexpr::Nil,
+            k => {
+                return Err(Unwind::from(format!(
+                    "reduce - bad option {} {}",
+                    sexpr_to_string(k),
+                    sexpr_to_string(&pair[1])
+                )))
+            }
+        }
+    }
+
+    if from_end {
+        items.reverse();
+    }
+    let mut items = items.into_iter();
+    let mut acc: Sexpr = match initial.or_else(|| items.next()) {
+        Some(acc) => acc,
+        None => return call(&f, Vec::new(), alist),
+    };
+    for item in items {
+        let args: Vec<Sexpr> = if from_end {
+            vec![item, acc]
+        } else {
+            vec![acc, item]
+        };
+        acc = call(&f, args, alist)?;
+    }
+    Ok(acc)
+}
+
+// (name pred seq) as the predicate and the items of seq
+fn predicate_and_sequence(
+    name: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<(Sexpr, Sexpr, Vec<Sexpr>), Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [pred, seq] => Ok((pred.clone(), seq.clone(), sequence_items(name, seq)?)),
+        _ => Err(Unwind::from(format!(
+            "{} - takes a predicate and a sequence",
+            name
+        ))),
+    }
+}
+
+// the items of seq pred holds for, or doesn't when keep is false
+fn filter(
+    name: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+    keep: bool,
+) -> Result<Sexpr, Unwind> {
+    let (pred, seq, items) = predicate_and_sequence(name, args, alist)?;
+    let mut kept: Vec<Sexpr> = Vec::new();
+    for item in items {
+        if (call(&pred, vec![item.clone()], alist)? != Sexpr::Nil) == keep {
+            kept.push(item);
+        }
+    }
+    same_sequence(name, &seq, kept)
+}
+
+pub fn remove_if(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    filter("remove-if", args, alist, false)
+}
+
+pub fn remove_if_not(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    filter("remove-if-not", args, alist, true)
+}
+
+pub fn find_if(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (pred, _, items) = predicate_and_sequence("find-if", args, alist)?;
+    for item in items {
+        if call(&pred, vec![item.clone()], alist)? != Sexpr::Nil {
+            return Ok(item);
+        }
+    }
+    Ok(Sexpr::Nil)
+}
+
+pub fn count_if(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (pred, _, items) = predicate_and_sequence("count-if", args, alist)?;
+    let mut count: i64 = 0;
+    for item in items {
+        if call(&pred, vec![item], alist)? != Sexpr::Nil {
+            count += 1;
+        }
+    }
+    Ok(Sexpr::Integer(count))
+}
+
+// (position item seq) is the index of the first item equal to item, or NIL
+pub fn position(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [item, seq] => Ok(
+            match sequence_items("position", seq)?
+                .iter()
+                .position(|i| i == item)
+            {
+                Some(i) => Sexpr::Integer(i as i64),
+                None => Sexpr::Nil,
+            },
+        ),
+        _ => Err(Unwind::from("position - takes an item and a sequence")),
+    }
+}
+
+// every stops at the first NIL, some at the first true value and returns it
+pub fn every(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (pred, seqs) = predicate_and_sequences("every", args, alist)?;
+    for args in across(&seqs) {
+        if call(&pred, args, alist)? == Sexpr::Nil {
+            return Ok(Sexpr::Nil);
+        }
+    }
+    Ok(Sexpr::T)
+}
+
+pub fn some(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (pred, seqs) = predicate_and_sequences("some", args, alist)?;
+    for args in across(&seqs) {
+        let result: Sexpr = call(&pred, args, alist)?;
+        if result != Sexpr::Nil {
+            return Ok(result);
+        }
+    }
+    Ok(Sexpr::Nil)
+}
+
+fn predicate_and_sequences(
+    name: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<(Sexpr, Vec<Vec<Sexpr>>), Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    match args.as_slice() {
+        [pred, seqs @ ..] if !seqs.is_empty() => Ok((
+            pred.clone(),
+            seqs.iter()
+                .map(|s| sequence_items(name, s))
+                .collect::<Result<_, _>>()?,
+        )),
+        _ => Err(Unwind::from(format!(
+            "{} - takes a predicate and at least one sequence",
+            name
+        ))),
+    }
+}
+
+// a merge sort, so that items pred doesn't order keep their order and pred
+// is called once per comparison however inconsistent it is
+fn merge_sort(
+    items: Vec<Sexpr>,
+    pred: &Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Vec<Sexpr>, Unwind> {
+    if items.len() < 2 {
+        return Ok(items);
+    }
+    let mut left: Vec<Sexpr> = items;
+    let right: Vec<Sexpr> = left.split_off(left.len() / 2);
+    let left: Vec<Sexpr> = merge_sort(left, pred, alist)?;
+    let right: Vec<Sexpr> = merge_sort(right, pred, alist)?;
+
+    let mut merged: Vec<Sexpr> = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        // only a right item strictly before the left one goes first
+        if call(pred, vec![r.clone(), l.clone()], alist)? != Sexpr::Nil {
+            merged.push(right.next().unwrap());
+        } else {
+            merged.push(left.next().unwrap());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+// (sort seq pred) is a sorted copy of seq, pred being true when its first
+// arg goes before its second; equal items stay in order
+pub fn sort(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (seq, pred) = match eval_args(args, alist)?.as_slice() {
+        [seq, pred] => (seq.clone(), pred.clone()),
+        _ => return Err(Unwind::from("sort - takes a sequence and a predicate")),
+    };
+    let items: Vec<Sexpr> = merge_sort(sequence_items("sort", &seq)?, &pred, alist)?;
+    same_sequence("sort", &seq, items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+
+    fn ints(items: &[i64]) -> Sexpr {
+        make_list(items.iter().map(|i| Sexpr::Integer(*i)).collect())
+    }
+
+    #[test]
+    fn test_mapping() {
+        assert_eq!(run("(mapcar #'1+ '(1 2 3))"), Ok(ints(&[2, 3, 4])));
+        assert_eq!(run("(mapcar #'+ '(1 2 3) '(10 20))"), Ok(ints(&[11, 22])));
+        assert_eq!(
+            run("(mapcar (lambda (x) (* x x)) '(1 2 3))"),
+            Ok(ints(&[1, 4, 9]))
+        );
+        assert_eq!(run("(mapcar 'car '((1) (2)))"), Ok(ints(&[1, 2])));
+        assert_eq!(run("(mapcar #'1+ nil)"), Ok(Sexpr::Nil));
+        assert_eq!(
+            run("(with-output-to-string (s) (mapc (lambda (x) (princ x s)) '(1 2)))"),
+            Ok(Sexpr::String(String::from("12")))
+        );
+        assert_eq!(run("(mapc #'1+ '(1 2))"), Ok(ints(&[1, 2])));
+        assert_eq!(run("(maplist #'length '(1 2 3))"), Ok(ints(&[3, 2, 1])));
+        assert_eq!(
+            run("(mapcan (lambda (x) (list x x)) '(1 2))"),
+            Ok(ints(&[1, 1, 2, 2]))
+        );
+        assert_eq!(
+            run("(mapcan (lambda (x) (cond ((> x 1) (list x)))) '(1 2 3))"),
+            Ok(ints(&[2, 3]))
+        );
+        assert_eq!(
+            run("(mapcar #'1+ '(1 . 2))"),
+            Err(Unwind::from("mapcar - (1 . 2) is not a proper list"))
+        );
+    }
+
+    #[test]
+    fn test_reduce() {
+        assert_eq!(run("(reduce #'+ '(1 2 3 4))"), Ok(Sexpr::Integer(10)));
+        assert_eq!(run("(reduce #'+ nil)"), Ok(Sexpr::Integer(0)));
+        assert_eq!(run("(reduce #'+ '(5))"), Ok(Sexpr::Integer(5)));
+        assert_eq!(
+            run("(reduce #'- '(1 2 3) :initial-value 10)"),
+            Ok(Sexpr::Integer(4))
+        );
+        assert_eq!(run("(reduce #'list '(1 2 3))"), run("'((1 2) 3)"));
+        assert_eq!(
+            run("(reduce #'list '(1 2 3) :from-end t)"),
+            run("'(1 (2 3))")
+        );
+        assert_eq!(
+            run("(reduce #'cons '(1 2) :from-end t :initial-value nil)"),
+            Ok(ints(&[1, 2]))
+        );
+        assert_eq!(
+            run("(reduce #'+ '(1) :start 0)"),
+            Err(Unwind::from("reduce - bad option :START 0"))
+        );
+    }
+
+    #[test]
+    fn test_filtering() {
+        assert_eq!(run("(remove-if #'atom '())"), Ok(Sexpr::Nil));
+        assert_eq!(
+            run("(remove-if (lambda (x) (> x 2)) '(1 3 2 4))"),
+            Ok(ints(&[1, 2]))
+        );
+        assert_eq!(
+            run("(remove-if-not (lambda (x) (> x 2)) '(1 3 2 4))"),
+            Ok(ints(&[3, 4]))
+        );
+        assert_eq!(
+            run("(remove-if #'alpha-char-p \"a1b2\")"),
+            Ok(Sexpr::String(String::from("12")))
+        );
+        assert_eq!(
+            run("(find-if (lambda (x) (> x 2)) '(1 3 4))"),
+            Ok(Sexpr::Integer(3))
+        );
+        assert_eq!(run("(find-if #'null '(1 2))"), Ok(Sexpr::Nil));
+        assert_eq!(
+            run("(count-if #'digit-char-p \"a1b22\")"),
+            Ok(Sexpr::Integer(3))
+        );
+        assert_eq!(run("(position 3 '(1 2 3))"), Ok(Sexpr::Integer(2)));
+        assert_eq!(run("(position #\\b \"abc\")"), Ok(Sexpr::Integer(1)));
+        assert_eq!(run("(position 9 '(1 2 3))"), Ok(Sexpr::Nil));
+    }
+
+    #[test]
+    fn test_every_and_some() {
+        assert_eq!(run("(every #'atom '(1 2))"), Ok(Sexpr::T));
+        assert_eq!(run("(every #'atom '(1 (2)))"), Ok(Sexpr::Nil));
+        assert_eq!(run("(every #'atom nil)"), Ok(Sexpr::T));
+        assert_eq!(run("(every #'< '(1 2) '(2 3 0))"), Ok(Sexpr::T));
+        assert_eq!(
+            run("(some (lambda (x) (cond ((> x 1) (* x 10)))) '(1 2 3))"),
+            Ok(Sexpr::Integer(20))
+        );
+        assert_eq!(run("(some #'listp '(1 2))"), Ok(Sexpr::Nil));
+    }
+
+    #[test]
+    fn test_sort() {
+        assert_eq!(run("(sort '(3 1 2) #'<)"), Ok(ints(&[1, 2, 3])));
+        assert_eq!(run("(sort '(3 1 2) '>)"), Ok(ints(&[3, 2, 1])));
+        assert_eq!(run("(sort nil #'<)"), Ok(Sexpr::Nil));
+        assert_eq!(
+            run("(sort \"cab\" #'char<)"),
+            Ok(Sexpr::String(String::from("abc")))
+        );
+        // pairs with equal cars keep their order
+        assert_eq!(
+            run("(sort '((2 . a) (1 . b) (2 . c) (1 . d)) (lambda (x y) (< (car x) (car y))))"),
+            run("'((1 . b) (1 . d) (2 . a) (2 . c))")
+        );
+        // an inconsistent predicate still gives back every item
+        assert_eq!(
+            run("(length (sort '(1 2 3 4 5) (lambda (x y) t)))"),
+            Ok(Sexpr::Integer(5))
+        );
+        assert_eq!(
+            run("(sort '(1 a) #'<)"),
+            Err(Unwind::from("< - A is not a number"))
+        );
+    }
+}
@@ -8,13 +8,22 @@ pub fn pure_lisp_interpreter_message() {
 
 fn paren_balance(command: &str) -> i16 {
     let mut bal: i16 = 0;
+    let mut in_string: bool = false;
+    let mut escaped: bool = false;
+    let mut previous: char = ' ';
 
     for c in command.chars() {
         match c {
+            // parens inside a string literal or after #\ don't count
+            _ if escaped => escaped = false,
+            '\\' if in_string || previous == '#' => escaped = true,
+            '"' => in_string = !in_string,
+            _ if in_string => {}
             '(' => bal += 1,
             ')' => bal -= 1,
             _ => {}
         }
+        previous = c;
 
         if bal < 0 {
             // can never have a ) before a (
@@ -24,7 +33,7 @@ fn paren_balance(command: &str) -> i16 {
 
     bal
 }
-fn rparen_is_last(input: &String) -> bool {
+fn rparen_is_last(input: &str) -> bool {
     // if there are inputs before the first (, only the first argument will be evaluated
     if input.starts_with('(') {
         match input.chars().last() {
@@ -35,13 +44,18 @@ fn rparen_is_last(input: &String) -> bool {
     true
 }
 
-fn get_command_new_line() -> String {
+// returned by get_command once stdin is closed
+pub const END_OF_INPUT: &str = "end of input";
+
+fn get_command_new_line(prompt: &str) -> Result<String, String> {
     let mut line = String::new();
-    print!("> ");
+    print!("{}", prompt);
     let _ = stdout().flush();
-    stdin().read_line(&mut line).expect("unable to read");
+    if stdin().read_line(&mut line).expect("unable to read") == 0 {
+        return Err(String::from(END_OF_INPUT));
+    }
 
-    line
+    Ok(line)
 }
 fn get_command_line() -> String {
     let mut line = String::new();
@@ -50,9 +64,12 @@ fn get_command_line() -> String {
     line
 }
 pub fn get_command() -> Result<String, String> {
+    get_command_prompt("> ")
+}
+pub fn get_command_prompt(prompt: &str) -> Result<String, String> {
     let mut input = String::new();
 
-    input += &get_command_new_line();
+    input += &get_command_new_line(prompt)?;
     let _ = input.pop();
     while paren_balance(&input) > 0 {
         input += &get_command_line();
@@ -90,6 +107,9 @@ mod tests {
 
         command = String::from("(hello world)");
         assert_eq!(paren_balance(&command), 0);
+
+        command = String::from("(print \"(\\\"(\")");
+        assert_eq!(paren_balance(&command), 0);
     }
 
     #[test]
@@ -0,0 +1,207 @@
+use crate::eval::*;
+use crate::parse::*;
+use crate::shell::*;
+use crate::streams::*;
+
+use std::cell::Cell;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum StepMode {
+    Off,
+    On,
+}
+
+#[derive(Debug, PartialEq)]
+enum StepCommand {
+    In,
+    Over,
+    Continue,
+    Abort,
+    Help,
+    Unknown,
+}
+
+// reads a command after printing the prompt
+type StepInput = fn(&str) -> Result<String, String>;
+
+thread_local! {
+    static MODE: Cell<StepMode> = const { Cell::new(StepMode::Off) };
+    // how deep the stepper is in the form given to STEP, for the indentation
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    // where the commands come from, stdin unless a test replaces it
+    static INPUT: Cell<StepInput> = const { Cell::new(get_command_prompt) };
+}
+
+pub fn stepping() -> bool {
+    MODE.with(|m| m.get()) == StepMode::On
+}
+
+fn parse_step_command(line: &str) -> StepCommand {
+    match line.trim().to_ascii_uppercase().as_str() {
+        // just pressing enter steps in, like most steppers
+        "" | "S" | "STEP" => StepCommand::In,
+        "N" | "NEXT" | "OVER" => StepCommand::Over,
+        "C" | "CONTINUE" => StepCommand::Continue,
+        "A" | "Q" | "ABORT" => StepCommand::Abort,
+        "H" | "?" | "HELP" => StepCommand::Help,
+        _ => StepCommand::Unknown,
+    }
+}
+
+fn read_step_command() -> StepCommand {
+    let input = INPUT.with(|i| i.get());
+    loop {
+        match input("step> ").map(|l| parse_step_command(&l)) {
+            Ok(StepCommand::Help) => {
+                println!("s or enter  evaluate the form one sub-form at a time");
+                println!("n           evaluate the form without stepping into it");
+                println!("c           stop stepping and finish the evaluation");
+                println!("a           abort the evaluation");
+            }
+            Ok(StepCommand::Unknown) => println!("unknown command, h for help"),
+            Ok(c) => return c,
+            // nobody is there to answer, so just finish
+            Err(_) => return StepCommand::Continue,
+        }
+    }
+}
+
+// numbers, strings, keywords, T and NIL are not worth stopping at
+fn self_evaluating(v: &Sexpr) -> bool {
+    match v {
+        Sexpr::Symbol(s) => s.starts_with(':'),
+        Sexpr::List(l) => l.is_empty() || l.first() == Some(&Sexpr::Nil),
+        _ => true,
+    }
+}
+
+// called by evaluate in place of itself while STEP is running
+pub fn step_evaluate(v: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    if self_evaluating(&v) {
+        return eval_form(v, alist);
+    }
+
+    let depth: usize = DEPTH.with(|d| d.get());
+    let indent: String = "  ".repeat(depth);
+    write_standard_output(
+        "step",
+        &format!("{}{}: {}\n", indent, depth, sexpr_to_string(&v)),
+        alist,
+    )?;
+
+    let result: Result<Sexpr, Unwind> = match read_step_command() {
+        StepCommand::Over => {
+            MODE.with(|m| m.set(StepMode::Off));
+            let result = eval_form(v, alist);
+            MODE.with(|m| m.set(StepMode::On));
+            result
+        }
+        StepCommand::Continue => {
+            MODE.with(|m| m.set(StepMode::Off));
+            return eval_form(v, alist);
+        }
+        StepCommand::Abort => {
+            MODE.with(|m| m.set(StepMode::Off));
+            return Err(Unwind::Abort);
+        }
+        _ => {
+            DEPTH.with(|d| d.set(depth + 1));
+            let result = eval_form(v, alist);
+            DEPTH.with(|d| d.set(depth));
+            result
+        }
+    };
+
+    // continue inside a sub-form turns off the values of the outer ones too
+    if stepping() {
+        let shown: String = match &result {
+            Ok(value) => format!("{}=> {}\n", indent, sexpr_to_string(value)),
+            Err(e) => format!("{}unwound: {}\n", indent, e),
+        };
+        write_standard_output("step", &shown, alist)?;
+    }
+    result
+}
+
+// (step form) evaluates form one evaluate call at a time
+pub fn step(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let form: Sexpr = match &args {
+        Sexpr::List(l) => match list_items(l) {
+            [form] => form.clone(),
+            _ => return Err(Unwind::from("step - takes exactly one form")),
+        },
+        _ => return Err(Unwind::from("step - args must be a list")),
+    };
+
+    // a step inside a step just keeps going with the outer one
+    if stepping() {
+        return evaluate(form, alist);
+    }
+
+    MODE.with(|m| m.set(StepMode::On));
+    DEPTH.with(|d| d.set(0));
+    let result = evaluate(form, alist);
+    MODE.with(|m| m.set(StepMode::Off));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+
+    fn always_in(_: &str) -> Result<String, String> {
+        Ok(String::from("s"))
+    }
+    fn always_over(_: &str) -> Result<String, String> {
+        Ok(String::from("n"))
+    }
+    fn always_abort(_: &str) -> Result<String, String> {
+        Ok(String::from("a"))
+    }
+    fn end_of_input(_: &str) -> Result<String, String> {
+        Err(String::from(END_OF_INPUT))
+    }
+
+    #[test]
+    fn test_parse_step_command() {
+        assert_eq!(parse_step_command(""), StepCommand::In);
+        assert_eq!(parse_step_command(" n "), StepCommand::Over);
+        assert_eq!(parse_step_command("C"), StepCommand::Continue);
+        assert_eq!(parse_step_command("abort"), StepCommand::Abort);
+        assert_eq!(parse_step_command("x"), StepCommand::Unknown);
+    }
+
+    #[test]
+    fn test_step() {
+        run("(defun step-test (n) (cond ((equal n 0) 0) (t (+ n (step-test (- n 1))))))").unwrap();
+
+        for input in [always_in, always_over, end_of_input] {
+            INPUT.with(|i| i.set(input));
+            assert_eq!(run("(step (step-test 3))"), Ok(Sexpr::Integer(6)));
+            assert!(!stepping());
+        }
+
+        // what step shows goes wherever *standard-output* does
+        INPUT.with(|i| i.set(always_in));
+        assert_eq!(
+            run("(with-output-to-string (*standard-output*) (step (+ 1 2)))"),
+            Ok(Sexpr::String(String::from("0: (+ 1 2)\n=> 3\n")))
+        );
+
+        INPUT.with(|i| i.set(always_abort));
+        assert_eq!(run("(step (step-test 3))"), Err(Unwind::Abort));
+        assert!(!stepping());
+
+        // handlers in the stepped code can't take an abort
+        assert_eq!(
+            run("(step (ignore-errors (step-test 3)))"),
+            Err(Unwind::Abort)
+        );
+        assert_eq!(
+            run("(step (handler-case (step-test 3) (error () 'caught)))"),
+            Err(Unwind::Abort)
+        );
+        assert!(!stepping());
+    }
+}
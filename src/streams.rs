@@ -0,0 +1,755 @@
+use crate::eval::*;
+use crate::lisp::*;
+use crate::parse::*;
+
+use crate::condition::*;
+use crate::lexer::*;
+
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, VecDeque};
+use std::io::{stderr, stdin, stdout, BufRead, Cursor, Write};
+use std::sync::Mutex;
+
+pub const STANDARD_OUTPUT: usize = 0;
+pub const ERROR_OUTPUT: usize = 1;
+pub const STANDARD_INPUT: usize = 2;
+
+pub enum Source {
+    // shares the buffer of stdin with the REPL, so nothing it reads ahead is lost
+    Stdin,
+    Reader(Box<dyn BufRead + Send>),
+}
+
+// reads a line at a time from its source and hands out chars from it
+pub struct Input {
+    source: Source,
+    pending: VecDeque<char>,
+}
+
+impl Input {
+    pub fn new(source: Source) -> Self {
+        Input {
+            source,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn from_string(s: &str) -> Self {
+        Input::new(Source::Reader(Box::new(Cursor::new(s.as_bytes().to_vec()))))
+    }
+
+    // false at the end of the input
+    fn fill(&mut self) -> Result<bool, String> {
+        if !self.pending.is_empty() {
+            return Ok(true);
+        }
+        let mut line = String::new();
+        let read = match &mut self.source {
+            Source::Stdin => stdin().read_line(&mut line),
+            Source::Reader(r) => r.read_line(&mut line),
+        };
+        match read {
+            Ok(0) => Ok(false),
+            Ok(_) => {
+                self.pending.extend(line.chars());
+                Ok(true)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub fn peek_char(&mut self) -> Result<Option<char>, String> {
+        self.fill()?;
+        Ok(self.pending.front().copied())
+    }
+
+    pub fn read_char(&mut self) -> Result<Option<char>, String> {
+        self.fill()?;
+        Ok(self.pending.pop_front())
+    }
+
+    // the next line without its newline, None at the end of the input
+    pub fn read_line(&mut self) -> Result<Option<String>, String> {
+        if !self.fill()? {
+            return Ok(None);
+        }
+        let mut line = String::new();
+        while let Some(c) = self.read_char()? {
+            if c == '\n' {
+                break;
+            }
+            line.push(c);
+        }
+        Ok(Some(line))
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), String> {
+        while let Some(c) = self.peek_char()? {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.read_char()?;
+        }
+        Ok(())
+    }
+
+    // the text of the next form, which is what tokenize takes, None if only
+    // whitespace is left
+    pub fn read_form_text(&mut self) -> Result<Option<String>, String> {
+        let mut text = String::new();
+        // quotes belong to the form after them
+        loop {
+            self.skip_whitespace()?;
+            match self.peek_char()? {
+                Some('\'') => {
+                    self.read_char()?;
+                    text.push('\'');
+                }
+                Some('#') if self.pending.get(1) == Some(&'\'') => {
+                    self.read_char()?;
+                    self.read_char()?;
+                    text.push_str("#'");
+                }
+                _ => break,
+            }
+        }
+
+        match self.peek_char()? {
+            None if text.is_empty() => return Ok(None),
+            None => return Err(String::from("end of input inside a form")),
+            Some(')') => return Err(String::from("too many closing parens")),
+            _ => {}
+        }
+
+        let mut depth: usize = 0;
+        let mut in_string: bool = false;
+        while let Some(c) = self.peek_char()? {
+            // an atom ends where whitespace or a paren starts
+            let at_atom_end: bool = c.is_whitespace() || c == '(' || c == ')';
+            if depth == 0 && !in_string && !text.is_empty() && !text.ends_with('\'') && at_atom_end
+            {
+                return Ok(Some(text));
+            }
+            self.read_char()?;
+            text.push(c);
+            match c {
+                // the char after a \ in a string or after #\ is taken as is
+                '\\' if in_string || text.ends_with("#\\") => {
+                    if let Some(escaped) = self.read_char()? {
+                        text.push(escaped);
+                    }
+                }
+                '"' => in_string = !in_string,
+                '(' if !in_string => depth += 1,
+                ')' if !in_string => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(Some(text));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if depth > 0 || in_string {
+            Err(String::from("end of input inside a form"))
+        } else {
+            Ok(Some(text))
+        }
+    }
+}
+
+pub enum Stream {
+    // anything the host can write to, stdout and stderr to begin with
+    Sink(Box<dyn Write + Send>),
+    // collects what is written for get-output-stream-string
+    StringOutput(String),
+    Input(Input),
+}
+
+// every open stream by id, a Sexpr::Stream names one of these
+pub static STREAMS: Lazy<Mutex<HashMap<usize, Stream>>> = Lazy::new(|| {
+    let mut streams: HashMap<usize, Stream> = HashMap::new();
+    streams.insert(STANDARD_OUTPUT, Stream::Sink(Box::new(stdout())));
+    streams.insert(ERROR_OUTPUT, Stream::Sink(Box::new(stderr())));
+    streams.insert(STANDARD_INPUT, Stream::Input(Input::new(Source::Stdin)));
+    Mutex::new(streams)
+});
+static NEXT_STREAM: Lazy<Mutex<usize>> = Lazy::new(|| Mutex::new(STANDARD_INPUT + 1));
+
+// the globals the OBLIST starts out with
+pub fn standard_streams() -> Vec<(String, Sexpr)> {
+    vec![
+        (
+            String::from("*STANDARD-OUTPUT*"),
+            Sexpr::Stream(STANDARD_OUTPUT),
+        ),
+        (String::from("*ERROR-OUTPUT*"), Sexpr::Stream(ERROR_OUTPUT)),
+        (
+            String::from("*STANDARD-INPUT*"),
+            Sexpr::Stream(STANDARD_INPUT),
+        ),
+    ]
+}
+
+// lets the host send *standard-output* or *error-output* anywhere, a GUI
+// buffer or a log file instead of the terminal
+pub fn set_output_sink(stream: usize, sink: Box<dyn Write + Send>) {
+    STREAMS.lock().unwrap().insert(stream, Stream::Sink(sink));
+}
+
+// the id of the new stream, a Sexpr::Stream with it is what lisp code sees
+pub fn open_stream(stream: Stream) -> usize {
+    let mut next = NEXT_STREAM.lock().unwrap();
+    let id: usize = *next;
+    *next += 1;
+    STREAMS.lock().unwrap().insert(id, stream);
+    id
+}
+
+pub fn close_stream(id: usize) -> Option<Stream> {
+    STREAMS.lock().unwrap().remove(&id)
+}
+
+pub fn write_stream(name: &str, id: usize, s: &str) -> Result<(), Unwind> {
+    let mut streams = STREAMS.lock().unwrap();
+    match streams.get_mut(&id) {
+        Some(Stream::Sink(w)) => w
+            .write_all(s.as_bytes())
+            .and_then(|_| w.flush())
+            .map_err(|e| Unwind::from(format!("{} - can't write to the stream: {}", name, e))),
+        Some(Stream::StringOutput(buf)) => {
+            buf.push_str(s);
+            Ok(())
+        }
+        Some(Stream::Input(_)) => Err(Unwind::from(format!("{} - not an output stream", name))),
+        None => Err(Unwind::from(format!("{} - stream is closed", name))),
+    }
+}
+
+// writes to whatever *standard-output* is bound to, which is where trace,
+// step and profile show what they see
+pub fn write_standard_output(name: &str, s: &str, alist: &[(String, Sexpr)]) -> Result<(), Unwind> {
+    write_stream(name, output_stream(name, None, alist)?, s)
+}
+
+// runs f on the input stream id
+fn with_input<T>(
+    name: &str,
+    id: usize,
+    f: impl FnOnce(&mut Input) -> Result<T, String>,
+) -> Result<T, Unwind> {
+    let mut streams = STREAMS.lock().unwrap();
+    match streams.get_mut(&id) {
+        Some(Stream::Input(input)) => {
+            f(input).map_err(|e| Unwind::from(format!("{} - {}", name, e)))
+        }
+        Some(_) => Err(Unwind::from(format!("{} - not an input stream", name))),
+        None => Err(Unwind::from(format!("{} - stream is closed", name))),
+    }
+}
+
+// the stream an output function writes to, NIL or T mean *standard-output*
+fn output_stream(
+    name: &str,
+    stream: Option<&Sexpr>,
+    alist: &[(String, Sexpr)],
+) -> Result<usize, Unwind> {
+    let stream: Sexpr = match stream {
+        None | Some(Sexpr::Nil) | Some(Sexpr::T) => {
+            lookup(String::from("*STANDARD-OUTPUT*"), alist)
+        }
+        Some(s) => s.clone(),
+    };
+    match stream {
+        Sexpr::Stream(id) => Ok(id),
+        _ => Err(Unwind::from(format!("{} - not an output stream", name))),
+    }
+}
+
+// the radix *print-base* asks integers to be printed in
+pub fn print_base(name: &str, alist: &[(String, Sexpr)]) -> Result<u32, Unwind> {
+    match lookup(String::from("*PRINT-BASE*"), alist) {
+        Sexpr::Integer(base) if (2..=36).contains(&base) => Ok(base as u32),
+        base => Err(Unwind::from(format!(
+            "{} - *print-base* {} is not a radix from 2 to 36",
+            name,
+            sexpr_to_string(&base)
+        ))),
+    }
+}
+
+// what princ shows, strings without their quotes and escapes
+pub fn princ_to_string(v: &Sexpr, base: u32) -> String {
+    match v {
+        Sexpr::String(s) => s.clone(),
+        Sexpr::Char(c) => c.to_string(),
+        v => sexpr_to_string_radix(v, base),
+    }
+}
+
+// (name object [stream])
+fn object_and_stream(
+    name: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<(Sexpr, usize), Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    match args.as_slice() {
+        [object] => Ok((object.clone(), output_stream(name, None, alist)?)),
+        [object, stream] => Ok((object.clone(), output_stream(name, Some(stream), alist)?)),
+        _ => Err(Unwind::from(format!(
+            "{} - takes an object and an optional stream",
+            name
+        ))),
+    }
+}
+
+// a newline, the object as read would see it and a space
+pub fn print(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (object, stream) = object_and_stream("print", args, alist)?;
+    let base: u32 = print_base("print", alist)?;
+    write_stream(
+        "print",
+        stream,
+        &format!("\n{} ", sexpr_to_string_radix(&object, base)),
+    )?;
+    Ok(object)
+}
+
+pub fn prin1(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (object, stream) = object_and_stream("prin1", args, alist)?;
+    let base: u32 = print_base("prin1", alist)?;
+    write_stream("prin1", stream, &sexpr_to_string_radix(&object, base))?;
+    Ok(object)
+}
+
+pub fn princ(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (object, stream) = object_and_stream("princ", args, alist)?;
+    let base: u32 = print_base("princ", alist)?;
+    write_stream("princ", stream, &princ_to_string(&object, base))?;
+    Ok(object)
+}
+
+pub fn terpri(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let stream: usize = match args.as_slice() {
+        [] => output_stream("terpri", None, alist)?,
+        [stream] => output_stream("terpri", Some(stream), alist)?,
+        _ => return Err(Unwind::from("terpri - takes an optional stream")),
+    };
+    write_stream("terpri", stream, "\n")?;
+    Ok(Sexpr::Nil)
+}
+
+pub fn make_string_output_stream(
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Sexpr, Unwind> {
+    eval_args(args, alist)?;
+    Ok(Sexpr::Stream(open_stream(Stream::StringOutput(
+        String::new(),
+    ))))
+}
+
+// what was written to a string output stream since the last call, which empties it
+pub fn get_output_stream_string(
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Sexpr, Unwind> {
+    let id: usize = match eval_args(args, alist)?.as_slice() {
+        [Sexpr::Stream(id)] => *id,
+        _ => {
+            return Err(Unwind::from(
+                "get-output-stream-string - arg must be a stream",
+            ))
+        }
+    };
+    match STREAMS.lock().unwrap().get_mut(&id) {
+        Some(Stream::StringOutput(buf)) => Ok(Sexpr::String(std::mem::take(buf))),
+        _ => Err(Unwind::from(
+            "get-output-stream-string - not a string output stream",
+        )),
+    }
+}
+
+// (with-output-to-string (var) body...) binds var to a string output stream
+// while body runs and returns what was written to it, with var being
+// *standard-output* everything printed inside ends up in the string
+pub fn with_output_to_string(
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("with-output-to-string - args must be a list")),
+    };
+    let var: String = match args.first() {
+        Some(Sexpr::List(l)) => match list_items(l).first() {
+            Some(Sexpr::Symbol(s)) => s.clone(),
+            _ => return Err(Unwind::from("with-output-to-string - var must be a symbol")),
+        },
+        _ => {
+            return Err(Unwind::from(
+                "with-output-to-string - first arg must be (var)",
+            ))
+        }
+    };
+
+    let id: usize = open_stream(Stream::StringOutput(String::new()));
+    let depth = alist.len();
+    alist.push((var, Sexpr::Stream(id)));
+    let result = progn_forms(&args[1..], alist);
+    alist.truncate(depth);
+
+    match (result, close_stream(id)) {
+        (Ok(_), Some(Stream::StringOutput(buf))) => Ok(Sexpr::String(buf)),
+        (Ok(_), _) => Err(Unwind::from("with-output-to-string - stream was closed")),
+        (Err(e), _) => Err(e),
+    }
+}
+
+// the stream an input function reads from, NIL or T mean *standard-input*
+fn input_stream(
+    name: &str,
+    stream: Option<&Sexpr>,
+    alist: &[(String, Sexpr)],
+) -> Result<usize, Unwind> {
+    let stream: Sexpr = match stream {
+        None | Some(Sexpr::Nil) | Some(Sexpr::T) => lookup(String::from("*STANDARD-INPUT*"), alist),
+        Some(s) => s.clone(),
+    };
+    match stream {
+        Sexpr::Stream(id) => Ok(id),
+        _ => Err(Unwind::from(format!("{} - not an input stream", name))),
+    }
+}
+
+// [stream [eof-error-p [eof-value]]], eof-error-p is T unless given
+fn input_args(
+    name: &str,
+    args: &[Sexpr],
+    alist: &[(String, Sexpr)],
+) -> Result<(usize, bool, Sexpr), Unwind> {
+    if args.len() > 3 {
+        return Err(Unwind::from(format!(
+            "{} - takes a stream, eof-error-p and eof-value",
+            name
+        )));
+    }
+    let stream: usize = input_stream(name, args.first(), alist)?;
+    let eof_error: bool = args.get(1) != Some(&Sexpr::Nil);
+    let eof_value: Sexpr = args.get(2).cloned().unwrap_or(Sexpr::Nil);
+    Ok((stream, eof_error, eof_value))
+}
+
+fn end_of_file(
+    name: &str,
+    eof_error: bool,
+    eof_value: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Sexpr, Unwind> {
+    if eof_error {
+        let c: Sexpr = make_condition("END-OF-FILE", format!("{} - end of file", name));
+        Err(signal_error(c, alist))
+    } else {
+        Ok(eof_value)
+    }
+}
+
+fn read_form(
+    name: &str,
+    id: usize,
+    eof_error: bool,
+    eof_value: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Sexpr, Unwind> {
+    match with_input(name, id, |input| input.read_form_text())? {
+        Some(text) => {
+            let mut tokens: Vec<Token> =
+                tokenize(&text).map_err(|e| Unwind::from(format!("{} - {}", name, e)))?;
+            Ok(parse(&mut tokens))
+        }
+        None => end_of_file(name, eof_error, eof_value, alist),
+    }
+}
+
+// (read [stream eof-error-p eof-value]) parses the next form like the REPL does
+pub fn read(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let (id, eof_error, eof_value) = input_args("read", &args, alist)?;
+    read_form("read", id, eof_error, eof_value, alist)
+}
+
+pub fn read_line(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let (id, eof_error, eof_value) = input_args("read-line", &args, alist)?;
+    match with_input("read-line", id, |input| input.read_line())? {
+        Some(line) => Ok(Sexpr::String(line)),
+        None => end_of_file("read-line", eof_error, eof_value, alist),
+    }
+}
+
+pub fn read_char(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let (id, eof_error, eof_value) = input_args("read-char", &args, alist)?;
+    match with_input("read-char", id, |input| input.read_char())? {
+        Some(c) => Ok(Sexpr::Char(c)),
+        None => end_of_file("read-char", eof_error, eof_value, alist),
+    }
+}
+
+// (peek-char [peek-type stream eof-error-p eof-value]), a peek-type of T
+// skips whitespace first
+pub fn peek_char(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let skip_whitespace: bool = matches!(args.first(), Some(Sexpr::T));
+    let rest: &[Sexpr] = if args.is_empty() { &[] } else { &args[1..] };
+    let (id, eof_error, eof_value) = input_args("peek-char", rest, alist)?;
+
+    let peeked = with_input("peek-char", id, |input| {
+        if skip_whitespace {
+            input.skip_whitespace()?;
+        }
+        input.peek_char()
+    })?;
+    match peeked {
+        Some(c) => Ok(Sexpr::Char(c)),
+        None => end_of_file("peek-char", eof_error, eof_value, alist),
+    }
+}
+
+pub fn string_arg(name: &str, arg: Option<&Sexpr>) -> Result<String, Unwind> {
+    match arg {
+        Some(Sexpr::String(s)) => Ok(s.clone()),
+        _ => Err(Unwind::from(format!("{} - arg must be a string", name))),
+    }
+}
+
+// (read-from-string string [eof-error-p eof-value]) reads the first form in string
+pub fn read_from_string(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let text: String = string_arg("read-from-string", args.first())?;
+    let eof_error: bool = args.get(1) != Some(&Sexpr::Nil);
+    let eof_value: Sexpr = args.get(2).cloned().unwrap_or(Sexpr::Nil);
+
+    let id: usize = open_stream(Stream::Input(Input::from_string(&text)));
+    let result = read_form("read-from-string", id, eof_error, eof_value, alist);
+    close_stream(id);
+    result
+}
+
+pub fn make_string_input_stream(
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let text: String = string_arg("make-string-input-stream", args.first())?;
+    Ok(Sexpr::Stream(open_stream(Stream::Input(
+        Input::from_string(&text),
+    ))))
+}
+
+// (with-input-from-string (var string) body...) binds var to a stream reading string
+pub fn with_input_from_string(
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("with-input-from-string - args must be a list")),
+    };
+    let (var, string): (String, Sexpr) = match args.first() {
+        Some(Sexpr::List(l)) => match list_items(l) {
+            [Sexpr::Symbol(var), string] => (var.clone(), string.clone()),
+            _ => {
+                return Err(Unwind::from(
+                    "with-input-from-string - first arg must be (var string)",
+                ))
+            }
+        },
+        _ => {
+            return Err(Unwind::from(
+                "with-input-from-string - first arg must be (var string)",
+            ))
+        }
+    };
+    let text: String = string_arg("with-input-from-string", Some(&evaluate(string, alist)?))?;
+
+    let id: usize = open_stream(Stream::Input(Input::from_string(&text)));
+    let depth = alist.len();
+    alist.push((var, Sexpr::Stream(id)));
+    let result = progn_forms(&args[1..], alist);
+    alist.truncate(depth);
+    close_stream(id);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+    use std::sync::{Arc, Mutex};
+
+    fn string(s: &str) -> Sexpr {
+        Sexpr::String(String::from(s))
+    }
+
+    // a sink the test can still read after handing it to the streams
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_with_output_to_string() {
+        assert_eq!(
+            run("(with-output-to-string (*standard-output*) (prin1 'a) (princ \"b\") (prin1 \"c\") (terpri) (print 1))"),
+            Ok(string("Ab\"c\"\n\n1 "))
+        );
+        assert_eq!(
+            run("(with-output-to-string (s) (princ 42 s) (princ 'x))"),
+            Ok(string("42"))
+        );
+        // output from called functions goes to the rebound stream too
+        run("(defun streams-test-say (x) (princ x))").unwrap();
+        assert_eq!(
+            run("(with-output-to-string (*standard-output*) (streams-test-say 1) (streams-test-say 2))"),
+            Ok(string("12"))
+        );
+        assert_eq!(
+            run("(print 1 'nowhere)"),
+            Err(Unwind::from("print - not an output stream"))
+        );
+    }
+
+    #[test]
+    fn test_print_base() {
+        // bound as a parameter so other tests keep printing in decimal
+        assert_eq!(
+            run("(funcall (lambda (*print-base*) (with-output-to-string (s) (princ 255 s) (prin1 '(-10 1/2 1.5) s))) 16)"),
+            Ok(string("FF(-A 1/2 1.5)"))
+        );
+        assert_eq!(
+            run("(funcall (lambda (*print-base*) (with-output-to-string (s) (princ 100000000000000000000 s))) 2)"),
+            Ok(string("1010110101111000111010111100010110101100011000100000000000000000000"))
+        );
+        assert_eq!(
+            run("(funcall (lambda (*print-base*) (princ 1)) 37)"),
+            Err(Unwind::from(
+                "princ - *print-base* 37 is not a radix from 2 to 36"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_string_output_stream() {
+        assert_eq!(
+            run("(progn (setq streams-test-s (make-string-output-stream)) (princ 'ab streams-test-s) (get-output-stream-string streams-test-s))"),
+            Ok(string("AB"))
+        );
+        assert_eq!(
+            run("(get-output-stream-string streams-test-s)"),
+            Ok(string(""))
+        );
+    }
+
+    #[test]
+    fn test_read_form_text() {
+        let mut input = Input::from_string("  (a (b \"c)\"))\n'x #'car 12 \"s\"(d)");
+        assert_eq!(
+            input.read_form_text(),
+            Ok(Some(String::from("(a (b \"c)\"))")))
+        );
+        assert_eq!(input.read_form_text(), Ok(Some(String::from("'x"))));
+        assert_eq!(input.read_form_text(), Ok(Some(String::from("#'car"))));
+        assert_eq!(input.read_form_text(), Ok(Some(String::from("12"))));
+        assert_eq!(input.read_form_text(), Ok(Some(String::from("\"s\""))));
+        assert_eq!(input.read_form_text(), Ok(Some(String::from("(d)"))));
+        assert_eq!(input.read_form_text(), Ok(None));
+
+        let mut input = Input::from_string("(a\nb");
+        assert_eq!(
+            input.read_form_text(),
+            Err(String::from("end of input inside a form"))
+        );
+    }
+
+    #[test]
+    fn test_read() {
+        assert_eq!(
+            run("(read-from-string \"(+ 1 2)\")"),
+            Ok(Sexpr::List(vec![
+                Sexpr::Symbol(String::from("+")),
+                Sexpr::Integer(1),
+                Sexpr::Integer(2),
+                Sexpr::Nil
+            ]))
+        );
+        assert_eq!(
+            run("(eval (read-from-string \"(+ 1 2)\"))"),
+            Ok(Sexpr::Integer(3))
+        );
+        assert_eq!(
+            run("(read-from-string \"x\")"),
+            Ok(Sexpr::Symbol(String::from("X")))
+        );
+        assert_eq!(
+            run("(read-from-string \"\" nil 'done)"),
+            Ok(Sexpr::Symbol(String::from("DONE")))
+        );
+        assert_eq!(
+            run("(handler-case (read-from-string \"\") (end-of-file () 'eof))"),
+            Ok(Sexpr::Symbol(String::from("EOF")))
+        );
+        assert_eq!(
+            run("(with-input-from-string (s \"1 2\") (+ (read s) (read s)))"),
+            Ok(Sexpr::Integer(3))
+        );
+        assert_eq!(
+            run("(with-input-from-string (s \"1\") (read s) (read s nil 'eof))"),
+            Ok(Sexpr::Symbol(String::from("EOF")))
+        );
+    }
+
+    #[test]
+    fn test_read_line_and_char() {
+        run("(setq streams-test-in (make-string-input-stream \"ab\"))").unwrap();
+        assert_eq!(run("(peek-char nil streams-test-in)"), Ok(Sexpr::Char('a')));
+        assert_eq!(run("(read-char streams-test-in)"), Ok(Sexpr::Char('a')));
+        assert_eq!(run("(read-line streams-test-in)"), Ok(string("b")));
+        assert_eq!(run("(read-line streams-test-in nil)"), Ok(Sexpr::Nil));
+        assert_eq!(
+            run("(read-char streams-test-in nil 'eof)"),
+            Ok(Sexpr::Symbol(String::from("EOF")))
+        );
+
+        let mut input = Input::from_string("one\ntwo");
+        assert_eq!(input.read_line(), Ok(Some(String::from("one"))));
+        assert_eq!(input.read_line(), Ok(Some(String::from("two"))));
+        assert_eq!(input.read_line(), Ok(None));
+    }
+
+    #[test]
+    fn test_output_sink() {
+        let sink = Shared::default();
+        // a stream of its own, the standard ones are shared with other tests
+        let id: usize = open_stream(Stream::StringOutput(String::new()));
+        set_output_sink(id, Box::new(sink.clone()));
+        let mut alist: Vec<(String, Sexpr)> = vec![(String::from("OUT"), Sexpr::Stream(id))];
+        let mut tokens = crate::lexer::tokenize("(progn (princ 'hello out) (terpri out))").unwrap();
+        evaluate(parse(&mut tokens), &mut alist).unwrap();
+        assert_eq!(sink.0.lock().unwrap().as_slice(), b"HELLO\n");
+
+        assert!(close_stream(id).is_some());
+        assert!(write_stream("princ", id, "x").is_err());
+    }
+}
@@ -0,0 +1,577 @@
+use crate::condition::*;
+use crate::eval::*;
+use crate::lisp::*;
+use crate::numbers::*;
+use crate::parse::*;
+
+use num_bigint::BigInt;
+
+// strings count, index and slice by chars rather than bytes, so "λx" has a
+// length of 2 and (char "λx" 1) is #\x
+
+fn index_arg(name: &str, v: &Sexpr) -> Result<usize, Unwind> {
+    match v {
+        Sexpr::Integer(i) if *i >= 0 => Ok(*i as usize),
+        v => Err(Unwind::from(format!(
+            "{} - {} is not a valid index",
+            name,
+            sexpr_to_string(v)
+        ))),
+    }
+}
+
+// what a string designator names, symbols by their name and chars as a
+// string of one char
+fn designated_string(name: &str, v: &Sexpr) -> Result<String, Unwind> {
+    match v {
+        Sexpr::String(s) => Ok(s.clone()),
+        Sexpr::Char(c) => Ok(c.to_string()),
+        Sexpr::T | Sexpr::Nil | Sexpr::Symbol(_) => Ok(symbol_string(v)),
+        v => Err(Unwind::from(format!(
+            "{} - {} is not a string",
+            name,
+            sexpr_to_string(v)
+        ))),
+    }
+}
+
+// keywords are named without their colon
+fn symbol_string(v: &Sexpr) -> String {
+    match v {
+        Sexpr::Symbol(s) => s.strip_prefix(':').unwrap_or(s).to_string(),
+        v => sexpr_to_string(v),
+    }
+}
+
+// the items of a string or proper list, chars for a string
+pub fn sequence_items(name: &str, v: &Sexpr) -> Result<Vec<Sexpr>, Unwind> {
+    match v {
+        Sexpr::String(s) => Ok(s.chars().map(Sexpr::Char).collect()),
+        Sexpr::Nil => Ok(Vec::new()),
+        Sexpr::List(l) if l.last() == Some(&Sexpr::Nil) => Ok(list_items(l).to_vec()),
+        v => Err(Unwind::from(format!(
+            "{} - {} is not a string or proper list",
+            name,
+            sexpr_to_string(v)
+        ))),
+    }
+}
+
+pub fn chars_to_string(name: &str, items: &[Sexpr]) -> Result<String, Unwind> {
+    items
+        .iter()
+        .map(|c| match c {
+            Sexpr::Char(c) => Ok(*c),
+            x => Err(Unwind::from(format!(
+                "{} - {} is not a character",
+                name,
+                sexpr_to_string(x)
+            ))),
+        })
+        .collect()
+}
+
+pub fn length(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [Sexpr::String(s)] => Ok(Sexpr::Integer(s.chars().count() as i64)),
+        [seq] => Ok(Sexpr::Integer(sequence_items("length", seq)?.len() as i64)),
+        _ => Err(Unwind::from("length - takes exactly one arg")),
+    }
+}
+
+// (concatenate 'string "a" "b") or (concatenate 'list '(1) "ab")
+pub fn concatenate(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let mut items: Vec<Sexpr> = Vec::new();
+    for seq in args.iter().skip(1) {
+        items.extend(sequence_items("concatenate", seq)?);
+    }
+    match args.first() {
+        Some(Sexpr::Symbol(t)) if t == "STRING" => {
+            Ok(Sexpr::String(chars_to_string("concatenate", &items)?))
+        }
+        Some(Sexpr::Symbol(t)) if t == "LIST" => Ok(make_list(items)),
+        Some(t) => Err(Unwind::from(format!(
+            "concatenate - {} is not STRING or LIST",
+            sexpr_to_string(t)
+        ))),
+        None => Err(Unwind::from("concatenate - no result type arg")),
+    }
+}
+
+// (subseq seq start [end]), of the same type as seq
+pub fn subseq(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let (seq, start, end) = match args.as_slice() {
+        [seq, start] => (seq, start, None),
+        [seq, start, Sexpr::Nil] => (seq, start, None),
+        [seq, start, end] => (seq, start, Some(end)),
+        _ => {
+            return Err(Unwind::from(
+                "subseq - takes a sequence, start and an optional end",
+            ))
+        }
+    };
+    let items: Vec<Sexpr> = sequence_items("subseq", seq)?;
+    let start: usize = index_arg("subseq", start)?;
+    let end: usize = match end {
+        Some(end) => index_arg("subseq", end)?,
+        None => items.len(),
+    };
+    if start > end || end > items.len() {
+        return Err(Unwind::from(format!(
+            "subseq - {} to {} is out of range for {}",
+            start,
+            end,
+            sexpr_to_string(seq)
+        )));
+    }
+    let items: &[Sexpr] = &items[start..end];
+    match seq {
+        Sexpr::String(_) => Ok(Sexpr::String(chars_to_string("subseq", items)?)),
+        _ => Ok(make_list(items.to_vec())),
+    }
+}
+
+pub fn string_upcase(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [s] => Ok(Sexpr::String(
+            designated_string("string-upcase", s)?.to_uppercase(),
+        )),
+        _ => Err(Unwind::from("string-upcase - takes exactly one arg")),
+    }
+}
+
+pub fn string_downcase(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [s] => Ok(Sexpr::String(
+            designated_string("string-downcase", s)?.to_lowercase(),
+        )),
+        _ => Err(Unwind::from("string-downcase - takes exactly one arg")),
+    }
+}
+
+fn two_strings(
+    name: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<(Vec<char>, Vec<char>), Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [a, b] => Ok((
+            designated_string(name, a)?.chars().collect(),
+            designated_string(name, b)?.chars().collect(),
+        )),
+        _ => Err(Unwind::from(format!("{} - takes exactly two args", name))),
+    }
+}
+
+pub fn string_equals(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (a, b) = two_strings("string=", args, alist)?;
+    Ok(bool_sexpr(a == b))
+}
+
+// (string< a b) is the index where a first differs from b when a sorts
+// before it by char code, NIL otherwise
+pub fn string_less(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (a, b) = two_strings("string<", args, alist)?;
+    let mismatch: usize = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let less: bool = match (a.get(mismatch), b.get(mismatch)) {
+        (Some(x), Some(y)) => x < y,
+        (None, Some(_)) => true,
+        _ => false,
+    };
+    Ok(if less {
+        Sexpr::Integer(mismatch as i64)
+    } else {
+        Sexpr::Nil
+    })
+}
+
+// (search needle seq) is the index where needle first appears in seq, or NIL
+pub fn search(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (needle, seq) = match eval_args(args, alist)?.as_slice() {
+        [needle, seq] => (
+            sequence_items("search", needle)?,
+            sequence_items("search", seq)?,
+        ),
+        _ => return Err(Unwind::from("search - takes exactly two args")),
+    };
+    if needle.is_empty() {
+        return Ok(Sexpr::Integer(0));
+    }
+    Ok(match seq.windows(needle.len()).position(|w| w == needle) {
+        Some(i) => Sexpr::Integer(i as i64),
+        None => Sexpr::Nil,
+    })
+}
+
+// (string-trim bag string) drops the chars in bag, a string or a list of
+// chars, from both ends of string
+pub fn string_trim(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let (bag, s) = match eval_args(args, alist)?.as_slice() {
+        [bag, s] => (
+            chars_to_string("string-trim", &sequence_items("string-trim", bag)?)?,
+            designated_string("string-trim", s)?,
+        ),
+        _ => return Err(Unwind::from("string-trim - takes a char bag and a string")),
+    };
+    Ok(Sexpr::String(
+        s.trim_matches(|c| bag.contains(c)).to_string(),
+    ))
+}
+
+// (split-string string [separator]) splits at every separator, a char or a
+// string, or without one at runs of whitespace
+pub fn split_string(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let s: String = match args.first() {
+        Some(s) if args.len() <= 2 => designated_string("split-string", s)?,
+        _ => {
+            return Err(Unwind::from(
+                "split-string - takes a string and an optional separator",
+            ))
+        }
+    };
+    let parts: Vec<&str> = match args.get(1) {
+        None => s.split_whitespace().collect(),
+        Some(Sexpr::Char(c)) => s.split(*c).collect(),
+        Some(Sexpr::String(sep)) if !sep.is_empty() => s.split(sep.as_str()).collect(),
+        Some(sep) => {
+            return Err(Unwind::from(format!(
+                "split-string - {} is not a separator",
+                sexpr_to_string(sep)
+            )))
+        }
+    };
+    Ok(make_list(
+        parts
+            .into_iter()
+            .map(|p| Sexpr::String(String::from(p)))
+            .collect(),
+    ))
+}
+
+struct ParseIntegerArgs {
+    start: usize,
+    end: Option<usize>,
+    radix: u32,
+    junk_allowed: bool,
+}
+
+fn parse_integer_options(args: &[Sexpr]) -> Result<ParseIntegerArgs, Unwind> {
+    if !args.len().is_multiple_of(2) {
+        return Err(Unwind::from("parse-integer - odd number of keyword args"));
+    }
+    let mut options = ParseIntegerArgs {
+        start: 0,
+        end: None,
+        radix: 10,
+        junk_allowed: false,
+    };
+    for pair in args.chunks(2) {
+        match (&pair[0], &pair[1]) {
+            (Sexpr::Symbol(k), v) if k == ":START" => {
+                options.start = index_arg("parse-integer", v)?
+            }
+            (Sexpr::Symbol(k), Sexpr::Nil) if k == ":END" => options.end = None,
+            (Sexpr::Symbol(k), v) if k == ":END" => {
+                options.end = Some(index_arg("parse-integer", v)?)
+            }
+            (Sexpr::Symbol(k), Sexpr::Integer(r)) if k == ":RADIX" && (2..=36).contains(r) => {
+                options.radix = *r as u32
+            }
+            (Sexpr::Symbol(k), v) if k == ":JUNK-ALLOWED" => {
+                options.junk_allowed = v != &Sexpr::Nil
+            }
+            (k, v) => {
+                return Err(Unwind::from(format!(
+                    "parse-integer - bad option {} {}",
+                    sexpr_to_string(k),
+                    sexpr_to_string(v)
+                )))
+            }
+        }
+    }
+    Ok(options)
+}
+
+// (parse-integer string &key start end radix junk-allowed) is the integer
+// and, as a second value, the index where reading stopped; without
+// junk-allowed anything but whitespace around the digits is a PARSE-ERROR
+pub fn parse_integer(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = eval_args(args, alist)?;
+    let s: Vec<char> = match args.first() {
+        Some(Sexpr::String(s)) => s.chars().collect(),
+        _ => return Err(Unwind::from("parse-integer - first arg must be a string")),
+    };
+    let options: ParseIntegerArgs = parse_integer_options(&args[1..])?;
+    let end: usize = options.end.unwrap_or(s.len());
+    if options.start > end || end > s.len() {
+        return Err(Unwind::from(format!(
+            "parse-integer - {} to {} is out of range for {}",
+            options.start,
+            end,
+            sexpr_to_string(&args[0])
+        )));
+    }
+
+    let mut i: usize = options.start;
+    while i < end && s[i].is_whitespace() {
+        i += 1;
+    }
+    let negative: bool = i < end && s[i] == '-';
+    if i < end && (s[i] == '-' || s[i] == '+') {
+        i += 1;
+    }
+    let digits_start: usize = i;
+    while i < end && s[i].is_digit(options.radix) {
+        i += 1;
+    }
+    let digits: String = s[digits_start..i].iter().collect();
+    let digits_end: usize = i;
+    while i < end && s[i].is_whitespace() {
+        i += 1;
+    }
+
+    let text: String = s[options.start..end].iter().collect();
+    let value: Sexpr = if digits.is_empty() {
+        Sexpr::Nil
+    } else {
+        let n: BigInt = BigInt::parse_bytes(digits.as_bytes(), options.radix).unwrap();
+        normalize(if negative { -n } else { n })
+    };
+    if options.junk_allowed {
+        set_extra_values(vec![Sexpr::Integer(digits_end as i64)]);
+        return Ok(value);
+    }
+    if value == Sexpr::Nil || i < end {
+        let c: Sexpr = make_condition(
+            "PARSE-ERROR",
+            format!(
+                "parse-integer - {} is not an integer",
+                string_to_string(&text)
+            ),
+        );
+        return Err(signal_error(c, alist));
+    }
+    set_extra_values(vec![Sexpr::Integer(end as i64)]);
+    Ok(value)
+}
+
+// the symbol named by the string as it is, so (string-to-symbol "foo") is
+// not the symbol the reader makes of foo
+pub fn string_to_symbol(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [Sexpr::String(s)] => Ok(match s.as_str() {
+            "T" => Sexpr::T,
+            "NIL" => Sexpr::Nil,
+            _ => Sexpr::Symbol(s.clone()),
+        }),
+        [x] => Err(Unwind::from(format!(
+            "string-to-symbol - {} is not a string",
+            sexpr_to_string(x)
+        ))),
+        _ => Err(Unwind::from("string-to-symbol - takes exactly one arg")),
+    }
+}
+
+pub fn symbol_name(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [s @ (Sexpr::Symbol(_) | Sexpr::T | Sexpr::Nil)] => Ok(Sexpr::String(symbol_string(s))),
+        [x] => Err(Unwind::from(format!(
+            "symbol-name - {} is not a symbol",
+            sexpr_to_string(x)
+        ))),
+        _ => Err(Unwind::from("symbol-name - takes exactly one arg")),
+    }
+}
+
+// (char string index)
+pub fn char_index(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [Sexpr::String(s), i] => {
+            let i: usize = index_arg("char", i)?;
+            match s.chars().nth(i) {
+                Some(c) => Ok(Sexpr::Char(c)),
+                None => Err(Unwind::from(format!(
+                    "char - index {} is out of range for {}",
+                    i,
+                    string_to_string(s)
+                ))),
+            }
+        }
+        [x, _] => Err(Unwind::from(format!(
+            "char - {} is not a string",
+            sexpr_to_string(x)
+        ))),
+        _ => Err(Unwind::from("char - takes a string and an index")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+
+    fn string(s: &str) -> Sexpr {
+        Sexpr::String(String::from(s))
+    }
+
+    #[test]
+    fn test_string_syntax() {
+        assert_eq!(run("\"a \\\"b\\\" \\\\\""), Ok(string("a \"b\" \\")));
+        assert_eq!(
+            sexpr_to_string(&string("a \"b\" \\")),
+            "\"a \\\"b\\\" \\\\\""
+        );
+        assert_eq!(
+            run("(read-from-string \"\\\"a\\\\\\\"b\\\"\")"),
+            Ok(string("a\"b"))
+        );
+        assert_eq!(
+            run("(with-output-to-string (s) (prin1 \"x\\\"y\" s) (princ \"x\\\"y\" s))"),
+            Ok(string("\"x\\\"y\"x\"y"))
+        );
+    }
+
+    #[test]
+    fn test_length_and_char() {
+        assert_eq!(run("(length \"λx\")"), Ok(Sexpr::Integer(2)));
+        assert_eq!(run("(length \"\")"), Ok(Sexpr::Integer(0)));
+        assert_eq!(run("(char \"λx\" 1)"), Ok(Sexpr::Char('x')));
+        assert_eq!(run("(char \"λx\" 0)"), Ok(Sexpr::Char('λ')));
+        assert_eq!(
+            run("(char \"λx\" 2)"),
+            Err(Unwind::from("char - index 2 is out of range for \"λx\""))
+        );
+    }
+
+    #[test]
+    fn test_concatenate_and_subseq() {
+        assert_eq!(
+            run("(concatenate 'string \"ab\" \"λ\" '(#\\c))"),
+            Ok(string("abλc"))
+        );
+        assert_eq!(
+            run("(concatenate 'list '(1) \"a\")"),
+            Ok(Sexpr::List(vec![
+                Sexpr::Integer(1),
+                Sexpr::Char('a'),
+                Sexpr::Nil
+            ]))
+        );
+        assert_eq!(run("(concatenate 'string)"), Ok(string("")));
+        assert_eq!(run("(subseq \"héllo\" 1 3)"), Ok(string("él")));
+        assert_eq!(run("(subseq \"héllo\" 3)"), Ok(string("lo")));
+        assert_eq!(
+            run("(subseq '(1 2 3) 1)"),
+            Ok(Sexpr::List(vec![
+                Sexpr::Integer(2),
+                Sexpr::Integer(3),
+                Sexpr::Nil
+            ]))
+        );
+        assert_eq!(
+            run("(subseq \"abc\" 2 4)"),
+            Err(Unwind::from("subseq - 2 to 4 is out of range for \"abc\""))
+        );
+    }
+
+    #[test]
+    fn test_string_case_and_comparisons() {
+        assert_eq!(run("(string-upcase \"straße λ\")"), Ok(string("STRASSE Λ")));
+        assert_eq!(run("(string-downcase 'abc)"), Ok(string("abc")));
+        assert_eq!(run("(string= \"abc\" \"abc\")"), Ok(Sexpr::T));
+        assert_eq!(run("(string= \"abc\" \"ABC\")"), Ok(Sexpr::Nil));
+        assert_eq!(run("(string= 'abc \"ABC\")"), Ok(Sexpr::T));
+        assert_eq!(run("(string< \"abc\" \"abd\")"), Ok(Sexpr::Integer(2)));
+        assert_eq!(run("(string< \"ab\" \"abc\")"), Ok(Sexpr::Integer(2)));
+        assert_eq!(run("(string< \"abc\" \"abc\")"), Ok(Sexpr::Nil));
+        assert_eq!(run("(string< \"b\" \"a\")"), Ok(Sexpr::Nil));
+    }
+
+    #[test]
+    fn test_search_trim_split() {
+        assert_eq!(run("(search \"lo\" \"hello\")"), Ok(Sexpr::Integer(3)));
+        assert_eq!(run("(search \"x\" \"λx\")"), Ok(Sexpr::Integer(1)));
+        assert_eq!(run("(search \"z\" \"hello\")"), Ok(Sexpr::Nil));
+        assert_eq!(run("(search '(2 3) '(1 2 3))"), Ok(Sexpr::Integer(1)));
+        assert_eq!(run("(string-trim \" -\" \" -a b- \")"), Ok(string("a b")));
+        assert_eq!(run("(string-trim '(#\\λ) \"λxλ\")"), Ok(string("x")));
+        assert_eq!(
+            run("(split-string \"  a b\tc \")"),
+            Ok(Sexpr::List(vec![
+                string("a"),
+                string("b"),
+                string("c"),
+                Sexpr::Nil
+            ]))
+        );
+        assert_eq!(
+            run("(split-string \"a,,b\" #\\,)"),
+            Ok(Sexpr::List(vec![
+                string("a"),
+                string(""),
+                string("b"),
+                Sexpr::Nil
+            ]))
+        );
+        assert_eq!(
+            run("(split-string \"a::b\" \"::\")"),
+            Ok(Sexpr::List(vec![string("a"), string("b"), Sexpr::Nil]))
+        );
+    }
+
+    #[test]
+    fn test_parse_integer() {
+        assert_eq!(run("(parse-integer \" 42 \")"), Ok(Sexpr::Integer(42)));
+        assert_eq!(run("(parse-integer \"-17\")"), Ok(Sexpr::Integer(-17)));
+        assert_eq!(
+            run("(parse-integer \"ff\" :radix 16)"),
+            Ok(Sexpr::Integer(255))
+        );
+        assert_eq!(
+            run("(parse-integer \"x123y\" :start 1 :end 4)"),
+            Ok(Sexpr::Integer(123))
+        );
+        assert_eq!(
+            run("(multiple-value-list (parse-integer \"12abc\" :junk-allowed t))"),
+            Ok(Sexpr::List(vec![
+                Sexpr::Integer(12),
+                Sexpr::Integer(2),
+                Sexpr::Nil
+            ]))
+        );
+        assert_eq!(
+            run("(parse-integer \"abc\" :junk-allowed t)"),
+            Ok(Sexpr::Nil)
+        );
+        assert_eq!(
+            run("(handler-case (parse-integer \"12abc\") (parse-error () 'bad))"),
+            Ok(Sexpr::Symbol(String::from("BAD")))
+        );
+        assert_eq!(
+            run("(parse-integer \"123456789012345678901234567890\")"),
+            Ok(Sexpr::Bignum(
+                "123456789012345678901234567890".parse().unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_symbols_and_strings() {
+        assert_eq!(run("(symbol-name 'foo)"), Ok(string("FOO")));
+        assert_eq!(run("(symbol-name :key)"), Ok(string("KEY")));
+        assert_eq!(run("(symbol-name nil)"), Ok(string("NIL")));
+        assert_eq!(
+            run("(string-to-symbol \"FOO\")"),
+            Ok(Sexpr::Symbol(String::from("FOO")))
+        );
+        assert_eq!(
+            run("(equal (string-to-symbol \"foo\") 'foo)"),
+            Ok(Sexpr::Nil)
+        );
+        assert_eq!(run("(equal (string-to-symbol \"FOO\") 'foo)"), Ok(Sexpr::T));
+        assert_eq!(run("(string-to-symbol \"NIL\")"), Ok(Sexpr::Nil));
+    }
+}
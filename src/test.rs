@@ -1,6 +1,12 @@
 use crate::parse::*;
+#[cfg(test)]
+use crate::{eval::evaluate, eval::Unwind, lexer::tokenize};
 
-pub fn equal_sexpr_lists(l: &Vec<Sexpr>, r: &Vec<Sexpr>) -> bool {
+pub fn equal_sexpr_lists(l: &[Sexpr], r: &[Sexpr]) -> bool {
+    // (1 . 2) and (1 2) only differ in length, their last items are 2 and NIL
+    if l.len() != r.len() {
+        return false;
+    }
     let comp = l.iter().zip(r);
     for (a, b) in comp {
         if !equal_sexprs(a, b) {
@@ -11,13 +17,22 @@ pub fn equal_sexpr_lists(l: &Vec<Sexpr>, r: &Vec<Sexpr>) -> bool {
 }
 pub fn equal_sexprs(l: &Sexpr, r: &Sexpr) -> bool {
     match (l, r) {
-        (Sexpr::List(a), Sexpr::List(b)) => equal_sexpr_lists(&a, &b),
+        (Sexpr::List(a), Sexpr::List(b)) => equal_sexpr_lists(a, b),
         (a, b) => a == b,
     }
 }
 
+// reads and evaluates one form at the top level, for tests that would be
+// unreadable written out as nested Sexprs
+#[cfg(test)]
+pub fn run(input: &str) -> Result<Sexpr, Unwind> {
+    let mut tokens = tokenize(input)?;
+    let mut alist: Vec<(String, Sexpr)> = Vec::new();
+    evaluate(parse(&mut tokens), &mut alist)
+}
+
 #[cfg(test)]
-mod test {
+mod tests {
     use super::*;
 
     #[test]
@@ -0,0 +1,334 @@
+use crate::condition::*;
+use crate::eval::*;
+use crate::parse::*;
+
+// (type, parent) for the types of values, one for every kind of Sexpr and
+// the types grouping them; condition types are looked up in CONDITION_TYPES
+const TYPES: &[(&str, &str)] = &[
+    ("NUMBER", "T"),
+    ("REAL", "NUMBER"),
+    ("RATIONAL", "REAL"),
+    ("INTEGER", "RATIONAL"),
+    ("FIXNUM", "INTEGER"),
+    ("BIGNUM", "INTEGER"),
+    ("RATIO", "RATIONAL"),
+    ("FLOAT", "REAL"),
+    ("CHARACTER", "T"),
+    ("SEQUENCE", "T"),
+    ("STRING", "SEQUENCE"),
+    ("LIST", "SEQUENCE"),
+    ("CONS", "LIST"),
+    ("NULL", "LIST"),
+    ("SYMBOL", "T"),
+    ("KEYWORD", "SYMBOL"),
+    ("BOOLEAN", "SYMBOL"),
+    ("FUNCTION", "T"),
+    ("STREAM", "T"),
+    ("CONDITION", "T"),
+];
+
+// an empty list is NIL, even the one cdr leaves of a list of one item
+fn is_cons(v: &Sexpr) -> bool {
+    match v {
+        Sexpr::List(l) => !l.is_empty() && l.as_slice() != [Sexpr::Nil],
+        _ => false,
+    }
+}
+
+// the most specific type of v
+pub fn type_of_sexpr(v: &Sexpr) -> String {
+    let t: &str = match v {
+        Sexpr::Integer(_) => "FIXNUM",
+        Sexpr::Bignum(_) => "BIGNUM",
+        Sexpr::Ratio(_) => "RATIO",
+        Sexpr::Float(_) => "FLOAT",
+        Sexpr::Char(_) => "CHARACTER",
+        Sexpr::String(_) => "STRING",
+        Sexpr::Symbol(s) if s.starts_with(':') => "KEYWORD",
+        Sexpr::Symbol(_) => "SYMBOL",
+        Sexpr::List(_) if is_cons(v) => "CONS",
+        Sexpr::List(_) | Sexpr::Nil => "NULL",
+        Sexpr::T => "BOOLEAN",
+        Sexpr::Lambda(_, _) | Sexpr::Builtin(_) => "FUNCTION",
+        Sexpr::Stream(_) => "STREAM",
+        Sexpr::Condition(t, _) => return t.clone(),
+    };
+    String::from(t)
+}
+
+fn is_condition_type(t: &str) -> bool {
+    t == "CONDITION"
+        || CONDITION_TYPES
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(child, _)| child == t)
+}
+
+fn is_type(t: &str) -> bool {
+    matches!(t, "T" | "NIL" | "ATOM")
+        || TYPES.iter().any(|(child, _)| *child == t)
+        || is_condition_type(t)
+}
+
+// walks up from t through the parents in TYPES
+fn subtype(t: &str, target: &str) -> bool {
+    let mut t: &str = t;
+    loop {
+        if t == target {
+            return true;
+        }
+        match TYPES.iter().find(|(child, _)| *child == t) {
+            Some((_, parent)) => t = parent,
+            None => return false,
+        }
+    }
+}
+
+// NIL is a symbol and a boolean as well as the empty list, and ATOM is
+// anything but a cons
+pub fn is_of_type(v: &Sexpr, target: &str) -> bool {
+    match (v, target) {
+        (_, "T") => true,
+        (_, "NIL") => false,
+        (_, "ATOM") => !is_cons(v),
+        (Sexpr::Condition(t, _), _) if is_condition_type(target) => subtypep(t, target),
+        (Sexpr::Nil, _) => ["NULL", "BOOLEAN"].iter().any(|t| subtype(t, target)),
+        (Sexpr::List(_), _) if !is_cons(v) => {
+            ["NULL", "BOOLEAN"].iter().any(|t| subtype(t, target))
+        }
+        _ => subtype(&type_of_sexpr(v), target),
+    }
+}
+
+fn type_arg(name: &str, v: &Sexpr) -> Result<String, Unwind> {
+    let t: String = match v {
+        Sexpr::Symbol(s) => s.clone(),
+        Sexpr::T => String::from("T"),
+        Sexpr::Nil => String::from("NIL"),
+        v => {
+            return Err(Unwind::from(format!(
+                "{} - {} is not a type",
+                name,
+                sexpr_to_string(v)
+            )))
+        }
+    };
+    if !is_type(&t) {
+        return Err(Unwind::from(format!("{} - {} is not a type", name, t)));
+    }
+    Ok(t)
+}
+
+fn one_arg(name: &str, args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [arg] => Ok(arg.clone()),
+        _ => Err(Unwind::from(format!("{} - takes exactly one arg", name))),
+    }
+}
+
+fn predicate(
+    name: &str,
+    t: &str,
+    args: Sexpr,
+    alist: &mut Vec<(String, Sexpr)>,
+) -> Result<Sexpr, Unwind> {
+    Ok(bool_sexpr(is_of_type(&one_arg(name, args, alist)?, t)))
+}
+
+pub fn numberp(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    predicate("numberp", "NUMBER", args, alist)
+}
+
+pub fn integerp(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    predicate("integerp", "INTEGER", args, alist)
+}
+
+pub fn floatp(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    predicate("floatp", "FLOAT", args, alist)
+}
+
+pub fn stringp(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    predicate("stringp", "STRING", args, alist)
+}
+
+pub fn symbolp(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    predicate("symbolp", "SYMBOL", args, alist)
+}
+
+pub fn consp(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    predicate("consp", "CONS", args, alist)
+}
+
+pub fn functionp(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    predicate("functionp", "FUNCTION", args, alist)
+}
+
+pub fn characterp(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    predicate("characterp", "CHARACTER", args, alist)
+}
+
+pub fn type_of(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let v: Sexpr = one_arg("type-of", args, alist)?;
+    Ok(Sexpr::Symbol(type_of_sexpr(&v)))
+}
+
+// (typep value 'type)
+pub fn typep(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    match eval_args(args, alist)?.as_slice() {
+        [v, t] => Ok(bool_sexpr(is_of_type(v, &type_arg("typep", t)?))),
+        _ => Err(Unwind::from("typep - takes a value and a type")),
+    }
+}
+
+// (check-type place type [description]) signals a TYPE-ERROR unless the
+// value of place is of type, which isn't evaluated
+pub fn check_type(args: Sexpr, alist: &mut Vec<(String, Sexpr)>) -> Result<Sexpr, Unwind> {
+    let args: Vec<Sexpr> = match &args {
+        Sexpr::List(l) => list_items(l).to_vec(),
+        _ => return Err(Unwind::from("check-type - args must be a list")),
+    };
+    let (place, t) = match args.as_slice() {
+        [place, t] | [place, t, _] => (place.clone(), type_arg("check-type", t)?),
+        _ => {
+            return Err(Unwind::from(
+                "check-type - takes a place, a type and an optional description",
+            ))
+        }
+    };
+    let value: Sexpr = evaluate(place.clone(), alist)?;
+    if is_of_type(&value, &t) {
+        return Ok(Sexpr::Nil);
+    }
+    let expected: String = match args.get(2) {
+        Some(description) => princ_description(evaluate(description.clone(), alist)?),
+        None => format!("of type {}", t),
+    };
+    let c: Sexpr = make_condition(
+        "TYPE-ERROR",
+        format!(
+            "check-type - the value of {} is {}, which is not {}",
+            sexpr_to_string(&place),
+            sexpr_to_string(&value),
+            expected
+        ),
+    );
+    Err(signal_error(c, alist))
+}
+
+fn princ_description(v: Sexpr) -> String {
+    match v {
+        Sexpr::String(s) => s,
+        v => sexpr_to_string(&v),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::*;
+
+    fn symbol(s: &str) -> Sexpr {
+        Sexpr::Symbol(String::from(s))
+    }
+
+    #[test]
+    fn test_predicates() {
+        assert_eq!(run("(numberp 1/2)"), Ok(Sexpr::T));
+        assert_eq!(run("(numberp \"1\")"), Ok(Sexpr::Nil));
+        assert_eq!(run("(integerp 100000000000000000000)"), Ok(Sexpr::T));
+        assert_eq!(run("(integerp 1.0)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(floatp 1.0)"), Ok(Sexpr::T));
+        assert_eq!(run("(stringp \"a\")"), Ok(Sexpr::T));
+        assert_eq!(run("(stringp #\\a)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(characterp #\\a)"), Ok(Sexpr::T));
+        assert_eq!(run("(symbolp 'a)"), Ok(Sexpr::T));
+        assert_eq!(run("(symbolp nil)"), Ok(Sexpr::T));
+        assert_eq!(run("(symbolp :key)"), Ok(Sexpr::T));
+        assert_eq!(run("(symbolp \"a\")"), Ok(Sexpr::Nil));
+        assert_eq!(run("(consp '(1 . 2))"), Ok(Sexpr::T));
+        assert_eq!(run("(consp nil)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(consp (cdr '(1)))"), Ok(Sexpr::Nil));
+        assert_eq!(run("(functionp #'car)"), Ok(Sexpr::T));
+        assert_eq!(run("(functionp (lambda (x) x))"), Ok(Sexpr::T));
+        assert_eq!(run("(functionp 'car)"), Ok(Sexpr::Nil));
+    }
+
+    #[test]
+    fn test_type_of() {
+        assert_eq!(run("(type-of 1)"), Ok(symbol("FIXNUM")));
+        assert_eq!(run("(type-of 100000000000000000000)"), Ok(symbol("BIGNUM")));
+        assert_eq!(run("(type-of 1/2)"), Ok(symbol("RATIO")));
+        assert_eq!(run("(type-of 1.5)"), Ok(symbol("FLOAT")));
+        assert_eq!(run("(type-of #\\a)"), Ok(symbol("CHARACTER")));
+        assert_eq!(run("(type-of \"a\")"), Ok(symbol("STRING")));
+        assert_eq!(run("(type-of 'a)"), Ok(symbol("SYMBOL")));
+        assert_eq!(run("(type-of :a)"), Ok(symbol("KEYWORD")));
+        assert_eq!(run("(type-of '(1))"), Ok(symbol("CONS")));
+        assert_eq!(run("(type-of nil)"), Ok(symbol("NULL")));
+        assert_eq!(run("(type-of t)"), Ok(symbol("BOOLEAN")));
+        assert_eq!(run("(type-of #'+)"), Ok(symbol("FUNCTION")));
+        assert_eq!(run("(type-of *standard-output*)"), Ok(symbol("STREAM")));
+        assert_eq!(
+            run("(type-of (make-condition 'division-by-zero))"),
+            Ok(symbol("DIVISION-BY-ZERO"))
+        );
+    }
+
+    #[test]
+    fn test_typep() {
+        assert_eq!(run("(typep 1 'integer)"), Ok(Sexpr::T));
+        assert_eq!(run("(typep 1 'rational)"), Ok(Sexpr::T));
+        assert_eq!(run("(typep 1/2 'integer)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(typep 1.5 'real)"), Ok(Sexpr::T));
+        assert_eq!(run("(typep \"a\" 'sequence)"), Ok(Sexpr::T));
+        assert_eq!(run("(typep nil 'list)"), Ok(Sexpr::T));
+        assert_eq!(run("(typep nil 'symbol)"), Ok(Sexpr::T));
+        assert_eq!(run("(typep nil 'boolean)"), Ok(Sexpr::T));
+        assert_eq!(run("(typep nil 'cons)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(typep '(1) 'atom)"), Ok(Sexpr::Nil));
+        assert_eq!(run("(typep 1 'atom)"), Ok(Sexpr::T));
+        assert_eq!(run("(typep 1 t)"), Ok(Sexpr::T));
+        assert_eq!(run("(typep 1 nil)"), Ok(Sexpr::Nil));
+        assert_eq!(
+            run("(typep (make-condition 'division-by-zero) 'arithmetic-error)"),
+            Ok(Sexpr::T)
+        );
+        assert_eq!(
+            run("(typep (make-condition 'division-by-zero) 'number)"),
+            Ok(Sexpr::Nil)
+        );
+        assert_eq!(
+            run("(typep 1 'widget)"),
+            Err(Unwind::from("typep - WIDGET is not a type"))
+        );
+    }
+
+    #[test]
+    fn test_check_type() {
+        assert_eq!(
+            run("(funcall (lambda (x) (check-type x integer) x) 3)"),
+            Ok(Sexpr::Integer(3))
+        );
+        assert_eq!(
+            run("(funcall (lambda (x) (handler-case (check-type x string) (type-error () 'bad))) 3)"),
+            Ok(symbol("BAD"))
+        );
+        assert_eq!(
+            run("(funcall (lambda (x) (handler-case (check-type x string) (error (c) (condition-message c)))) 3)"),
+            Ok(Sexpr::String(String::from(
+                "check-type - the value of X is 3, which is not of type STRING"
+            )))
+        );
+        assert_eq!(
+            run("(funcall (lambda (x) (handler-case (check-type x integer \"a count\") (error (c) (condition-message c)))) 1.5)"),
+            Ok(Sexpr::String(String::from(
+                "check-type - the value of X is 1.5, which is not a count"
+            )))
+        );
+        assert_eq!(
+            run("(check-type 1 widget)"),
+            Err(Unwind::from("check-type - WIDGET is not a type"))
+        );
+    }
+}